@@ -0,0 +1,443 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::Gpg,
+    gpg::{self, KeyInfo},
+    pgp::{self, KeyId},
+    recipients,
+    service::Progress,
+};
+
+/// Someone who can decrypt password files
+///
+/// Readers are identified by the fingerprint of their key,
+/// or by the bare key ID or recipient when the key is not in the keyring.
+#[derive(Debug, Clone)]
+pub struct Reader {
+    pub id: String,
+    pub name: String,
+    pub known: bool,
+}
+
+/// Who can decrypt a password file
+#[derive(Debug, Clone)]
+pub struct EntryAccess {
+    pub pass_id: String,
+    /// Folder of the `.gpg-id` file that governs the entry
+    pub governed_by: Option<String>,
+    /// Readers according to the packet header of the file
+    pub readers: BTreeSet<String>,
+    /// Readers that are not listed in the `.gpg-id` file
+    pub unexpected: BTreeSet<String>,
+    /// Readers of the `.gpg-id` file that cannot decrypt the file
+    pub missing: BTreeSet<String>,
+    pub error: Option<String>,
+}
+
+impl EntryAccess {
+    /// Whether the file is not encrypted for the recipients of its `.gpg-id` file
+    pub fn is_mismatch(&self) -> bool {
+        !self.unexpected.is_empty() || !self.missing.is_empty()
+    }
+}
+
+/// Readers listed in the `.gpg-id` file of a folder
+#[derive(Debug, Clone)]
+pub struct FolderAccess {
+    pub folder: String,
+    pub readers: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AccessMatrix {
+    pub readers: BTreeMap<String, Reader>,
+    pub folders: Vec<FolderAccess>,
+    pub entries: Vec<EntryAccess>,
+}
+
+impl AccessMatrix {
+    /// Folders whose `.gpg-id` file lists the reader
+    pub fn folders_of<'a>(&'a self, reader: &'a str) -> impl Iterator<Item = &'a FolderAccess> {
+        self.folders
+            .iter()
+            .filter(move |folder| folder.readers.contains(reader))
+    }
+
+    /// Entries the reader can decrypt
+    pub fn entries_of<'a>(&'a self, reader: &'a str) -> impl Iterator<Item = &'a EntryAccess> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.readers.contains(reader))
+    }
+
+    /// Entries governed by the `.gpg-id` file of a folder
+    pub fn entries_in<'a>(&'a self, folder: &'a str) -> impl Iterator<Item = &'a EntryAccess> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.governed_by.as_deref() == Some(folder))
+    }
+
+    pub fn mismatches(&self) -> impl Iterator<Item = &EntryAccess> {
+        self.entries.iter().filter(|entry| entry.is_mismatch())
+    }
+
+    /// Returns the name of a reader
+    pub fn name<'a>(&'a self, reader: &'a str) -> &'a str {
+        self.readers
+            .get(reader)
+            .map_or(reader, |reader| reader.name.as_str())
+    }
+}
+
+/// Progress of a scan of the store
+#[derive(Debug, Clone)]
+pub enum Scan {
+    Progress { done: usize, total: usize },
+    Finished(Result<AccessMatrix, String>),
+}
+
+/// Password file as found on disk
+struct File {
+    pass_id: String,
+    gpg_id: Option<PathBuf>,
+    key_ids: Result<Vec<KeyId>, String>,
+}
+
+/// Reads the `.gpg-id` files and packet headers of all password files in the store
+pub fn scan(store_dir: &Path, progress: &Progress<Scan>) {
+    let paths = recipients::password_files(&Gpg, store_dir);
+    let total = paths.len();
+    let mut files = Vec::with_capacity(total);
+    let mut gpg_ids: HashMap<PathBuf, Vec<String>> = HashMap::new();
+
+    for (done, path) in paths.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            return;
+        }
+        let relative = path.strip_prefix(store_dir).unwrap_or(&path);
+        let folder = relative
+            .parent()
+            .map(|parent| parent.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let gpg_id = recipients::governing_file(&Gpg, store_dir, &folder);
+        if let Some(gpg_id) = &gpg_id {
+            if !gpg_ids.contains_key(gpg_id) {
+                let recipients = recipients::read(gpg_id).unwrap_or_default();
+                gpg_ids.insert(gpg_id.clone(), recipients);
+            }
+        }
+        files.push(File {
+            pass_id: relative.with_extension("").to_string_lossy().into(),
+            gpg_id,
+            key_ids: pgp::recipients(&path).map_err(|e| e.to_string()),
+        });
+        progress.report(Scan::Progress {
+            done: done + 1,
+            total,
+        });
+    }
+
+    let mut lookups: BTreeSet<String> = gpg_ids.values().flatten().cloned().collect();
+    for file in &files {
+        if let Ok(key_ids) = &file.key_ids {
+            lookups.extend(
+                key_ids
+                    .iter()
+                    .filter(|key_id| !key_id.is_wildcard())
+                    .map(|key_id| key_id.to_string()),
+            );
+        }
+    }
+    let keys = match gpg::resolve_keys(&lookups.into_iter().collect::<Vec<_>>()) {
+        Ok(keys) => keys,
+        Err(e) => {
+            progress.report(Scan::Finished(Err(e.to_string())));
+            return;
+        }
+    };
+
+    let matrix = build(store_dir, files, &gpg_ids, &keys);
+    progress.report(Scan::Finished(Ok(matrix)));
+}
+
+fn build(
+    store_dir: &Path,
+    files: Vec<File>,
+    gpg_ids: &HashMap<PathBuf, Vec<String>>,
+    keys: &[KeyInfo],
+) -> AccessMatrix {
+    let mut matrix = AccessMatrix::default();
+
+    let folder_of = |gpg_id: &Path| -> String {
+        gpg_id
+            .parent()
+            .and_then(|dir| dir.strip_prefix(store_dir).ok())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+
+    let mut folder_readers: HashMap<&PathBuf, BTreeSet<String>> = HashMap::new();
+    for (gpg_id, recipients) in gpg_ids {
+        let readers = recipients
+            .iter()
+            .map(|recipient| {
+                let reader = recipient_reader(recipient, keys);
+                let id = reader.id.clone();
+                matrix.readers.entry(id.clone()).or_insert(reader);
+                id
+            })
+            .collect();
+        folder_readers.insert(gpg_id, readers);
+    }
+    matrix.folders = folder_readers
+        .iter()
+        .map(|(gpg_id, readers)| FolderAccess {
+            folder: folder_of(gpg_id),
+            readers: readers.clone(),
+        })
+        .collect();
+    matrix.folders.sort_by(|a, b| a.folder.cmp(&b.folder));
+
+    for file in files {
+        let expected = file
+            .gpg_id
+            .as_ref()
+            .and_then(|gpg_id| folder_readers.get(gpg_id))
+            .cloned()
+            .unwrap_or_default();
+        let (readers, error) = match file.key_ids {
+            Ok(key_ids) => {
+                let readers = key_ids
+                    .iter()
+                    .map(|key_id| {
+                        let reader = key_id_reader(key_id, keys);
+                        let id = reader.id.clone();
+                        matrix.readers.entry(id.clone()).or_insert(reader);
+                        id
+                    })
+                    .collect();
+                (readers, None)
+            }
+            Err(e) => (BTreeSet::new(), Some(e)),
+        };
+        let (unexpected, missing) = if error.is_none() {
+            (
+                readers.difference(&expected).cloned().collect(),
+                expected.difference(&readers).cloned().collect(),
+            )
+        } else {
+            (BTreeSet::new(), BTreeSet::new())
+        };
+        matrix.entries.push(EntryAccess {
+            pass_id: file.pass_id,
+            governed_by: file.gpg_id.as_deref().map(folder_of),
+            readers,
+            unexpected,
+            missing,
+            error,
+        });
+    }
+    matrix
+}
+
+fn key_reader(key: &KeyInfo) -> Reader {
+    Reader {
+        id: key.fingerprint.clone(),
+        name: key
+            .user_ids
+            .first()
+            .cloned()
+            .unwrap_or_else(|| key.fingerprint.clone()),
+        known: true,
+    }
+}
+
+fn recipient_reader(recipient: &str, keys: &[KeyInfo]) -> Reader {
+    match keys.iter().find(|key| key.recipient == recipient) {
+        Some(key) => key_reader(key),
+        None => Reader {
+            id: recipient.to_owned(),
+            name: recipient.to_owned(),
+            known: false,
+        },
+    }
+}
+
+fn key_id_reader(key_id: &KeyId, keys: &[KeyInfo]) -> Reader {
+    if key_id.is_wildcard() {
+        return Reader {
+            id: key_id.to_string(),
+            name: "Hidden recipient".to_owned(),
+            known: false,
+        };
+    }
+    let key = keys.iter().find(|key| {
+        key.key_ids
+            .iter()
+            .any(|id| id.eq_ignore_ascii_case(key_id.as_str()))
+    });
+    match key {
+        Some(key) => key_reader(key),
+        None => Reader {
+            id: key_id.to_string(),
+            name: format!("Unknown key {key_id}"),
+            known: false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypted by GnuPG for the Curve25519 subkey of Alice and the RSA subkey of Bob
+    const TWO_RECIPIENTS: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/two-recipients.gpg"
+    );
+    const HIDDEN_RECIPIENT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/hidden-recipient.gpg"
+    );
+    const ALICE: &str = "6B8E3F0E47C4E0A2D2D3A1F9A7C0D1E2F3A4B5C6";
+    const BOB: &str = "0D1C2B3A495867768594A3B2C1D0E9F8A7B6C5D4";
+
+    fn store_dir() -> &'static Path {
+        Path::new("/store")
+    }
+
+    /// Key of the keyring with a primary key and the subkey the fixtures are encrypted for
+    fn key(recipient: &str, fingerprint: &str, subkey: &str) -> KeyInfo {
+        KeyInfo {
+            recipient: recipient.to_owned(),
+            fingerprint: fingerprint.to_owned(),
+            user_ids: vec![format!("<{recipient}>")],
+            key_ids: vec![fingerprint[24..].to_owned(), subkey.to_owned()],
+            problem: None,
+        }
+    }
+
+    fn alice() -> KeyInfo {
+        key("alice@example.com", ALICE, "C14F8EA815B65481")
+    }
+
+    fn bob() -> KeyInfo {
+        key("bob@example.com", BOB, "C544ADE7E4B6A858")
+    }
+
+    fn file(pass_id: &str, folder: &str, packets: &str) -> File {
+        File {
+            pass_id: pass_id.to_owned(),
+            gpg_id: Some(store_dir().join(folder).join(".gpg-id")),
+            key_ids: pgp::recipients(Path::new(packets)).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn gpg_ids(folders: &[(&str, &[&str])]) -> HashMap<PathBuf, Vec<String>> {
+        folders
+            .iter()
+            .map(|(folder, recipients)| {
+                (
+                    store_dir().join(folder).join(".gpg-id"),
+                    recipients
+                        .iter()
+                        .map(|recipient| recipient.to_string())
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn ids(ids: &[&str]) -> BTreeSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn subkeys_of_the_packets_are_mapped_to_their_primary_keys() {
+        let matrix = build(
+            store_dir(),
+            vec![
+                file("team/mail", "team", TWO_RECIPIENTS),
+                file("solo/bank", "solo", TWO_RECIPIENTS),
+            ],
+            &gpg_ids(&[
+                ("team", &["alice@example.com", "bob@example.com"]),
+                ("solo", &["alice@example.com"]),
+            ]),
+            &[alice(), bob()],
+        );
+
+        let [solo, team] = matrix.folders.as_slice() else {
+            panic!("both folders are listed: {:?}", matrix.folders);
+        };
+        assert_eq!(
+            (solo.folder.as_str(), &solo.readers),
+            ("solo", &ids(&[ALICE]))
+        );
+        assert_eq!(
+            (team.folder.as_str(), &team.readers),
+            ("team", &ids(&[ALICE, BOB]))
+        );
+        assert_eq!(matrix.name(BOB), "<bob@example.com>");
+        assert!(matrix.readers.values().all(|reader| reader.known));
+
+        let mail = &matrix.entries[0];
+        assert_eq!(mail.governed_by.as_deref(), Some("team"));
+        assert_eq!(mail.readers, ids(&[ALICE, BOB]));
+        assert!(!mail.is_mismatch());
+        // Bob can decrypt the bank although the folder is only meant for Alice
+        let mismatches: Vec<_> = matrix.mismatches().collect();
+        let [bank] = mismatches.as_slice() else {
+            panic!("only the bank is a mismatch: {mismatches:?}");
+        };
+        assert_eq!(bank.pass_id, "solo/bank");
+        assert_eq!(bank.unexpected, ids(&[BOB]));
+        assert!(bank.missing.is_empty());
+        assert_eq!(matrix.entries_of(BOB).count(), 2);
+        assert_eq!(matrix.folders_of(BOB).count(), 1);
+    }
+
+    #[test]
+    fn unknown_keys_and_hidden_recipients_are_mismatches() {
+        let matrix = build(
+            store_dir(),
+            vec![
+                file("team/mail", "team", TWO_RECIPIENTS),
+                file("team/hidden", "team", HIDDEN_RECIPIENT),
+                File {
+                    pass_id: "team/broken".to_owned(),
+                    gpg_id: Some(store_dir().join("team/.gpg-id")),
+                    key_ids: Err("not an OpenPGP message".to_owned()),
+                },
+            ],
+            &gpg_ids(&[("team", &["alice@example.com", "carol@example.com"])]),
+            // Neither Bob nor Carol are in the keyring
+            &[alice()],
+        );
+
+        let carol = &matrix.readers["carol@example.com"];
+        assert!(!carol.known);
+        let bob = &matrix.readers["C544ADE7E4B6A858"];
+        assert!(!bob.known);
+        assert_eq!(bob.name, "Unknown key C544ADE7E4B6A858");
+
+        let mail = &matrix.entries[0];
+        assert_eq!(mail.readers, ids(&[ALICE, "C544ADE7E4B6A858"]));
+        assert_eq!(mail.unexpected, ids(&["C544ADE7E4B6A858"]));
+        assert_eq!(mail.missing, ids(&["carol@example.com"]));
+
+        let hidden = &matrix.entries[1];
+        assert_eq!(hidden.unexpected, ids(&["0000000000000000"]));
+        assert_eq!(hidden.missing, ids(&[ALICE, "carol@example.com"]));
+        assert_eq!(matrix.name("0000000000000000"), "Hidden recipient");
+
+        // Files that cannot be read are reported, but not as a mismatch
+        let broken = &matrix.entries[2];
+        assert!(broken.error.is_some());
+        assert!(!broken.is_mismatch());
+        assert_eq!(matrix.mismatches().count(), 2);
+    }
+}
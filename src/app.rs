@@ -1,16 +1,35 @@
-use iced::widget::{container, row};
+use iced::widget::{container, row, stack};
 use iced::window;
 use iced::{Element, Fill, Left, Subscription, Task, Theme, Top};
 use passepartout::PasswordStore;
+use std::{collections::BTreeSet, path::Path, sync::Arc};
 
 use crate::{
+    backend::BackendKind,
+    config::{self, Config},
+    environment::Environment,
+    key_check::{self, KeyWarning},
+    notification::{self, Notification, Notifications},
+    pinentry::{self, PassphraseRequest},
     screen::{
         self,
-        dashboard::{self, Dashboard},
+        access::{self, Access},
+        dashboard::{self, password_details, Dashboard},
+        health::{self, Health},
+        import::{self, Import},
+        keys::{self, Keys},
+        otp_export::{self, OtpExport},
+        recipients::{self, Recipients},
+        rotation::{self, Rotation},
         settings::{self, Settings},
+        share::{self, Share},
+        timeline::{self, Timeline},
         Screen,
     },
+    service::{Channel, Outcome, Request, RequestId, StoreService},
     sidebar::{self, Sidebar},
+    store::{self, StoreInfo},
+    watcher,
 };
 
 #[derive(Debug, Clone)]
@@ -19,31 +38,91 @@ pub enum Message {
     Sidebar(sidebar::Message),
     Dashboard(dashboard::Message),
     Settings(settings::Message),
+    Recipients(recipients::Message),
+    Access(access::Message),
+    Rotation(rotation::Message),
+    Keys(keys::Message),
+    Import(import::Message),
+    OtpExport(otp_export::Message),
+    Share(share::Message),
+    Health(health::Message),
+    Timeline(timeline::Message),
+    Notification(notification::Message),
+    Pinentry(pinentry::Message),
+    PassphraseRequested(PassphraseRequest),
+    PassphrasesForgotten(Outcome),
+    Watcher(watcher::Event),
+    KeysChecked((RequestId, key_check::Checked)),
 }
 
 pub struct App {
     sidebar: Sidebar,
     screen: Screen,
+    notifications: Notifications,
+    /// Dialog for passphrases when the loopback pinentry is enabled
+    pinentry: pinentry::Dialog,
     theme: Theme,
     scale_factor: f64,
-    store: PasswordStore,
+    config: Config,
+    active_store: usize,
+    /// Loaded stores in the order of the configuration
+    stores: Vec<PasswordStore>,
+    service: StoreService,
+    /// Problems with recipient keys per store
+    key_warnings: Vec<Vec<KeyWarning>>,
+    key_check: Option<RequestId>,
+    /// Store indices and pass IDs of entries whose password was found in the breach list
+    breached: BTreeSet<(usize, String)>,
+    environment: Environment,
 }
 
 impl App {
     pub fn new() -> (Self, Task<Message>) {
-        let store = PasswordStore::new();
-        let (sidebar, _) = Sidebar::new();
+        let mut notifications = Notifications::new();
+        let config = Config::load().unwrap_or_else(|e| {
+            notifications.push(Notification::error(format!(
+                "Configuration could not be loaded: {e:#}"
+            )));
+            let mut config = Config::default();
+            config.ensure_store();
+            config
+        });
+        Self::with_environment(config, notifications, Environment::system())
+    }
+
+    /// Creates the app with the given configuration that talks to the outside world
+    /// only through the environment
+    fn with_environment(
+        config: Config,
+        notifications: Notifications,
+        environment: Environment,
+    ) -> (Self, Task<Message>) {
+        let active_store = config.active_index();
+        let stores = load_stores(&config, &environment);
+        let service = StoreService::new(&environment);
+        let (sidebar, _) = Sidebar::new(
+            store_names(&config),
+            (!config.unified_view).then_some(active_store),
+        );
         let screen = Screen::Loading;
-        (
-            Self {
-                sidebar,
-                screen,
-                theme: Theme::default(),
-                scale_factor: 0.75,
-                store,
-            },
-            Task::none(),
-        )
+        let mut app = Self {
+            sidebar,
+            screen,
+            notifications,
+            pinentry: pinentry::Dialog::new(),
+            theme: Theme::default(),
+            scale_factor: 0.75,
+            config,
+            active_store,
+            stores,
+            service,
+            key_warnings: Vec::new(),
+            key_check: None,
+            breached: BTreeSet::new(),
+            environment,
+        };
+        let task = app.check_keys();
+        (app, task)
     }
 
     pub fn title(&self) -> String {
@@ -51,6 +130,15 @@ impl App {
             Screen::Loading => "Partout".to_owned(),
             Screen::Dashboard(dashboard) => dashboard.title(),
             Screen::Settings(settings) => settings.title(),
+            Screen::Recipients(recipients) => recipients.title(),
+            Screen::Access(access) => access.title(),
+            Screen::Rotation(rotation) => rotation.title(),
+            Screen::Keys(keys) => keys.title(),
+            Screen::Import(import) => import.title(),
+            Screen::OtpExport(otp_export) => otp_export.title(),
+            Screen::Share(share) => share.title(),
+            Screen::Health(health) => health.title(),
+            Screen::Timeline(timeline) => timeline.title(),
         }
     }
 
@@ -65,24 +153,166 @@ impl App {
                 let action = self.sidebar.update(message);
                 match action {
                     sidebar::Action::ShowDashboard => {
-                        let (dashboard, _) = Dashboard::new(self.store.passwords.clone());
-                        self.screen = Screen::Dashboard(dashboard);
+                        self.leave_screen();
+                        self.show_dashboard();
                     }
                     sidebar::Action::ShowSettings => {
-                        let (settings, _) = Settings::new(&mut self.theme);
+                        self.leave_screen();
+                        let (settings, _) = Settings::new(
+                            &mut self.theme,
+                            self.config.stores.clone(),
+                            self.config.passphrases.clone(),
+                            self.config.breach_list.clone(),
+                        );
                         self.screen = Screen::Settings(settings);
                     }
-                    sidebar::Action::Quit => return window::get_latest().and_then(window::close),
+                    sidebar::Action::ShowRecipients => {
+                        if let Screen::Recipients(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        return self.show_recipients(self.active_store, String::new());
+                    }
+                    sidebar::Action::ShowAccess => {
+                        if let Screen::Access(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (access, task) =
+                            Access::new(self.store_infos(), self.active_store, &mut self.service);
+                        self.screen = Screen::Access(access);
+                        return task.map(Message::Access);
+                    }
+                    sidebar::Action::ShowHealth => {
+                        if let Screen::Health(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (health, task) = Health::new(
+                            self.store_infos(),
+                            self.active_store,
+                            self.config.breach_list.clone(),
+                            &mut self.service,
+                        );
+                        self.screen = Screen::Health(health);
+                        return task.map(Message::Health);
+                    }
+                    sidebar::Action::ShowTimeline => {
+                        if let Screen::Timeline(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (timeline, task) =
+                            Timeline::new(self.store_infos(), self.active_store, &mut self.service);
+                        self.screen = Screen::Timeline(timeline);
+                        return task.map(Message::Timeline);
+                    }
+                    sidebar::Action::ShowRotation => {
+                        if let Screen::Rotation(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (rotation, task) =
+                            Rotation::new(self.store_infos(), self.active_store, &mut self.service);
+                        self.screen = Screen::Rotation(rotation);
+                        return task.map(Message::Rotation);
+                    }
+                    sidebar::Action::ShowKeys => {
+                        if let Screen::Keys(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (keys, task) =
+                            Keys::new(self.store_infos(), self.active_store, &mut self.service);
+                        self.screen = Screen::Keys(keys);
+                        return task.map(Message::Keys);
+                    }
+                    sidebar::Action::ShowImport => {
+                        if let Screen::Import(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (import, task) = Import::new(self.store_infos(), self.active_store);
+                        self.screen = Screen::Import(import);
+                        return task.map(Message::Import);
+                    }
+                    sidebar::Action::ShowOtpExport => {
+                        if let Screen::OtpExport(_) = self.screen {
+                            return Task::none();
+                        }
+                        self.leave_screen();
+                        let (otp_export, task) = OtpExport::new(
+                            self.store_infos(),
+                            self.active_store,
+                            &mut self.service,
+                        );
+                        self.screen = Screen::OtpExport(otp_export);
+                        return task.map(Message::OtpExport);
+                    }
+                    sidebar::Action::SwitchStore(index) => self.switch_store(index),
+                    sidebar::Action::ShowAllStores => self.show_all_stores(),
+                    sidebar::Action::ToggleNotifications => self
+                        .notifications
+                        .update(notification::Message::ToggleHistory),
+                    sidebar::Action::ForgetPassphrases => return self.forget_passphrases(),
+                    sidebar::Action::Lock => return self.lock(),
+                    sidebar::Action::Quit => {
+                        self.pinentry.cancel_all();
+                        self.leave_screen();
+                        self.service.cancel_operations();
+                        self.save_config();
+                        // Cancelled operations restore or finish the files they are writing
+                        // before the window is closed
+                        let close = self
+                            .service
+                            .operations_finished()
+                            .discard()
+                            .chain(window::get_latest().and_then(window::close));
+                        if self.config.passphrases.forget_on_quit {
+                            // The window is closed once gpg-agent was reloaded
+                            let (_, forget) = self
+                                .service
+                                .submit(Channel::Agent, Request::ForgetPassphrases);
+                            return forget.discard().chain(close);
+                        }
+                        return close;
+                    }
                     sidebar::Action::None => (),
                 }
                 Task::none()
             }
             Message::Dashboard(message) => {
                 if let Screen::Dashboard(dashboard) = &mut self.screen {
-                    let action = dashboard.update(message);
+                    let action = dashboard.update(message, &mut self.service);
                     return match action {
                         dashboard::Action::Run(task) => task.map(Message::Dashboard),
-                        _ => Task::none(),
+                        dashboard::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        dashboard::Action::ShowRecipients(store, folder) => {
+                            self.leave_screen();
+                            self.show_recipients(store, folder)
+                        }
+                        dashboard::Action::BreachChecked(entry, breached) => {
+                            let key = (entry.store.index, entry.pass_id);
+                            if breached {
+                                self.breached.insert(key);
+                            } else {
+                                self.breached.remove(&key);
+                            }
+                            Task::none()
+                        }
+                        dashboard::Action::ExportFor(entries, selection) => {
+                            if entries.is_empty() {
+                                return Task::none();
+                            }
+                            self.leave_screen();
+                            let (share, task) = Share::new(entries, selection, &mut self.service);
+                            self.screen = Screen::Share(share);
+                            task.map(Message::Share)
+                        }
+                        dashboard::Action::None => Task::none(),
                     };
                 }
                 Task::none()
@@ -95,11 +325,512 @@ impl App {
                             self.theme = theme;
                             Task::none()
                         }
+                        settings::Action::ChangeStores(stores) => {
+                            self.change_stores(stores);
+                            self.check_keys()
+                        }
+                        settings::Action::ChangeBreachList(breach_list) => {
+                            self.config.breach_list = breach_list;
+                            self.breached.clear();
+                            self.save_config();
+                            Task::none()
+                        }
+                        settings::Action::ChangePassphrases(passphrases) => {
+                            if !passphrases.loopback_pinentry {
+                                self.pinentry.cancel_all();
+                            }
+                            self.config.passphrases = passphrases;
+                            self.save_config();
+                            Task::none()
+                        }
                         _ => Task::none(),
                     };
                 }
                 Task::none()
             }
+            Message::Recipients(message) => {
+                if let Screen::Recipients(recipients) = &mut self.screen {
+                    let action = recipients.update(message, &mut self.service);
+                    return match action {
+                        recipients::Action::Run(task) => task.map(Message::Recipients),
+                        recipients::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        recipients::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Access(message) => {
+                if let Screen::Access(access) = &mut self.screen {
+                    let action = access.update(message, &mut self.service);
+                    return match action {
+                        access::Action::Run(task) => task.map(Message::Access),
+                        access::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        access::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Rotation(message) => {
+                if let Screen::Rotation(rotation) = &mut self.screen {
+                    let action = rotation.update(message, &mut self.service);
+                    return match action {
+                        rotation::Action::Run(task) => task.map(Message::Rotation),
+                        rotation::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        rotation::Action::None => Task::none(),
+                    };
+                }
+                // The rotation was cancelled when its screen was left,
+                // it ends once its changes were rolled back
+                if let rotation::Message::Event((_, crate::rotation::Event::Finished(result))) =
+                    message
+                {
+                    self.notifications.push(rotation::finished(&result));
+                }
+                Task::none()
+            }
+            Message::Keys(message) => {
+                if let Screen::Keys(keys) = &mut self.screen {
+                    let action = keys.update(message, &mut self.service);
+                    return match action {
+                        keys::Action::Run(task) => task.map(Message::Keys),
+                        keys::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        keys::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Import(message) => {
+                if let Screen::Import(import) = &mut self.screen {
+                    let action = import.update(message, &mut self.service);
+                    return match action {
+                        import::Action::Run(task) => task.map(Message::Import),
+                        import::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        import::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::OtpExport(message) => {
+                if let Screen::OtpExport(otp_export) = &mut self.screen {
+                    let action = otp_export.update(message, &mut self.service);
+                    return match action {
+                        otp_export::Action::Run(task) => task.map(Message::OtpExport),
+                        otp_export::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        otp_export::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Share(message) => {
+                if let Screen::Share(share) = &mut self.screen {
+                    let action = share.update(message, &mut self.service);
+                    return match action {
+                        share::Action::Run(task) => task.map(Message::Share),
+                        share::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        share::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Health(message) => {
+                if let Screen::Health(health) = &mut self.screen {
+                    let action = health.update(message, &mut self.service);
+                    return match action {
+                        health::Action::Run(task) => task.map(Message::Health),
+                        health::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        health::Action::ReportBreaches(store, breached) => {
+                            self.breached.retain(|(index, _)| *index != store);
+                            self.breached
+                                .extend(breached.into_iter().map(|pass_id| (store, pass_id)));
+                            Task::none()
+                        }
+                        health::Action::ShowEntry(store, pass_id) => {
+                            self.leave_screen();
+                            self.show_entry(store, &pass_id)
+                        }
+                        health::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Timeline(message) => {
+                if let Screen::Timeline(timeline) = &mut self.screen {
+                    let action = timeline.update(message, &mut self.service);
+                    return match action {
+                        timeline::Action::Run(task) => task.map(Message::Timeline),
+                        timeline::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        timeline::Action::ShowEntry(store, pass_id) => {
+                            self.leave_screen();
+                            self.show_entry(store, &pass_id)
+                        }
+                        timeline::Action::None => Task::none(),
+                    };
+                }
+                Task::none()
+            }
+            Message::Notification(message) => {
+                self.notifications.update(message);
+                Task::none()
+            }
+            Message::Pinentry(message) => {
+                self.pinentry.update(message);
+                Task::none()
+            }
+            Message::PassphraseRequested(request) => {
+                self.pinentry.push(request);
+                Task::none()
+            }
+            Message::PassphrasesForgotten((_, result)) => {
+                self.notifications.push(match result {
+                    Ok(_) => Notification::success("Cached passphrases were forgotten"),
+                    Err(e) => Notification::error(format!(
+                        "Cached passphrases could not be forgotten: {e}"
+                    )),
+                });
+                Task::none()
+            }
+            Message::KeysChecked((id, checked)) => {
+                if self.key_check != Some(id) {
+                    return Task::none();
+                }
+                match checked.warnings {
+                    Ok(warnings) => {
+                        if let Some(store_warnings) = self.key_warnings.get_mut(checked.store) {
+                            *store_warnings = warnings;
+                        }
+                        let warnings = self.dashboard_key_warnings();
+                        if let Screen::Dashboard(dashboard) = &mut self.screen {
+                            dashboard.set_key_warnings(warnings);
+                        }
+                    }
+                    Err(e) => self.notifications.push(Notification::warning(format!(
+                        "Recipient keys of {} could not be checked: {e}",
+                        self.config.stores[checked.store].name
+                    ))),
+                }
+                Task::none()
+            }
+            Message::Watcher(watcher::Event::Changed(paths)) => {
+                // Recipients may have changed
+                let check_keys = if paths.iter().any(|path| is_recipients_file(path)) {
+                    self.check_keys()
+                } else {
+                    Task::none()
+                };
+                let mut changed = Vec::new();
+                let mut changed_ids = Vec::new();
+                for (config, store) in self.config.stores.iter().zip(&mut self.stores) {
+                    let extension = (self.environment.backend)(config).extension();
+                    for pass_id in watcher::apply_changes(
+                        &mut store.passwords,
+                        &store.store_dir,
+                        extension,
+                        &paths,
+                    ) {
+                        changed.push(store::entry_path(&store.store_dir, &pass_id, extension));
+                        changed_ids.push(pass_id);
+                    }
+                }
+                if changed.is_empty() {
+                    return check_keys;
+                }
+                self.notifications
+                    .push(Notification::info(match changed_ids.len() {
+                        1 => format!("{} changed on disk", changed_ids[0]),
+                        n => format!("{n} password files changed on disk"),
+                    }));
+                let entries = self.dashboard_entries();
+                if let Screen::Dashboard(dashboard) = &mut self.screen {
+                    let action = dashboard.store_changed(entries, &changed, &mut self.service);
+                    let task = match action {
+                        dashboard::Action::Run(task) => task.map(Message::Dashboard),
+                        dashboard::Action::Notify(notification) => {
+                            self.notifications.push(notification);
+                            Task::none()
+                        }
+                        _ => Task::none(),
+                    };
+                    return Task::batch([task, check_keys]);
+                }
+                check_keys
+            }
+            Message::Watcher(watcher::Event::Failed(e)) => {
+                self.notifications.push(Notification::warning(format!(
+                    "Changes to the password store cannot be watched: {e}"
+                )));
+                Task::none()
+            }
+        }
+    }
+
+    /// Returns the entries shown on the dashboard, either of the active store
+    /// or of all stores when they are shown together
+    fn dashboard_entries(&self) -> Vec<store::Entry> {
+        let stores = self
+            .store_infos()
+            .into_iter()
+            .zip(&self.stores)
+            .filter(|(info, _)| self.config.unified_view || info.index == self.active_store);
+        store::entries(stores)
+    }
+
+    fn store_infos(&self) -> Vec<Arc<StoreInfo>> {
+        self.config
+            .stores
+            .iter()
+            .zip(&self.stores)
+            .enumerate()
+            .map(|(index, (config, store))| {
+                Arc::new(StoreInfo {
+                    index,
+                    name: config.name.clone(),
+                    dir: store.store_dir.clone(),
+                    backend: (self.environment.backend)(config),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the key warnings of the stores shown on the dashboard
+    fn dashboard_key_warnings(&self) -> Vec<(Arc<StoreInfo>, KeyWarning)> {
+        self.store_infos()
+            .into_iter()
+            .zip(&self.key_warnings)
+            .filter(|(info, _)| self.config.unified_view || info.index == self.active_store)
+            .flat_map(|(info, warnings)| {
+                warnings
+                    .iter()
+                    .map(move |warning| (Arc::clone(&info), warning.clone()))
+            })
+            .collect()
+    }
+
+    /// Checks the recipient keys of all stores in the background
+    fn check_keys(&mut self) -> Task<Message> {
+        // Previous warnings are kept until the stores are checked again
+        self.key_warnings.resize(self.stores.len(), Vec::new());
+        // Only GPG stores have recipient keys in the keyring
+        let stores = self
+            .config
+            .stores
+            .iter()
+            .zip(&self.stores)
+            .enumerate()
+            .filter(|(_, (config, _))| {
+                (self.environment.backend)(config).kind() == BackendKind::Gpg
+            })
+            .map(|(index, (_, store))| (index, store.store_dir.clone()))
+            .collect();
+        let keys = self.service.keys();
+        let (id, task) = self.service.run(Channel::KeyCheck, move |progress| {
+            key_check::check(keys.as_ref(), stores, progress)
+        });
+        self.key_check = Some(id);
+        task.map(Message::KeysChecked)
+    }
+
+    /// Clears the passphrase cache of gpg-agent
+    fn forget_passphrases(&mut self) -> Task<Message> {
+        let (_, task) = self
+            .service
+            .submit(Channel::Agent, Request::ForgetPassphrases);
+        task.map(Message::PassphrasesForgotten)
+    }
+
+    /// Removes all decrypted secrets from the screen, stops long operations and,
+    /// if configured, makes gpg-agent forget the passphrases
+    fn lock(&mut self) -> Task<Message> {
+        self.leave_screen();
+        self.service.cancel_operations();
+        self.pinentry.cancel_all();
+        self.service.cancel(Channel::Clipboard);
+        self.show_dashboard();
+        if self.config.passphrases.forget_on_lock {
+            self.forget_passphrases()
+        } else {
+            self.notifications
+                .push(Notification::info("Decrypted secrets were removed"));
+            Task::none()
+        }
+    }
+
+    fn show_recipients(&mut self, store: usize, folder: String) -> Task<Message> {
+        let (recipients, task) =
+            Recipients::new(self.store_infos(), store, folder, &mut self.service);
+        self.screen = Screen::Recipients(recipients);
+        task.map(Message::Recipients)
+    }
+
+    /// Opens the dashboard with the entry selected and its folder expanded
+    fn show_entry(&mut self, store: usize, pass_id: &str) -> Task<Message> {
+        if !self.config.unified_view && store != self.active_store {
+            self.switch_store(store);
+        }
+        self.show_dashboard();
+        let Some(entry) = self
+            .dashboard_entries()
+            .into_iter()
+            .find(|entry| entry.store.index == store && entry.pass_id == pass_id)
+        else {
+            return Task::none();
+        };
+        let folder = pass_id
+            .rsplit_once('/')
+            .map(|(folder, _)| folder.to_owned())
+            .unwrap_or_default();
+        let reveal = self.update(Message::Dashboard(dashboard::Message::RevealFolder(folder)));
+        let select = self.update(Message::Dashboard(dashboard::Message::PasswordDetails(
+            password_details::Message::SelectEntry(entry),
+        )));
+        Task::batch([reveal, select])
+    }
+
+    /// Saves the state of the current screen and cancels the operations it started
+    fn leave_screen(&mut self) {
+        self.remember_store_state();
+        // Results for the dashboard are no longer needed
+        self.service.cancel(Channel::Entry);
+        self.service.cancel(Channel::Otp);
+        self.service.cancel(Channel::Audit);
+        self.service.cancel(Channel::History);
+        self.service.cancel(Channel::FileInfo);
+        match &self.screen {
+            Screen::Recipients(recipients) if recipients.is_busy() => {
+                self.service.cancel(Channel::Batch);
+                self.notifications.push(Notification::warning(
+                    "Re-encryption was cancelled, some password files may still use the old recipients",
+                ));
+            }
+            // The outcome is reported once the rotation has rolled back its changes
+            Screen::Rotation(rotation) if rotation.is_busy() => {
+                self.service.cancel(Channel::Batch);
+            }
+            Screen::Import(import) if import.is_busy() => {
+                self.service.cancel(Channel::Batch);
+                self.notifications.push(Notification::warning(
+                    "Import was cancelled, some entries were not written",
+                ));
+            }
+            _ => (),
+        }
+    }
+
+    fn show_dashboard(&mut self) {
+        let state = if self.config.unified_view {
+            self.config.unified_state.clone()
+        } else {
+            self.config.stores[self.active_store].state.clone()
+        };
+        let show_badges = self.config.unified_view && self.stores.len() > 1;
+        let (mut dashboard, _) = Dashboard::new(self.dashboard_entries(), state, show_badges);
+        dashboard.set_key_warnings(self.dashboard_key_warnings());
+        dashboard.set_breach_list(self.config.breach_list.clone());
+        dashboard.set_breached(self.breached.clone());
+        self.screen = Screen::Dashboard(Box::new(dashboard));
+    }
+
+    /// Saves the state of the dashboard for the active store
+    fn remember_store_state(&mut self) {
+        if let Screen::Dashboard(dashboard) = &self.screen {
+            if self.config.unified_view {
+                self.config.unified_state = dashboard.state();
+            } else {
+                self.config.stores[self.active_store].state = dashboard.state();
+            }
+        }
+    }
+
+    fn switch_store(&mut self, index: usize) {
+        if index >= self.config.stores.len() {
+            return;
+        }
+        self.remember_store_state();
+        self.service.cancel(Channel::Entry);
+        self.service.cancel(Channel::Otp);
+
+        self.active_store = index;
+        self.config.unified_view = false;
+        self.config.active_store = Some(self.config.stores[index].name.clone());
+        self.sidebar
+            .set_stores(store_names(&self.config), Some(index));
+        self.save_config();
+
+        if let Screen::Dashboard(_) = self.screen {
+            self.show_dashboard();
+        }
+    }
+
+    fn show_all_stores(&mut self) {
+        self.remember_store_state();
+        self.service.cancel(Channel::Entry);
+        self.service.cancel(Channel::Otp);
+
+        self.config.unified_view = true;
+        self.sidebar.set_stores(store_names(&self.config), None);
+        self.save_config();
+
+        if let Screen::Dashboard(_) = self.screen {
+            self.show_dashboard();
+        }
+    }
+
+    fn change_stores(&mut self, stores: Vec<config::StoreConfig>) {
+        let active_name = self.config.stores[self.active_store].name.clone();
+        self.config.stores = stores;
+        self.stores = load_stores(&self.config, &self.environment);
+        self.active_store = self
+            .config
+            .stores
+            .iter()
+            .position(|store| store.name == active_name)
+            // The active store was removed
+            .unwrap_or(0);
+        self.config.active_store = Some(self.config.stores[self.active_store].name.clone());
+        if self.stores.len() < 2 {
+            self.config.unified_view = false;
+        }
+        self.sidebar.set_stores(
+            store_names(&self.config),
+            (!self.config.unified_view).then_some(self.active_store),
+        );
+        self.save_config();
+    }
+
+    fn save_config(&mut self) {
+        if !self.environment.save_config {
+            return;
+        }
+        if let Err(e) = self.config.save() {
+            self.notifications.push(Notification::error(format!(
+                "Configuration could not be saved: {e:#}"
+            )));
         }
     }
 
@@ -117,17 +848,93 @@ impl App {
             Screen::Loading => screen::loading(),
             Screen::Dashboard(dashboard) => dashboard.view().map(Message::Dashboard),
             Screen::Settings(settings) => settings.view().map(Message::Settings),
+            Screen::Recipients(recipients) => recipients.view().map(Message::Recipients),
+            Screen::Access(access) => access.view().map(Message::Access),
+            Screen::Rotation(rotation) => rotation.view().map(Message::Rotation),
+            Screen::Keys(keys) => keys.view().map(Message::Keys),
+            Screen::Import(import) => import.view().map(Message::Import),
+            Screen::OtpExport(otp_export) => otp_export.view().map(Message::OtpExport),
+            Screen::Share(share) => share.view().map(Message::Share),
+            Screen::Health(health) => health.view().map(Message::Health),
+            Screen::Timeline(timeline) => timeline.view().map(Message::Timeline),
         })
         .width(Fill);
 
-        container(row![sidebar, screen]).padding(5).into()
+        let notifications = self
+            .notifications
+            .view()
+            .map(|notifications| notifications.map(Message::Notification));
+
+        let pinentry = self
+            .pinentry
+            .view()
+            .map(|dialog| dialog.map(Message::Pinentry));
+
+        stack![container(row![sidebar, screen]).padding(5)]
+            .push_maybe(notifications)
+            .push_maybe(pinentry)
+            .into()
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::none()
+        let screen = match &self.screen {
+            Screen::Loading => Subscription::none(),
+            Screen::Dashboard(dashboard) => dashboard.subscription().map(Message::Dashboard),
+            Screen::Settings(settings) => settings.subscription().map(Message::Settings),
+            Screen::Recipients(recipients) => recipients.subscription().map(Message::Recipients),
+            Screen::Access(access) => access.subscription().map(Message::Access),
+            Screen::Rotation(rotation) => rotation.subscription().map(Message::Rotation),
+            Screen::Keys(keys) => keys.subscription().map(Message::Keys),
+            Screen::Import(import) => import.subscription().map(Message::Import),
+            Screen::OtpExport(otp_export) => otp_export.subscription().map(Message::OtpExport),
+            Screen::Share(share) => share.subscription().map(Message::Share),
+            Screen::Health(health) => health.subscription().map(Message::Health),
+            Screen::Timeline(timeline) => timeline.subscription().map(Message::Timeline),
+        };
+        Subscription::batch([
+            screen,
+            self.notifications.subscription().map(Message::Notification),
+            Subscription::batch(
+                self.stores
+                    .iter()
+                    .map(|store| watcher::watch(store.store_dir.clone())),
+            )
+            .map(Message::Watcher),
+            if self.config.passphrases.loopback_pinentry {
+                pinentry::requests().map(Message::PassphraseRequested)
+            } else {
+                Subscription::none()
+            },
+        ])
     }
 
     pub fn theme(&self) -> Theme {
         self.theme.clone()
     }
 }
+
+fn load_stores(config: &Config, environment: &Environment) -> Vec<PasswordStore> {
+    config
+        .stores
+        .iter()
+        .map(|store| store::load(store.path.clone(), (environment.backend)(store).as_ref()))
+        .collect()
+}
+
+/// Whether a path is a file that lists the recipients of a folder
+fn is_recipients_file(path: &Path) -> bool {
+    BackendKind::ALL
+        .iter()
+        .any(|kind| path.ends_with(kind.recipients_file()))
+}
+
+fn store_names(config: &Config) -> Vec<String> {
+    config
+        .stores
+        .iter()
+        .map(|store| store.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
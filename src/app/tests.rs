@@ -0,0 +1,1272 @@
+//! End-to-end tests that drive the app with messages,
+//! using an in-memory store, a fake clipboard, a fake keyring and a fixed clock
+
+use iced::futures::{executor::block_on, StreamExt};
+use iced::time::Instant;
+use iced_runtime::{task, Action};
+use std::{
+    collections::VecDeque,
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
+use tempfile::TempDir;
+
+use super::*;
+use crate::{
+    backend::{Backend, Memory},
+    clipboard::{Clipboard, FakeClipboard},
+    clock::FixedClock,
+    config::StoreConfig,
+    git,
+    gpg::{FakeKeyStore, KeyProblem, KeyStore, KeyringKey},
+    keyring,
+    notification::Level,
+    rotation,
+    screen::{
+        dashboard::password_details::{self, PasswordDetails, Secret},
+        dashboard::password_list,
+        health, import, otp_export, recipients, share, timeline,
+    },
+    service::Progress,
+};
+
+/// Secret of the SHA-1 test vectors of RFC 6238
+const OTP_URL: &str =
+    "otpauth://totp/Test?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&digits=8&period=30";
+/// One-time password of [`OTP_URL`] at 59 seconds after the epoch
+const OTP_AT_59: &str = "94287082";
+
+/// App with a single store whose password files only exist in memory
+struct Harness {
+    app: App,
+    clipboard: Arc<FakeClipboard>,
+    keys: Arc<FakeKeyStore>,
+    backend: Arc<Memory>,
+    store_dir: TempDir,
+}
+
+impl Harness {
+    /// Creates the store with the given password files and opens the dashboard,
+    /// files without contents cannot be decrypted
+    fn new(files: &[(&str, Option<&str>)]) -> Self {
+        Self::with_kind(BackendKind::Age, files)
+    }
+
+    /// Creates a store with the layout of the given backend, i.e. its file extension
+    /// and recipients file, the keys of GPG stores are looked up in the fake keyring
+    fn with_kind(kind: BackendKind, files: &[(&str, Option<&str>)]) -> Self {
+        let store_dir = tempfile::tempdir().expect("temporary directory can be created");
+        let backend = Arc::new(Memory::new(kind));
+        for (pass_id, contents) in files {
+            let path = store::entry_path(store_dir.path(), pass_id, backend.extension());
+            fs::create_dir_all(path.parent().expect("password file is in the store"))
+                .expect("folder can be created");
+            fs::write(&path, "").expect("placeholder can be written");
+            if let Some(contents) = contents {
+                backend.insert(path, *contents);
+            }
+        }
+
+        let clipboard = Arc::new(FakeClipboard::default());
+        let keys = Arc::new(FakeKeyStore::default());
+        let environment = Environment {
+            backend: Arc::new({
+                let backend = Arc::clone(&backend);
+                move |_: &StoreConfig| Arc::clone(&backend) as Arc<dyn Backend>
+            }),
+            clipboard: Arc::clone(&clipboard) as Arc<dyn Clipboard>,
+            clock: Arc::new(FixedClock(UNIX_EPOCH + Duration::from_secs(59))),
+            keys: Arc::clone(&keys) as Arc<dyn KeyStore>,
+            save_config: false,
+        };
+        let config = Config {
+            stores: vec![StoreConfig::new("Test", store_dir.path())],
+            ..Config::default()
+        };
+
+        let (app, task) = App::with_environment(config, Notifications::new(), environment);
+        let mut harness = Self {
+            app,
+            clipboard,
+            keys,
+            backend,
+            store_dir,
+        };
+        harness.process(task);
+        harness.send(Message::Sidebar(sidebar::Message::ShowDashboard));
+        harness
+    }
+
+    /// Sends the messages one after another without waiting for their tasks,
+    /// then processes all resulting messages until the app is idle
+    fn send_all(&mut self, messages: impl IntoIterator<Item = Message>) {
+        let tasks: Vec<_> = messages
+            .into_iter()
+            .map(|message| self.app.update(message))
+            .collect();
+        for task in tasks {
+            self.process(task);
+        }
+    }
+
+    fn send(&mut self, message: Message) {
+        self.send_all([message]);
+    }
+
+    /// Runs the task and feeds its output back into the app
+    fn process(&mut self, task: Task<Message>) {
+        let mut queue = VecDeque::from(outputs(task));
+        while let Some(message) = queue.pop_front() {
+            queue.extend(outputs(self.app.update(message)));
+        }
+    }
+
+    fn details_message(message: password_details::Message) -> Message {
+        Message::Dashboard(dashboard::Message::PasswordDetails(message))
+    }
+
+    fn select(&mut self, pass_id: &str) {
+        let entry = self.entry(pass_id);
+        self.send(Self::details_message(
+            password_details::Message::SelectEntry(entry),
+        ));
+    }
+
+    fn entry(&self, pass_id: &str) -> store::Entry {
+        self.app
+            .dashboard_entries()
+            .into_iter()
+            .find(|entry| entry.pass_id == pass_id)
+            .unwrap_or_else(|| panic!("{pass_id} is in the store"))
+    }
+
+    fn details(&self) -> &PasswordDetails {
+        match &self.app.screen {
+            Screen::Dashboard(dashboard) => dashboard.details(),
+            _ => panic!("dashboard is shown"),
+        }
+    }
+
+    /// Runs git in the store directory and panics when it fails
+    fn git(&self, args: &[&str], date: Option<&str>) {
+        let mut command = std::process::Command::new("git");
+        command.arg("-C").arg(self.store_dir.path()).args(args);
+        if let Some(date) = date {
+            command
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date);
+        }
+        let status = command.status().expect("git can be run");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    fn init_repository(&self) {
+        self.git(&["init", "--quiet"], None);
+        self.git(&["config", "user.name", "Alice"], None);
+        self.git(&["config", "user.email", "alice@example.com"], None);
+    }
+
+    /// Commits all files of the store at the given date
+    fn commit(&self, message: &str, date: &str) {
+        self.git(&["add", "--all"], None);
+        self.git(&["commit", "--quiet", "-m", message], Some(date));
+    }
+
+    /// Returns the latest notification
+    fn notification(&self) -> Option<(Level, &str)> {
+        self.app
+            .notifications
+            .history()
+            .next()
+            .map(|notification| (notification.level, notification.text.as_str()))
+    }
+}
+
+/// Collects the messages a task produces, other actions are dropped
+fn outputs(task: Task<Message>) -> Vec<Message> {
+    let Some(stream) = task::into_stream(task) else {
+        return Vec::new();
+    };
+    block_on(
+        stream
+            .filter_map(|action| async move {
+                match action {
+                    Action::Output(message) => Some(message),
+                    _ => None,
+                }
+            })
+            .collect(),
+    )
+}
+
+#[test]
+fn selecting_an_entry_shows_its_fields() {
+    let mut harness = Harness::new(&[("email/work", Some("hunter2\nalice@example.com"))]);
+
+    harness.select("email/work");
+
+    let details = harness.details();
+    assert_eq!(details.selected().unwrap().pass_id, "email/work");
+    assert_eq!(details.password(), Some("hunter2"));
+    assert_eq!(details.login(), Some("alice@example.com"));
+    assert_eq!(details.otp(), None);
+}
+
+#[test]
+fn secrets_are_revealed_one_at_a_time_and_hidden_again() {
+    let mut harness = Harness::new(&[("email/work", Some("hunter2\nalice@example.com"))]);
+    harness.select("email/work");
+
+    harness.send(Harness::details_message(password_details::Message::Reveal(
+        Secret::Login,
+    )));
+    assert!(harness.details().is_revealed(Secret::Login));
+    assert!(!harness.details().is_revealed(Secret::Password));
+
+    harness.send(Harness::details_message(password_details::Message::Peek(
+        Secret::Password,
+    )));
+    assert!(harness.details().is_revealed(Secret::Password));
+    harness.send(Harness::details_message(password_details::Message::EndPeek));
+    assert!(!harness.details().is_revealed(Secret::Password));
+
+    harness.send(Harness::details_message(
+        password_details::Message::RevealAll,
+    ));
+    assert!(harness.details().is_revealed(Secret::Password));
+    harness.send(Harness::details_message(password_details::Message::Tick(
+        Instant::now() + password_details::REVEAL_TIMEOUT,
+    )));
+    assert!(!harness.details().is_revealed(Secret::Password));
+    assert!(!harness.details().is_revealed(Secret::Login));
+}
+
+#[test]
+fn only_the_latest_selection_is_shown() {
+    let mut harness = Harness::new(&[("a", Some("first")), ("b", Some("second"))]);
+    let (a, b) = (harness.entry("a"), harness.entry("b"));
+
+    harness.send_all([
+        Harness::details_message(password_details::Message::SelectEntry(a)),
+        Harness::details_message(password_details::Message::SelectEntry(b)),
+    ]);
+
+    let details = harness.details();
+    assert_eq!(details.selected().unwrap().pass_id, "b");
+    assert_eq!(details.password(), Some("second"));
+}
+
+#[test]
+fn otp_is_generated_for_the_current_time() {
+    let contents = format!("hunter2\nalice\n{OTP_URL}");
+    let mut harness = Harness::new(&[("bank", Some(&contents))]);
+
+    harness.select("bank");
+
+    assert_eq!(harness.details().otp(), Some(OTP_AT_59));
+}
+
+#[test]
+fn copying_the_password_uses_an_expiring_clipboard() {
+    let mut harness = Harness::new(&[("bank", Some("hunter2\nalice"))]);
+    let entry = harness.entry("bank");
+
+    harness.send(Harness::details_message(
+        password_details::Message::CopyPassword(entry),
+    ));
+
+    assert_eq!(
+        harness.clipboard.copied.lock().unwrap().as_slice(),
+        [("hunter2".to_owned(), true)]
+    );
+    assert_eq!(
+        harness.notification(),
+        Some((Level::Success, "Password copied to clipboard"))
+    );
+}
+
+#[test]
+fn copying_the_otp_copies_the_current_code() {
+    let contents = format!("hunter2\nalice\n{OTP_URL}");
+    let mut harness = Harness::new(&[("bank", Some(&contents))]);
+    let entry = harness.entry("bank");
+
+    harness.send(Harness::details_message(
+        password_details::Message::CopyOtp(entry),
+    ));
+
+    assert_eq!(harness.clipboard.contents().as_deref(), Some(OTP_AT_59));
+}
+
+#[test]
+fn copying_a_missing_login_fails() {
+    let mut harness = Harness::new(&[("pin", Some("1234"))]);
+    let entry = harness.entry("pin");
+
+    harness.send(Harness::details_message(
+        password_details::Message::CopyLogin(entry),
+    ));
+
+    assert_eq!(harness.clipboard.contents(), None);
+    assert_eq!(
+        harness.notification(),
+        Some((Level::Error, "Login could not be copied: no login found"))
+    );
+}
+
+#[test]
+fn decryption_errors_are_reported() {
+    let mut harness = Harness::new(&[("locked", None)]);
+
+    harness.select("locked");
+
+    assert_eq!(harness.details().password(), None);
+    assert_eq!(
+        harness.notification(),
+        Some((
+            Level::Error,
+            "Failed to decrypt password file: no secret key"
+        ))
+    );
+}
+
+#[test]
+fn locking_removes_decrypted_secrets() {
+    let mut harness = Harness::new(&[("bank", Some("hunter2\nalice"))]);
+    harness.select("bank");
+
+    harness.send(Message::Sidebar(sidebar::Message::Lock));
+
+    let details = harness.details();
+    assert!(details.selected().is_none());
+    assert_eq!(details.password(), None);
+}
+
+#[test]
+fn long_operations_do_not_hold_up_requests_and_stop_on_lock() {
+    let mut harness = Harness::new(&[("bank", Some("hunter2\n"))]);
+    let (release, released) = std::sync::mpsc::channel::<()>();
+    let (_, operation) =
+        harness
+            .app
+            .service
+            .run(Channel::Batch, move |progress: &Progress<bool>| {
+                released.recv().expect("operation is released");
+                progress.report(progress.is_cancelled());
+            });
+
+    // The entry is decrypted while the operation is still running
+    harness.select("bank");
+    assert_eq!(harness.details().password(), Some("hunter2"));
+
+    harness.send(Message::Sidebar(sidebar::Message::Lock));
+    release.send(()).expect("operation is waiting");
+    let cancelled: Vec<bool> = block_on(
+        task::into_stream(operation)
+            .expect("operation reports its progress")
+            .filter_map(|action| async move {
+                match action {
+                    Action::Output((_, cancelled)) => Some(cancelled),
+                    _ => None,
+                }
+            })
+            .collect(),
+    );
+    assert_eq!(cancelled, [true]);
+}
+
+#[test]
+fn quitting_waits_for_cancelled_operations_to_end() {
+    let mut harness = Harness::new(&[("bank", Some("hunter2\n"))]);
+    let restored = harness.store_dir.path().join("bank.age");
+    fs::write(&restored, "re-encrypted").expect("file can be written");
+    let operation_file = restored.clone();
+    let (started, starting) = std::sync::mpsc::channel();
+    let _ = harness
+        .app
+        .service
+        .run(Channel::Batch, move |progress: &Progress<()>| {
+            started.send(()).expect("test is waiting");
+            while !progress.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            // Cleaning up takes a while after the cancellation was noticed
+            std::thread::sleep(Duration::from_millis(50));
+            fs::write(&operation_file, "").expect("file can be restored");
+        });
+    starting.recv().expect("operation is started");
+
+    let quit = harness.app.update(Message::Sidebar(sidebar::Message::Quit));
+    let window_actions = block_on(
+        task::into_stream(quit)
+            .expect("window is closed")
+            .filter(|action| std::future::ready(matches!(action, Action::Window(_))))
+            .map(|_| fs::read_to_string(&restored).expect("file exists"))
+            .collect::<Vec<_>>(),
+    );
+    // The file was restored before the window was looked up to be closed
+    assert_eq!(window_actions, [""]);
+}
+
+#[test]
+fn changed_recipients_reencrypt_the_folder_without_subfolders_of_their_own() {
+    let mut harness = Harness::new(&[
+        ("bank", Some("bank secret\n")),
+        ("web/mail", Some("mail secret\n")),
+        ("web/forum", None),
+        ("work/vpn", Some("vpn secret\n")),
+    ]);
+    let store_dir = harness.store_dir.path().to_owned();
+    fs::write(store_dir.join(".age-recipients"), "age1alice\n").expect("file can be written");
+    fs::write(store_dir.join("work/.age-recipients"), "age1work\n").expect("file can be written");
+
+    harness.send_all([
+        Message::Sidebar(sidebar::Message::ShowRecipients),
+        Message::Recipients(recipients::Message::NewRecipientChanged(
+            "age1bob".to_owned(),
+        )),
+        Message::Recipients(recipients::Message::AddRecipient),
+        Message::Recipients(recipients::Message::Apply),
+    ]);
+
+    // The forum cannot be decrypted, the other files are written for the new recipients
+    assert_eq!(
+        harness.notification(),
+        Some((Level::Error, "Re-encrypted 2 of 3 password files, 1 failed"))
+    );
+    let read = |path: &str| fs::read_to_string(store_dir.join(path)).expect("file exists");
+    assert_eq!(read(".age-recipients"), "age1alice\nage1bob\n");
+    assert_eq!(read("bank.age"), "bank secret\n");
+    assert_eq!(read("web/mail.age"), "mail secret\n");
+    assert_eq!(read("web/forum.age"), "");
+    // The work folder keeps its own recipients
+    assert_eq!(read("work/.age-recipients"), "age1work\n");
+    assert_eq!(read("work/vpn.age"), "");
+}
+
+#[test]
+fn rotations_that_end_after_their_screen_was_left_are_reported() {
+    let mut harness = Harness::new(&[]);
+
+    harness.send(Message::Rotation(screen::rotation::Message::Event((
+        1,
+        rotation::Event::Finished(Err(
+            "The rotation was cancelled, all changes were rolled back".to_owned(),
+        )),
+    ))));
+
+    assert_eq!(
+        harness.notification(),
+        Some((
+            Level::Error,
+            "Key rotation failed: The rotation was cancelled, all changes were rolled back"
+        ))
+    );
+}
+
+#[test]
+fn csv_exports_are_imported_into_the_target_folder() {
+    let mut harness = Harness::new(&[("imported/bank", Some("old"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    fs::write(store_dir.join(".age-recipients"), "age1recipient\n")
+        .expect("recipients can be written");
+    let export = store_dir.join("export.csv");
+    fs::write(
+        &export,
+        "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+         ,,login,bank,,,0,https://bank.example,alice,new,\n\
+         Work,,login,mail,remember me,PIN: 1234,0,https://mail.example,bob,secret,JBSWY3DPEHPK3PXP\n",
+    )
+    .expect("export can be written");
+
+    harness.send_all([
+        Message::Sidebar(sidebar::Message::ShowImport),
+        Message::Import(import::Message::PathChanged(
+            export.to_string_lossy().into_owned(),
+        )),
+        Message::Import(import::Message::Preview),
+    ]);
+    harness.send(Message::Import(import::Message::Import));
+
+    // Existing password files are kept unless overwriting is enabled
+    let read = |pass_id: &str| {
+        fs::read_to_string(store::entry_path(&store_dir, pass_id, "age"))
+            .expect("password file exists")
+    };
+    assert_eq!(read("imported/bank"), "");
+    // Password files are only readable by the owner
+    let metadata = fs::metadata(store::entry_path(&store_dir, "imported/Work/mail", "age"))
+        .expect("password file exists");
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+    assert_eq!(
+        read("imported/Work/mail"),
+        "secret\nbob\nurl: https://mail.example\nPIN: 1234\n\
+         otpauth://totp/mail:bob?secret=JBSWY3DPEHPK3PXP&issuer=mail\nremember me\n"
+    );
+    assert_eq!(
+        harness.notification(),
+        Some((Level::Success, "Imported 1 entries"))
+    );
+}
+
+/// Path of a file in `tests/fixtures`
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Opens the KeePass database in the import wizard and previews or imports it
+fn import_kdbx(harness: &mut Harness, path: &str, password: &str, key_file: &str) {
+    harness.send_all([
+        Message::Sidebar(sidebar::Message::ShowImport),
+        Message::Import(import::Message::SelectSource(crate::import::Source::Kdbx)),
+        Message::Import(import::Message::PathChanged(path.to_owned())),
+        Message::Import(import::Message::PasswordChanged(password.to_owned())),
+        Message::Import(import::Message::KeyFileChanged(key_file.to_owned())),
+        Message::Import(import::Message::Preview),
+    ]);
+    harness.send(Message::Import(import::Message::Import));
+}
+
+#[test]
+fn keepass_databases_are_imported_with_their_otp_settings() {
+    // KDBX 3.1 with the AES key derivation and Salsa20 protected values,
+    // KDBX 4 with Argon2id, ChaCha20 and a key file
+    for (database, key_file) in [
+        ("kdbx3-aes-kdf.kdbx", String::new()),
+        ("kdbx4-argon2-chacha20.kdbx", fixture("kdbx4.keyx")),
+    ] {
+        let mut harness = Harness::new(&[]);
+        let store_dir = harness.store_dir.path().to_owned();
+        fs::write(store_dir.join(".age-recipients"), "age1recipient\n")
+            .expect("recipients can be written");
+        import_kdbx(&mut harness, &fixture(database), "correct horse", &key_file);
+
+        assert_eq!(
+            harness.notification(),
+            Some((Level::Success, "Imported 3 entries")),
+            "{database}"
+        );
+        let read = |pass_id: &str| {
+            fs::read(store::entry_path(&store_dir, pass_id, "age"))
+                .unwrap_or_else(|_| panic!("{pass_id} of {database} was imported"))
+        };
+        assert_eq!(
+            String::from_utf8(read("imported/Mail")).expect("entry is text"),
+            "s3cr3t\nbob\nurl: https://mail.example\n\
+             otpauth://totp/Mail:bob?secret=JBSWY3DPEHPK3PXP&issuer=Mail&digits=8\n\
+             line 1\nline 2\n"
+        );
+        assert_eq!(read("imported/Mail/doc.bin"), b"attached bytes\x00\x01");
+        // The hex secret of KeePass is converted to base32
+        assert_eq!(
+            String::from_utf8(read("imported/Work-Home/VPN")).expect("entry is text"),
+            "plain\n\nPIN: 4321\notpauth://totp/VPN?secret=JBSWY3DPEHPK3PXP&algorithm=SHA256\n"
+        );
+        // Entries in the recycle bin are left out
+        assert!(!store_dir.join("imported/Recycle Bin").exists());
+    }
+}
+
+#[test]
+fn unreadable_keepass_databases_are_reported() {
+    let mut harness = Harness::new(&[]);
+    let store_dir = harness.store_dir.path().to_owned();
+    let database = fs::read(fixture("kdbx4-argon2-chacha20.kdbx")).expect("fixture exists");
+    let key_file = fixture("kdbx4.keyx");
+    let truncated = store_dir.join("truncated.kdbx");
+    fs::write(&truncated, &database[..database.len() / 2]).expect("file can be written");
+    let corrupted = store_dir.join("corrupted.kdbx");
+    let mut bytes = database.clone();
+    let last = bytes.len() - 100;
+    bytes[last] ^= 1;
+    fs::write(&corrupted, bytes).expect("file can be written");
+    let not_keepass = store_dir.join("export.kdbx");
+    fs::write(&not_keepass, "title,password\n").expect("file can be written");
+
+    for (path, password, key_file, error) in [
+        (
+            fixture("kdbx3-aes-kdf.kdbx"),
+            "wrong horse",
+            "",
+            "wrong master password or key file",
+        ),
+        (
+            fixture("kdbx4-argon2-chacha20.kdbx"),
+            "correct horse",
+            "",
+            "wrong master password or key file",
+        ),
+        (
+            truncated.to_string_lossy().into_owned(),
+            "correct horse",
+            &key_file,
+            "the database is truncated",
+        ),
+        (
+            corrupted.to_string_lossy().into_owned(),
+            "correct horse",
+            &key_file,
+            "a data block of the database is corrupted",
+        ),
+        (
+            not_keepass.to_string_lossy().into_owned(),
+            "",
+            "",
+            "not a KeePass database",
+        ),
+    ] {
+        import_kdbx(&mut harness, &path, password, key_file);
+        assert_eq!(
+            harness.notification(),
+            Some((
+                Level::Error,
+                format!("Export could not be read: {error}").as_str()
+            ))
+        );
+    }
+}
+
+#[test]
+fn authenticator_backups_add_otp_urls_to_matching_entries() {
+    let mut harness = Harness::new(&[("web/mail", Some("hunter2\nbob\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    fs::write(store_dir.join(".age-recipients"), "age1recipient\n")
+        .expect("recipients can be written");
+    let backup = store_dir.join("aegis.json");
+    fs::write(
+        &backup,
+        r#"{"version": 1, "header": {"slots": null, "params": null}, "db": {"version": 2, "entries": [
+            {"type": "totp", "name": "bob", "issuer": "Mail",
+             "info": {"secret": "JBSWY3DPEHPK3PXP", "algo": "SHA1", "digits": 6, "period": 30}},
+            {"type": "totp", "name": "alice", "issuer": "Bank",
+             "info": {"secret": "GEZDGNBVGY3TQOJQ", "algo": "SHA256", "digits": 8, "period": 60}}
+        ]}}"#,
+    )
+    .expect("backup can be written");
+
+    harness.send_all([
+        Message::Sidebar(sidebar::Message::ShowImport),
+        Message::Import(import::Message::SelectSource(
+            crate::import::Source::Authenticator,
+        )),
+        Message::Import(import::Message::PathChanged(
+            backup.to_string_lossy().into_owned(),
+        )),
+        Message::Import(import::Message::Preview),
+    ]);
+    harness.send(Message::Import(import::Message::Import));
+
+    let read = |pass_id: &str| {
+        fs::read_to_string(store::entry_path(&store_dir, pass_id, "age"))
+            .expect("password file exists")
+    };
+    assert_eq!(
+        read("web/mail"),
+        "hunter2\nbob\notpauth://totp/Mail:bob?secret=JBSWY3DPEHPK3PXP&issuer=Mail\n"
+    );
+    assert_eq!(
+        read("imported/Bank"),
+        "\nalice\notpauth://totp/Bank:alice?secret=GEZDGNBVGY3TQOJQ&issuer=Bank\
+         &algorithm=SHA256&digits=8&period=60\n"
+    );
+}
+
+#[test]
+fn otp_secrets_are_exported_for_the_selected_entries() {
+    let mut harness = Harness::new(&[
+        ("bank", Some(&format!("hunter2\nalice\n{OTP_URL}"))),
+        (
+            "mail",
+            Some("secret\nbob\notpauth://totp/Mail:bob?secret=JBSWY3DPEHPK3PXP"),
+        ),
+        ("notes", Some("no otp")),
+    ]);
+    let export = harness.store_dir.path().join("export.json");
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowOtpExport));
+    harness.send_all([
+        Message::OtpExport(otp_export::Message::Toggle(1, false)),
+        Message::OtpExport(otp_export::Message::SelectFormat(
+            crate::authenticator::Format::AndOtp,
+        )),
+        Message::OtpExport(otp_export::Message::PathChanged(
+            export.to_string_lossy().into_owned(),
+        )),
+    ]);
+    harness.send(Message::OtpExport(otp_export::Message::Export));
+
+    let (format, tokens) = crate::authenticator::read(&export).expect("export can be read");
+    assert_eq!(format, crate::authenticator::Format::AndOtp);
+    assert_eq!(tokens.len(), 1);
+    // Tokens without issuer are named after their entry
+    assert_eq!(tokens[0].issuer, "bank");
+    assert_eq!(tokens[0].name, "Test");
+    assert_eq!(tokens[0].secret, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    assert_eq!(tokens[0].digits, 8);
+}
+
+#[test]
+fn shared_bundles_are_merged_into_another_folder() {
+    let mut harness = Harness::new(&[
+        ("work/clients/acme", Some("acme-pass\nalice\n")),
+        ("work/clients/beta", Some("beta-pass\n")),
+        ("work/intranet", Some("not shared\n")),
+    ]);
+    let store_dir = harness.store_dir.path().to_owned();
+    fs::write(store_dir.join(".age-recipients"), "age1recipient\n")
+        .expect("recipients can be written");
+    let bundle = store_dir.join("clients.bundle");
+
+    harness.send(Message::Dashboard(dashboard::Message::PasswordList(
+        password_list::Message::ExportFolder("work/clients".to_owned()),
+    )));
+    harness.send_all([
+        Message::Share(share::Message::RecipientChanged(
+            "age1contractor".to_owned(),
+        )),
+        Message::Share(share::Message::PathChanged(
+            bundle.to_string_lossy().into_owned(),
+        )),
+    ]);
+    harness.send(Message::Share(share::Message::Export));
+    assert_eq!(
+        harness.notification().map(|(level, _)| level),
+        Some(Level::Success)
+    );
+
+    // The in-memory backend writes the bundle unencrypted
+    let contents = fs::read(&bundle).expect("bundle was written");
+    harness.backend.insert(bundle.clone(), contents);
+    harness.send_all([
+        Message::Sidebar(sidebar::Message::ShowImport),
+        Message::Import(import::Message::SelectSource(crate::import::Source::Bundle)),
+        Message::Import(import::Message::PathChanged(
+            bundle.to_string_lossy().into_owned(),
+        )),
+        Message::Import(import::Message::FolderChanged("shared".to_owned())),
+        Message::Import(import::Message::Preview),
+    ]);
+    harness.send(Message::Import(import::Message::Import));
+
+    let read = |pass_id: &str| fs::read_to_string(store::entry_path(&store_dir, pass_id, "age"));
+    assert_eq!(
+        read("shared/clients/acme").expect("entry was imported"),
+        "acme-pass\nalice\n"
+    );
+    assert_eq!(
+        read("shared/clients/beta").expect("entry was imported"),
+        "beta-pass\n"
+    );
+    assert!(read("shared/intranet").is_err());
+}
+
+#[test]
+fn password_health_reports_weak_reused_passwords_and_missing_logins() {
+    let mut harness = Harness::new(&[
+        ("bank", Some("Xk9#mQ2$vL7&pR4!wZ\nalice\n")),
+        ("web/forum", Some("hunter2\nurl: https://forum.example\n")),
+        ("web/mail", Some("hunter2\nbob\n")),
+    ]);
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowHealth));
+    let Screen::Health(health) = &harness.app.screen else {
+        panic!("health is shown");
+    };
+    let report = health.report().expect("store was checked");
+    assert_eq!(report.audited, 3);
+    let issues = |pass_id: &str| {
+        report
+            .findings
+            .iter()
+            .find(|finding| finding.pass_id == pass_id)
+            .map(|finding| {
+                finding
+                    .issues
+                    .iter()
+                    .map(|issue| issue.kind())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    };
+    assert_eq!(issues("bank"), []);
+    assert_eq!(
+        issues("web/forum"),
+        [
+            crate::health::IssueKind::Weak,
+            crate::health::IssueKind::Reused,
+            crate::health::IssueKind::MissingLogin
+        ]
+    );
+    assert_eq!(
+        issues("web/mail"),
+        [
+            crate::health::IssueKind::Weak,
+            crate::health::IssueKind::Reused
+        ]
+    );
+
+    // Findings link to the entry in the details
+    harness.send(Message::Health(health::Message::ShowEntry(
+        "web/forum".to_owned(),
+    )));
+    assert_eq!(
+        harness
+            .details()
+            .selected()
+            .map(|entry| entry.pass_id.as_str()),
+        Some("web/forum")
+    );
+    assert_eq!(harness.details().password(), Some("hunter2"));
+}
+
+#[test]
+fn passwords_are_looked_up_in_the_breach_list() {
+    use sha1::{Digest, Sha1};
+
+    let mut harness = Harness::new(&[
+        ("bank", Some("Xk9#mQ2$vL7&pR4!wZ\nalice\n")),
+        ("web/mail", Some("hunter2\nbob\n")),
+    ]);
+    let hash = |password: &str| -> String {
+        Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect()
+    };
+    let mut lines: Vec<String> = (0..1000)
+        .map(|i| format!("{}:{}", hash(&format!("password{i}")), i + 1))
+        .collect();
+    lines.push(format!("{}:17043\r", hash("hunter2")));
+    lines.sort();
+    let breach_list = harness
+        .store_dir
+        .path()
+        .join("pwned-passwords-sha1-ordered-by-hash.txt");
+    fs::write(&breach_list, lines.join("\n")).expect("breach list can be written");
+    harness.app.config.breach_list = Some(breach_list);
+    harness.send(Message::Sidebar(sidebar::Message::ShowDashboard));
+
+    harness.select("web/mail");
+    assert_eq!(harness.details().breaches(), Some(17043));
+    // The password is looked up without decrypting it a second time
+    assert_eq!(harness.backend.decryptions(), 1);
+    harness.select("bank");
+    assert_eq!(harness.details().breaches(), None);
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowHealth));
+    let Screen::Health(health) = &harness.app.screen else {
+        panic!("health is shown");
+    };
+    let report = health.report().expect("store was checked");
+    assert!(report.breaches_checked);
+    assert_eq!(report.breached().collect::<Vec<_>>(), ["web/mail"]);
+    assert!(harness.app.breached.contains(&(0, "web/mail".to_owned())));
+}
+
+#[test]
+fn old_versions_are_restored_as_a_new_commit() {
+    let mut harness = Harness::new(&[("bank", Some("second\nalice\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    let file_path = store::entry_path(&store_dir, "bank", "age");
+    fs::write(store_dir.join(".age-recipients"), "age1recipient\n")
+        .expect("recipients can be written");
+    harness.init_repository();
+    for (contents, message) in [
+        ("first\nalice\n", "Add bank"),
+        ("second\nalice\n", "Edit bank"),
+    ] {
+        fs::write(&file_path, contents).expect("password file can be written");
+        harness.commit(message, "2024-01-01T12:00:00Z");
+    }
+
+    harness.select("bank");
+    harness.send(Harness::details_message(
+        password_details::Message::SelectTab(password_details::Tab::History),
+    ));
+    let commits = harness
+        .details()
+        .history()
+        .expect("history is loaded")
+        .to_vec();
+    let subjects: Vec<&str> = commits
+        .iter()
+        .map(|commit| commit.subject.as_str())
+        .collect();
+    assert_eq!(subjects, ["Edit bank", "Add bank"]);
+    assert_eq!(commits[1].author, "Alice");
+
+    harness.send(Harness::details_message(
+        password_details::Message::ViewVersion(commits[1].clone()),
+    ));
+    assert_eq!(harness.details().version_contents(), Some("first\nalice\n"));
+
+    harness.send(Harness::details_message(
+        password_details::Message::RestoreVersion(commits[1].clone()),
+    ));
+    assert_eq!(
+        harness.notification(),
+        Some((Level::Success, "Old version restored as a new commit"))
+    );
+    assert_eq!(
+        fs::read_to_string(&file_path).expect("password file can be read"),
+        "first\nalice\n"
+    );
+    let history = git::file_history(&store_dir, &file_path).expect("history can be read");
+    assert_eq!(history.len(), 3);
+    assert_eq!(
+        history[0].subject,
+        "Restore bank to the version of ".to_owned() + commits[1].short_hash()
+    );
+}
+
+#[test]
+fn old_versions_of_entries_with_non_ascii_names_are_read() {
+    let mut harness = Harness::new(&[("reisen/münchen", Some("second\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    let file_path = store::entry_path(&store_dir, "reisen/münchen", "age");
+    harness.init_repository();
+    for (contents, message) in [("first\n", "Add münchen"), ("second\n", "Edit münchen")] {
+        fs::write(&file_path, contents).expect("password file can be written");
+        harness.commit(message, "2024-01-01T12:00:00Z");
+    }
+
+    harness.select("reisen/münchen");
+    harness.send(Harness::details_message(
+        password_details::Message::SelectTab(password_details::Tab::History),
+    ));
+    let commits = harness
+        .details()
+        .history()
+        .expect("history is loaded")
+        .to_vec();
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[1].path, Path::new("reisen/münchen.age"));
+
+    harness.send(Harness::details_message(
+        password_details::Message::ViewVersion(commits[1].clone()),
+    ));
+    assert_eq!(harness.details().version_contents(), Some("first\n"));
+}
+
+#[test]
+fn the_timeline_is_filtered_by_author_folder_and_date() {
+    let mut harness = Harness::new(&[]);
+    let store_dir = harness.store_dir.path().to_owned();
+    harness.init_repository();
+    fs::create_dir_all(store_dir.join("web")).expect("folder can be created");
+    fs::write(store_dir.join(".age-recipients"), "age1alice\n").expect("file can be written");
+    fs::write(store_dir.join("bank.age"), "1").expect("file can be written");
+    harness.commit("Add bank", "2024-03-01T10:00:00Z");
+    fs::write(store_dir.join("web/mail.age"), "2").expect("file can be written");
+    fs::write(store_dir.join("bank.age"), "3").expect("file can be written");
+    harness.git(&["config", "user.name", "Mallory"], None);
+    harness.commit("Update entries", "2024-03-05T23:30:00Z");
+    fs::remove_file(store_dir.join("bank.age")).expect("file can be removed");
+    harness.commit("Remove bank", "2024-03-09T08:00:00Z");
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowTimeline));
+    let subjects = |harness: &Harness| -> Vec<String> {
+        let Screen::Timeline(timeline) = &harness.app.screen else {
+            panic!("timeline is shown");
+        };
+        timeline
+            .shown()
+            .iter()
+            .map(|commit| commit.subject.clone())
+            .collect()
+    };
+    assert_eq!(
+        subjects(&harness),
+        ["Remove bank", "Update entries", "Add bank"]
+    );
+    let Screen::Timeline(timeline) = &harness.app.screen else {
+        panic!("timeline is shown");
+    };
+    let first = timeline.shown()[2];
+    assert_eq!(first.signature, git::Signature::Unsigned);
+    assert_eq!(
+        first.changes,
+        [
+            git::Change::Added(".age-recipients".into()),
+            git::Change::Added("bank.age".into())
+        ]
+    );
+
+    harness.send(Message::Timeline(timeline::Message::SelectAuthor(
+        "Mallory".to_owned(),
+    )));
+    assert_eq!(subjects(&harness), ["Remove bank", "Update entries"]);
+    harness.send(Message::Timeline(timeline::Message::FolderChanged(
+        "web/".to_owned(),
+    )));
+    assert_eq!(subjects(&harness), ["Update entries"]);
+
+    harness.send(Message::Timeline(timeline::Message::ClearFilters));
+    harness.send_all([
+        Message::Timeline(timeline::Message::SinceChanged("2024-03-02".to_owned())),
+        Message::Timeline(timeline::Message::UntilChanged("2024-03-05".to_owned())),
+    ]);
+    assert_eq!(subjects(&harness), ["Update entries"]);
+}
+
+#[test]
+fn the_timeline_shows_renames_of_non_ascii_names() {
+    let mut harness = Harness::new(&[("reisen/münchen", None)]);
+    harness.init_repository();
+    harness.commit("Add münchen", "2024-03-01T10:00:00Z");
+    harness.git(&["mv", "reisen/münchen.age", "reisen/köln.age"], None);
+    harness.commit("Move münchen", "2024-03-02T10:00:00Z");
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowTimeline));
+    let Screen::Timeline(timeline) = &harness.app.screen else {
+        panic!("timeline is shown");
+    };
+    let changes: Vec<&[git::Change]> = timeline
+        .shown()
+        .iter()
+        .map(|commit| commit.changes.as_slice())
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            [git::Change::Renamed(
+                "reisen/münchen.age".into(),
+                "reisen/köln.age".into()
+            )],
+            [git::Change::Added("reisen/münchen.age".into())]
+        ]
+    );
+}
+
+#[test]
+fn file_information_is_shown_without_decrypting() {
+    let mut harness = Harness::new(&[("web/mail", Some("hunter2\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    fs::write(store_dir.join(".age-recipients"), "age1alice\n").expect("file can be written");
+    fs::write(store_dir.join("web/mail.age"), "ciphertext").expect("file can be written");
+    harness.init_repository();
+    harness.commit("Add mail", "2024-03-01T10:00:00Z");
+
+    harness.select("web/mail");
+
+    let info = harness.details().file_info().expect("file info is read");
+    assert_eq!(info.size, 10);
+    assert!(info.key_ids.is_none());
+    assert_eq!(
+        info.recipients_file,
+        Some(store_dir.join(".age-recipients"))
+    );
+    let commit = info
+        .last_commit
+        .clone()
+        .expect("store is a repository")
+        .expect("log can be read")
+        .expect("file was committed");
+    assert_eq!(commit.subject, "Add mail");
+    assert_eq!(keyring::format_time(commit.time), "2024-03-01 10:00 UTC");
+}
+
+/// Public key of the fake keyring that can be used for encryption
+fn keyring_key(fingerprint: &str, user_id: &str, problem: Option<KeyProblem>) -> KeyringKey {
+    KeyringKey {
+        fingerprint: fingerprint.to_owned(),
+        user_ids: vec![user_id.to_owned()],
+        expires: None,
+        can_encrypt: true,
+        can_sign: true,
+        can_certify: true,
+        can_authenticate: false,
+        secret: false,
+        problem,
+    }
+}
+
+#[test]
+fn recipient_keys_are_checked_again_when_recipients_change() {
+    let mut harness = Harness::with_kind(BackendKind::Gpg, &[("work/mail", Some("hunter2\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    *harness.keys.keys.lock().unwrap() = vec![
+        keyring_key(
+            "A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1",
+            "Alice <alice@example.com>",
+            Some(KeyProblem::Expired),
+        ),
+        keyring_key(
+            "B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2",
+            "Bob <bob@example.com>",
+            None,
+        ),
+    ];
+    fs::write(
+        store_dir.join(".gpg-id"),
+        "alice@example.com\nbob@example.com\n",
+    )
+    .expect("file can be written");
+    fs::write(
+        store_dir.join("work/.gpg-id"),
+        "alice@example.com\ncarol@example.com\n",
+    )
+    .expect("file can be written");
+
+    harness.send(Message::Watcher(watcher::Event::Changed(vec![
+        store_dir.join(".gpg-id"),
+        store_dir.join("work/.gpg-id"),
+    ])));
+
+    let warnings: Vec<_> = harness.app.key_warnings[0]
+        .iter()
+        .map(|warning| (warning.text(), warning.folders.clone()))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            (
+                "Key of Alice <alice@example.com> (alice@example.com) is expired".to_owned(),
+                vec![String::new(), "work".to_owned()]
+            ),
+            (
+                "Key carol@example.com is missing from the keyring".to_owned(),
+                vec!["work".to_owned()]
+            ),
+        ]
+    );
+    assert_eq!(harness.app.dashboard_key_warnings().len(), 2);
+
+    // Alice renewed her key and Carol was removed
+    harness.keys.keys.lock().unwrap()[0].problem = None;
+    fs::write(store_dir.join("work/.gpg-id"), "alice@example.com\n").expect("file can be written");
+    harness.send(Message::Watcher(watcher::Event::Changed(vec![
+        store_dir.join("work/.gpg-id")
+    ])));
+
+    assert!(harness.app.key_warnings[0].is_empty());
+    assert!(harness.app.dashboard_key_warnings().is_empty());
+}
+
+fn keys_screen(harness: &Harness) -> &Keys {
+    match &harness.app.screen {
+        Screen::Keys(keys) => keys,
+        _ => panic!("keys are shown"),
+    }
+}
+
+/// Returns the names of the listed keys with the folders they are recipients of
+fn listed_keys(harness: &Harness) -> Vec<(String, Vec<String>)> {
+    keys_screen(harness)
+        .keyring()
+        .expect("keys are listed")
+        .keys
+        .iter()
+        .map(|key| (key.name().to_owned(), key.folders.clone()))
+        .collect()
+}
+
+#[test]
+fn imported_keys_are_listed_with_the_folders_they_are_recipients_of() {
+    let mut harness = Harness::with_kind(BackendKind::Gpg, &[("work/mail", Some("hunter2\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    *harness.keys.keys.lock().unwrap() = vec![keyring_key(
+        "A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1",
+        "Alice <alice@example.com>",
+        None,
+    )];
+    fs::write(store_dir.join(".gpg-id"), "alice@example.com\n").expect("file can be written");
+    fs::write(store_dir.join("work/.gpg-id"), "bob@example.com\n").expect("file can be written");
+
+    harness.send(Message::Sidebar(sidebar::Message::ShowKeys));
+    assert_eq!(
+        listed_keys(&harness),
+        [("Alice <alice@example.com>".to_owned(), vec![String::new()])]
+    );
+    assert_eq!(keys_screen(&harness).keyring().unwrap().folder_count, 2);
+
+    let key_file = store_dir.join("keys.asc");
+    fs::write(
+        &key_file,
+        "A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1 Alice <alice@example.com>\n\
+         B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2B2 Bob <bob@example.com>\n",
+    )
+    .expect("file can be written");
+    harness.send_all([
+        Message::Keys(keys::Message::ImportPathChanged(
+            key_file.to_string_lossy().into_owned(),
+        )),
+        Message::Keys(keys::Message::Import),
+    ]);
+
+    assert_eq!(
+        harness.notification(),
+        Some((
+            Level::Success,
+            "Imported 1 of 2 keys, 1 unchanged, 0 secret keys"
+        ))
+    );
+    assert_eq!(keys_screen(&harness).import_path(), "");
+    assert_eq!(
+        listed_keys(&harness),
+        [
+            ("Alice <alice@example.com>".to_owned(), vec![String::new()]),
+            ("Bob <bob@example.com>".to_owned(), vec!["work".to_owned()]),
+        ]
+    );
+
+    harness.send_all([
+        Message::Keys(keys::Message::ImportPathChanged(
+            store_dir.join("missing.asc").to_string_lossy().into_owned(),
+        )),
+        Message::Keys(keys::Message::Import),
+    ]);
+    let (level, text) = harness.notification().expect("import failure is reported");
+    assert_eq!(level, Level::Error);
+    assert!(text.starts_with("Keys could not be imported: "), "{text}");
+}
+
+#[test]
+fn public_keys_are_exported_to_the_chosen_file() {
+    let mut harness = Harness::with_kind(BackendKind::Gpg, &[("mail", Some("hunter2\n"))]);
+    let store_dir = harness.store_dir.path().to_owned();
+    let fingerprint = "A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1A1";
+    *harness.keys.keys.lock().unwrap() =
+        vec![keyring_key(fingerprint, "Alice <alice@example.com>", None)];
+    harness.send(Message::Sidebar(sidebar::Message::ShowKeys));
+
+    let export = store_dir.join("alice.asc");
+    harness.send_all([
+        Message::Keys(keys::Message::Export(fingerprint.to_owned())),
+        Message::Keys(keys::Message::ExportPathChanged(
+            export.to_string_lossy().into_owned(),
+        )),
+        Message::Keys(keys::Message::SaveExport),
+    ]);
+
+    assert_eq!(
+        harness.notification(),
+        Some((
+            Level::Success,
+            format!("Public key was exported to {}", export.display()).as_str()
+        ))
+    );
+    assert_eq!(
+        fs::read_to_string(&export).expect("key was exported"),
+        format!("{fingerprint} Alice <alice@example.com>\n")
+    );
+
+    harness.send_all([
+        Message::Keys(keys::Message::Export("C3C3".to_owned())),
+        Message::Keys(keys::Message::ExportPathChanged(
+            store_dir.join("carol.asc").to_string_lossy().into_owned(),
+        )),
+        Message::Keys(keys::Message::SaveExport),
+    ]);
+    assert_eq!(
+        harness.notification(),
+        Some((
+            Level::Error,
+            "Key could not be exported: no public key found"
+        ))
+    );
+    assert!(!store_dir.join("carol.asc").exists());
+}
@@ -0,0 +1,485 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{backend::Backend, otp, recipients, service::Progress};
+
+/// Backup formats of authenticator apps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Aegis,
+    AndOtp,
+}
+
+impl Format {
+    pub const ALL: [Format; 2] = [Format::Aegis, Format::AndOtp];
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Aegis => write!(f, "Aegis"),
+            Format::AndOtp => write!(f, "andOTP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Totp,
+    Hotp,
+    /// TOTP with the alphabet of Steam Guard
+    Steam,
+}
+
+/// One-time password settings as found in an `otpauth://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: Kind,
+    pub issuer: String,
+    /// Account name, usually the login
+    pub name: String,
+    /// Secret in base32
+    pub secret: String,
+    /// `SHA1`, `SHA256` or `SHA512`
+    pub algorithm: String,
+    pub digits: u32,
+    /// Seconds a TOTP is valid
+    pub period: u64,
+    /// Counter of a HOTP
+    pub counter: u64,
+}
+
+impl Token {
+    /// Parses an `otpauth://` URL, the label may contain the issuer before a colon
+    pub fn from_url(url: &str) -> Option<Self> {
+        let rest = url.strip_prefix("otpauth://")?;
+        let (kind, rest) = rest.split_once('/')?;
+        let kind = match kind.to_lowercase().as_str() {
+            "totp" => Kind::Totp,
+            "hotp" => Kind::Hotp,
+            _ => return None,
+        };
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = percent_decode(label);
+        let (issuer, name) = label.split_once(':').unwrap_or(("", &label));
+
+        let mut token = Token {
+            kind,
+            issuer: issuer.trim().to_owned(),
+            name: name.trim().to_owned(),
+            secret: String::new(),
+            algorithm: "SHA1".to_owned(),
+            digits: 6,
+            period: 30,
+            counter: 0,
+        };
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = percent_decode(value);
+            match key.to_lowercase().as_str() {
+                "secret" => token.secret = value.replace([' ', '='], "").to_uppercase(),
+                "issuer" => token.issuer = value,
+                "algorithm" => token.algorithm = value.to_uppercase(),
+                "digits" => token.digits = value.parse().ok()?,
+                "period" => token.period = value.parse().ok()?,
+                "counter" => token.counter = value.parse().ok()?,
+                "encoder" if value == "steam" => token.kind = Kind::Steam,
+                _ => (),
+            }
+        }
+        (!token.secret.is_empty()).then_some(token)
+    }
+
+    pub fn to_url(&self) -> String {
+        let kind = match self.kind {
+            Kind::Hotp => "hotp",
+            Kind::Totp | Kind::Steam => "totp",
+        };
+        let label = if self.issuer.is_empty() {
+            percent_encode(&self.name)
+        } else {
+            format!(
+                "{}:{}",
+                percent_encode(&self.issuer),
+                percent_encode(&self.name)
+            )
+        };
+        let mut url = format!("otpauth://{kind}/{label}?secret={}", self.secret);
+        if !self.issuer.is_empty() {
+            url.push_str(&format!("&issuer={}", percent_encode(&self.issuer)));
+        }
+        if self.algorithm != "SHA1" {
+            url.push_str(&format!("&algorithm={}", self.algorithm));
+        }
+        if self.digits != 6 {
+            url.push_str(&format!("&digits={}", self.digits));
+        }
+        match self.kind {
+            Kind::Hotp => url.push_str(&format!("&counter={}", self.counter)),
+            _ if self.period != 30 => url.push_str(&format!("&period={}", self.period)),
+            _ => (),
+        }
+        if self.kind == Kind::Steam {
+            url.push_str("&encoder=steam");
+        }
+        url
+    }
+
+    /// Issuer and account name as shown by authenticator apps
+    pub fn label(&self) -> String {
+        match (self.issuer.is_empty(), self.name.is_empty()) {
+            (true, _) => self.name.clone(),
+            (false, true) => self.issuer.clone(),
+            (false, false) => format!("{} ({})", self.issuer, self.name),
+        }
+    }
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = value
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                char::from(byte).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// Plain (unencrypted) Aegis vault
+#[derive(Serialize, Deserialize)]
+struct AegisBackup {
+    version: u32,
+    header: AegisHeader,
+    db: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AegisHeader {
+    slots: Option<serde_json::Value>,
+    params: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AegisDatabase {
+    version: u32,
+    entries: Vec<AegisEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AegisEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    uuid: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    issuer: String,
+    #[serde(default)]
+    note: String,
+    #[serde(default)]
+    icon: Option<String>,
+    info: AegisInfo,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AegisInfo {
+    secret: String,
+    algo: String,
+    digits: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    period: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    counter: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AndOtpEntry {
+    secret: String,
+    #[serde(default)]
+    issuer: String,
+    #[serde(default)]
+    label: String,
+    digits: u32,
+    #[serde(rename = "type")]
+    kind: String,
+    algorithm: String,
+    #[serde(default)]
+    thumbnail: String,
+    #[serde(default)]
+    last_used: u64,
+    #[serde(default)]
+    used_frequency: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    period: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    counter: Option<u64>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Reads an unencrypted Aegis or andOTP backup
+pub fn read(file_path: &Path) -> Result<(Format, Vec<Token>), String> {
+    let data = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+    let json: serde_json::Value = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    if json.is_array() {
+        let entries: Vec<AndOtpEntry> = serde_json::from_value(json).map_err(|e| e.to_string())?;
+        return Ok((
+            Format::AndOtp,
+            entries.into_iter().filter_map(and_otp_token).collect(),
+        ));
+    }
+
+    let backup: AegisBackup = serde_json::from_value(json)
+        .map_err(|_| "neither an Aegis nor an andOTP backup".to_owned())?;
+    if backup.db.is_string() {
+        return Err(
+            "encrypted Aegis backups are not supported, export the vault without encryption"
+                .to_owned(),
+        );
+    }
+    let db: AegisDatabase = serde_json::from_value(backup.db).map_err(|e| e.to_string())?;
+    Ok((
+        Format::Aegis,
+        db.entries.into_iter().filter_map(aegis_token).collect(),
+    ))
+}
+
+fn aegis_token(entry: AegisEntry) -> Option<Token> {
+    let kind = match entry.kind.as_str() {
+        "totp" => Kind::Totp,
+        "hotp" => Kind::Hotp,
+        "steam" => Kind::Steam,
+        // Yandex and mOTP have no otpauth:// representation
+        _ => return None,
+    };
+    Some(Token {
+        kind,
+        issuer: entry.issuer,
+        name: entry.name,
+        secret: entry.info.secret.to_uppercase(),
+        algorithm: entry.info.algo.to_uppercase(),
+        digits: entry.info.digits,
+        period: entry.info.period.unwrap_or(30),
+        counter: entry.info.counter.unwrap_or_default(),
+    })
+}
+
+fn and_otp_token(entry: AndOtpEntry) -> Option<Token> {
+    let kind = match entry.kind.to_uppercase().as_str() {
+        "TOTP" => Kind::Totp,
+        "HOTP" => Kind::Hotp,
+        "STEAM" => Kind::Steam,
+        _ => return None,
+    };
+    // Older versions keep the issuer in the label
+    let (issuer, name) = match entry.label.split_once(" - ") {
+        Some((issuer, name)) if entry.issuer.is_empty() => (issuer.to_owned(), name.to_owned()),
+        _ => (entry.issuer, entry.label),
+    };
+    Some(Token {
+        kind,
+        issuer,
+        name,
+        secret: entry.secret.to_uppercase(),
+        algorithm: entry.algorithm.to_uppercase(),
+        digits: entry.digits,
+        period: entry.period.unwrap_or(30),
+        counter: entry.counter.unwrap_or_default(),
+    })
+}
+
+/// Writes the tokens as an unencrypted backup that only the user can read
+pub fn write(format: Format, tokens: &[Token], file_path: &Path) -> Result<(), String> {
+    let json = match format {
+        Format::Aegis => {
+            let db = AegisDatabase {
+                version: 2,
+                entries: tokens.iter().map(aegis_entry).collect(),
+            };
+            serde_json::to_string_pretty(&AegisBackup {
+                version: 1,
+                header: AegisHeader {
+                    slots: None,
+                    params: None,
+                },
+                db: serde_json::to_value(db).map_err(|e| e.to_string())?,
+            })
+        }
+        Format::AndOtp => {
+            serde_json::to_string_pretty(&tokens.iter().map(and_otp_entry).collect::<Vec<_>>())
+        }
+    }
+    .map_err(|e| e.to_string())?;
+
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(file_path)
+        .and_then(|mut file| file.write_all(json.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+fn aegis_entry(token: &Token) -> AegisEntry {
+    let (kind, period, counter) = match token.kind {
+        Kind::Totp => ("totp", Some(token.period), None),
+        Kind::Hotp => ("hotp", None, Some(token.counter)),
+        Kind::Steam => ("steam", Some(token.period), None),
+    };
+    AegisEntry {
+        kind: kind.to_owned(),
+        uuid: uuid(token),
+        name: token.name.clone(),
+        issuer: token.issuer.clone(),
+        note: String::new(),
+        icon: None,
+        info: AegisInfo {
+            secret: token.secret.clone(),
+            algo: token.algorithm.clone(),
+            digits: token.digits,
+            period,
+            counter,
+        },
+    }
+}
+
+fn and_otp_entry(token: &Token) -> AndOtpEntry {
+    let (kind, period, counter) = match token.kind {
+        Kind::Totp => ("TOTP", Some(token.period), None),
+        Kind::Hotp => ("HOTP", None, Some(token.counter)),
+        Kind::Steam => ("STEAM", Some(token.period), None),
+    };
+    AndOtpEntry {
+        secret: token.secret.clone(),
+        issuer: token.issuer.clone(),
+        label: token.name.clone(),
+        digits: token.digits,
+        kind: kind.to_owned(),
+        algorithm: token.algorithm.clone(),
+        thumbnail: "Default".to_owned(),
+        last_used: 0,
+        used_frequency: 0,
+        period,
+        counter,
+        tags: Vec::new(),
+    }
+}
+
+/// UUID of an Aegis entry, derived from the token so that repeated exports match
+fn uuid(token: &Token) -> String {
+    let mut bytes: [u8; 16] = Sha256::digest(token.to_url()).as_slice()[..16]
+        .try_into()
+        .expect("digest is longer than a UUID");
+    // Version 4 and RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Token found in a password file
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub pass_id: String,
+    pub token: Token,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    /// Tokens of all password files and the number of files that could not be decrypted
+    Scanned {
+        found: Vec<Found>,
+        failures: usize,
+    },
+    Exported(Result<PathBuf, String>),
+}
+
+/// Decrypts all password files of the store and collects their `otpauth://` URLs
+pub fn scan(backend: &dyn Backend, store_dir: &Path, progress: &Progress<Event>) {
+    let paths = recipients::password_files(backend, store_dir);
+    let total = paths.len();
+    let mut found = Vec::new();
+    let mut failures = 0;
+    for (done, path) in paths.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            return;
+        }
+        progress.report(Event::Progress { done, total });
+        let Ok(contents) = backend.decrypt(&path) else {
+            failures += 1;
+            continue;
+        };
+        let pass_id = path
+            .strip_prefix(store_dir)
+            .unwrap_or(&path)
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned();
+        let contents = String::from_utf8_lossy(&contents);
+        for mut token in contents
+            .lines()
+            .filter(|line| otp::is_url(line))
+            .filter_map(Token::from_url)
+        {
+            // Authenticator apps list tokens by their issuer
+            if token.issuer.is_empty() {
+                token.issuer = pass_id.rsplit('/').next().unwrap_or_default().to_owned();
+            }
+            found.push(Found {
+                pass_id: pass_id.clone(),
+                token,
+            });
+        }
+    }
+    progress.report(Event::Scanned { found, failures });
+}
+
+/// Writes the selected tokens to a backup file
+pub fn export(format: Format, tokens: Vec<Token>, file_path: PathBuf, progress: &Progress<Event>) {
+    let result = write(format, &tokens, &file_path).map(|_| file_path);
+    progress.report(Event::Exported(result));
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+mod age;
+#[cfg(test)]
+mod memory;
+
+pub use self::age::Age;
+#[cfg(test)]
+pub use self::memory::Memory;
+use crate::{config::StoreConfig, gpg};
+
+/// Encryption tool a password store is used with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// `pass` with GnuPG
+    #[default]
+    Gpg,
+    /// `passage` with age
+    Age,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 2] = [BackendKind::Gpg, BackendKind::Age];
+
+    /// Extension of password files
+    pub fn extension(self) -> &'static str {
+        match self {
+            BackendKind::Gpg => "gpg",
+            BackendKind::Age => "age",
+        }
+    }
+
+    /// Name of the files that list the recipients of a folder
+    pub fn recipients_file(self) -> &'static str {
+        match self {
+            BackendKind::Gpg => ".gpg-id",
+            BackendKind::Age => ".age-recipients",
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendKind::Gpg => write!(f, "GPG (pass)"),
+            BackendKind::Age => write!(f, "age (passage)"),
+        }
+    }
+}
+
+/// Encryption of password files in the layout of a password store
+pub trait Backend: fmt::Debug + Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    /// Extension of password files
+    fn extension(&self) -> &'static str {
+        self.kind().extension()
+    }
+
+    /// Name of the files that list the recipients of a folder
+    fn recipients_file(&self) -> &'static str {
+        self.kind().recipients_file()
+    }
+
+    fn decrypt(&self, file_path: &Path) -> Result<Vec<u8>, String>;
+
+    /// Decrypts the contents of a password file that is not on disk, like an old version
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String>;
+
+    /// Prepares the encryption for the given recipients
+    fn encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String>;
+
+    /// Prepares the encryption for the given recipients with ASCII armored output,
+    /// used for files that are handed to others
+    fn armored_encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        self.encrypter(recipients)
+    }
+}
+
+/// Encrypts password files for a fixed set of recipients
+pub trait Encrypt {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Returns the backend that is configured for a store
+pub fn for_store(store: &StoreConfig) -> Arc<dyn Backend> {
+    match store.backend {
+        BackendKind::Gpg => Arc::new(Gpg),
+        BackendKind::Age => Arc::new(Age::new(store.identities.clone())),
+    }
+}
+
+/// Decrypts a password file as text
+pub fn decrypt_text(backend: &dyn Backend, file_path: &Path) -> Result<String, String> {
+    String::from_utf8(backend.decrypt(file_path)?)
+        .map_err(|_| "password file is not valid UTF-8".to_owned())
+}
+
+/// Decrypts a password file and encrypts it again
+pub fn reencrypt(
+    backend: &dyn Backend,
+    encrypter: &mut dyn Encrypt,
+    file_path: &Path,
+) -> Result<(), String> {
+    let plaintext = backend
+        .decrypt(file_path)
+        .map_err(|e| format!("decryption failed: {e}"))?;
+    let ciphertext = encrypter
+        .encrypt(&plaintext)
+        .map_err(|e| format!("encryption failed: {e}"))?;
+    write_atomically(file_path, &ciphertext)
+}
+
+/// Writes the file next to the old one and then moves it over it,
+/// so a password file is never left half written
+pub fn write_atomically(file_path: &Path, contents: &[u8]) -> Result<(), String> {
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| "invalid file name".to_owned())?;
+    let temp_path: PathBuf =
+        file_path.with_file_name(format!(".{}.partout-tmp", file_name.to_string_lossy()));
+    // A leftover of an earlier attempt could have wider permissions
+    let _ = fs::remove_file(&temp_path);
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, file_path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            e.to_string()
+        })
+}
+
+/// Store of `pass`, encrypted with GnuPG
+#[derive(Debug)]
+pub struct Gpg;
+
+impl Backend for Gpg {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Gpg
+    }
+
+    fn decrypt(&self, file_path: &Path) -> Result<Vec<u8>, String> {
+        gpg::decrypt(file_path)
+    }
+
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        gpg::decrypt_bytes(ciphertext)
+    }
+
+    fn encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        Ok(Box::new(gpg::Encrypter::new(recipients)?))
+    }
+
+    fn armored_encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        let mut encrypter = gpg::Encrypter::new(recipients)?;
+        encrypter.set_armor(true);
+        Ok(Box::new(encrypter))
+    }
+}
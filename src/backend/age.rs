@@ -0,0 +1,210 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use super::{Backend, BackendKind, Encrypt};
+
+/// Store of `passage`, encrypted with the `age` command line tool
+#[derive(Debug)]
+pub struct Age {
+    /// File with the identities that are used for decryption
+    identities: PathBuf,
+}
+
+impl Age {
+    /// Uses the given identities file or the default of `passage`
+    pub fn new(identities: Option<PathBuf>) -> Self {
+        Self {
+            identities: identities.unwrap_or_else(default_identities),
+        }
+    }
+}
+
+/// Location of the identities file like `passage` determines it
+fn default_identities() -> PathBuf {
+    env::var_os("PASSAGE_IDENTITIES_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_default()
+                .join(".passage")
+                .join("identities")
+        })
+}
+
+impl Backend for Age {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Age
+    }
+
+    fn decrypt(&self, file_path: &Path) -> Result<Vec<u8>, String> {
+        let mut command = Command::new("age");
+        command
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(&self.identities)
+            .arg(file_path);
+        run(command, None)
+    }
+
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut command = Command::new("age");
+        command
+            .arg("--decrypt")
+            .arg("--identity")
+            .arg(&self.identities);
+        run(command, Some(ciphertext))
+    }
+
+    fn encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        Ok(Box::new(AgeEncrypter::new(recipients, false)?))
+    }
+
+    fn armored_encrypter(&self, recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        Ok(Box::new(AgeEncrypter::new(recipients, true)?))
+    }
+}
+
+struct AgeEncrypter {
+    recipients: RecipientsFile,
+    /// Whether the output is PEM encoded
+    armor: bool,
+}
+
+impl AgeEncrypter {
+    fn new(recipients: &[String], armor: bool) -> Result<Self, String> {
+        if recipients.is_empty() {
+            return Err("no recipients".to_owned());
+        }
+        let recipients = RecipientsFile::write(recipients)
+            .map_err(|e| format!("recipients could not be written: {e}"))?;
+        Ok(Self { recipients, armor })
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new("age");
+        command.arg("--encrypt");
+        if self.armor {
+            command.arg("--armor");
+        }
+        // Like passage, age reads the recipients from a file, so that it understands
+        // everything a recipients file may contain, like SSH keys with comments
+        command.arg("--recipients-file").arg(&self.recipients.0);
+        command
+    }
+}
+
+impl Encrypt for AgeEncrypter {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        run(self.command(), Some(plaintext))
+    }
+}
+
+/// Temporary copy of the recipients of a folder, removed when it is dropped
+struct RecipientsFile(PathBuf);
+
+impl RecipientsFile {
+    fn write(recipients: &[String]) -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "partout-recipients-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left behind by an earlier process with the same ID
+        let _ = fs::remove_file(&path);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        let recipients_file = Self(path);
+        file.write_all(format!("{}\n", recipients.join("\n")).as_bytes())?;
+        Ok(recipients_file)
+    }
+}
+
+impl Drop for RecipientsFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Runs age with the input on stdin and returns its output
+fn run(mut command: Command, input: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("age could not be run: {e}"))?;
+    let stdin = child.stdin.take();
+    let (output, written) = thread::scope(|scope| {
+        // Writing from another thread while the output is read keeps age
+        // from blocking on a full pipe, the input ends when stdin is dropped
+        let writer = scope.spawn(move || match (input, stdin) {
+            (Some(input), Some(mut stdin)) => stdin.write_all(input),
+            _ => Ok(()),
+        });
+        let output = child.wait_with_output();
+        (
+            output,
+            writer.join().expect("writing the input does not panic"),
+        )
+    });
+    let output = output.map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+    written.map_err(|e| e.to_string())?;
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ffi::OsStr, os::unix::fs::PermissionsExt};
+
+    #[test]
+    fn recipients_are_passed_to_age_in_a_file() {
+        let recipients = [
+            "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p".to_owned(),
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIHsKLqeplhpW+uObz5dvMgjz1OxfM/XXUB+VHtZ6isGN alice@laptop"
+                .to_owned(),
+        ];
+        let encrypter = AgeEncrypter::new(&recipients, true).expect("recipients can be written");
+        let path = encrypter.recipients.0.clone();
+
+        let command = encrypter.command();
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            ["--encrypt", "--armor", "--recipients-file"]
+                .map(OsStr::new)
+                .into_iter()
+                .chain([path.as_os_str()])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            fs::read_to_string(&path).expect("recipients file exists"),
+            format!("{}\n{}\n", recipients[0], recipients[1])
+        );
+        let metadata = fs::metadata(&path).expect("recipients file exists");
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+        drop(encrypter);
+        assert!(!path.exists());
+        assert!(AgeEncrypter::new(&[], false).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use super::{Backend, BackendKind, Encrypt};
+
+/// Plaintext password files that are kept in memory, used in tests
+///
+/// The files in the store directory only need to exist so that the store can be listed,
+/// their contents are looked up by path.
+#[derive(Debug)]
+pub struct Memory {
+    /// Layout of the store, i.e. file extension and recipients file
+    kind: BackendKind,
+    files: Mutex<HashMap<PathBuf, Vec<u8>>>,
+    /// Number of password files that were decrypted
+    decryptions: AtomicUsize,
+}
+
+impl Memory {
+    pub fn new(kind: BackendKind) -> Self {
+        Self {
+            kind,
+            files: Mutex::new(HashMap::new()),
+            decryptions: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the decrypted contents of a password file
+    pub fn insert(&self, file_path: PathBuf, plaintext: impl Into<Vec<u8>>) {
+        self.files
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .insert(file_path, plaintext.into());
+    }
+
+    /// Returns how many password files were decrypted so far
+    pub fn decryptions(&self) -> usize {
+        self.decryptions.load(Ordering::Relaxed)
+    }
+}
+
+impl Backend for Memory {
+    fn kind(&self) -> BackendKind {
+        self.kind
+    }
+
+    /// Files without contents cannot be decrypted
+    fn decrypt(&self, file_path: &Path) -> Result<Vec<u8>, String> {
+        self.decryptions.fetch_add(1, Ordering::Relaxed);
+        self.files
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .get(file_path)
+            .cloned()
+            .ok_or_else(|| "no secret key".to_owned())
+    }
+
+    /// The contents were written by [`Plaintext`]
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(ciphertext.to_vec())
+    }
+
+    fn encrypter(&self, _recipients: &[String]) -> Result<Box<dyn Encrypt>, String> {
+        Ok(Box::new(Plaintext))
+    }
+}
+
+/// Leaves the plaintext as it is
+struct Plaintext;
+
+impl Encrypt for Plaintext {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(plaintext.to_vec())
+    }
+}
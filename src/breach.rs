@@ -0,0 +1,98 @@
+use sha1::{Digest, Sha1};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Downloaded list of breached password hashes of Have I Been Pwned
+///
+/// Every line holds the uppercase SHA-1 hash of a password and how often it was seen,
+/// like `7C4A8D09CA3762AF61E59520943DC26494F8941B:24230577`. The lines are sorted by hash,
+/// so a password is looked up with a binary search over the file without reading it.
+pub struct BreachList {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl BreachList {
+    pub fn open(file_path: &Path) -> Result<Self, String> {
+        let file = File::open(file_path).map_err(|e| {
+            format!(
+                "breach list {} could not be opened: {e}",
+                file_path.display()
+            )
+        })?;
+        let len = file.metadata().map_err(|e| e.to_string())?.len();
+        Ok(Self {
+            // Only single lines are read after each seek
+            reader: BufReader::with_capacity(256, file),
+            len,
+        })
+    }
+
+    /// Returns how often the password appeared in breaches, `None` if it did not
+    pub fn count(&mut self, password: &str) -> Result<Option<u64>, String> {
+        self.count_hash(&hash(password))
+    }
+
+    /// Returns how often the password with the [`hash`] appeared in breaches
+    pub fn count_hash(&mut self, hash: &str) -> Result<Option<u64>, String> {
+        self.find(hash).map_err(|e| e.to_string())
+    }
+
+    /// Searches the line of the hash, the line that is looked for
+    /// always starts between `low` and `high`
+    fn find(&mut self, hash: &str) -> std::io::Result<Option<u64>> {
+        let (mut low, mut high) = (0, self.len);
+        let mut line = String::new();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let start = self.line_start(middle)?;
+            if start >= high {
+                high = middle;
+                continue;
+            }
+            line.clear();
+            let read = self.reader.read_line(&mut line)? as u64;
+            if read == 0 {
+                high = middle;
+                continue;
+            }
+            let (line_hash, count) = line.trim_end().split_once(':').unwrap_or((&line, ""));
+            match line_hash.to_ascii_uppercase().as_str().cmp(hash) {
+                std::cmp::Ordering::Equal => {
+                    return Ok(Some(count.trim().parse().unwrap_or(1)));
+                }
+                std::cmp::Ordering::Less => low = start + read,
+                std::cmp::Ordering::Greater => high = middle,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Moves to the first line that starts at or after the position and returns its offset
+    fn line_start(&mut self, position: u64) -> std::io::Result<u64> {
+        if position == 0 {
+            self.reader.seek(SeekFrom::Start(0))?;
+            return Ok(0);
+        }
+        self.reader.seek(SeekFrom::Start(position - 1))?;
+        let mut skipped = Vec::new();
+        let read = self.reader.read_until(b'\n', &mut skipped)? as u64;
+        Ok(position - 1 + read)
+    }
+}
+
+/// Uppercase SHA-1 hash of a password as it is written in the breach list
+pub fn hash(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect()
+}
+
+/// Looks up the [`hash`] of a single password in the breach list
+pub fn check(file_path: &Path, hash: &str) -> Result<Option<u64>, String> {
+    BreachList::open(file_path)?.count_hash(hash)
+}
@@ -0,0 +1,83 @@
+use std::{sync::Mutex, thread, time::Duration};
+
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// Time after which copied secrets are removed from the clipboard
+const EXPIRATION_INTERVAL: Duration = Duration::from_secs(45);
+
+/// Destination of copied passwords and IDs
+pub trait Clipboard: Send + Sync {
+    /// Copies the text to the clipboard, expiring text is cleared after some time
+    fn copy(&self, text: &str, expires: bool) -> Result<(), String>;
+}
+
+/// Clipboard of the desktop session
+pub struct SystemClipboard;
+
+impl Clipboard for SystemClipboard {
+    /// Expiring text is only cleared when the clipboard has not changed in the meantime
+    fn copy(&self, text: &str, expires: bool) -> Result<(), String> {
+        let mut clipboard = CLIPBOARD
+            .lock()
+            .expect("another thread holding the lock panicked");
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new().map_err(|e| e.to_string())?);
+        }
+        clipboard
+            .as_mut()
+            .expect("clipboard is initialized")
+            .set_text(text)
+            .map_err(|e| e.to_string())?;
+
+        if expires {
+            let text = text.to_owned();
+            thread::spawn(move || {
+                thread::sleep(EXPIRATION_INTERVAL);
+                clear_if_unchanged(&text);
+            });
+        }
+        Ok(())
+    }
+}
+
+fn clear_if_unchanged(text: &str) {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .expect("another thread holding the lock panicked");
+    if let Some(clipboard) = clipboard.as_mut() {
+        if clipboard.get_text().is_ok_and(|current| current == text) {
+            let _ = clipboard.clear();
+        }
+    }
+}
+
+/// Clipboard that only remembers what was copied
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeClipboard {
+    /// Copied texts and whether they expire, the latest last
+    pub copied: Mutex<Vec<(String, bool)>>,
+}
+
+#[cfg(test)]
+impl FakeClipboard {
+    /// Returns the text that was copied last
+    pub fn contents(&self) -> Option<String> {
+        self.copied
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .last()
+            .map(|(text, _)| text.clone())
+    }
+}
+
+#[cfg(test)]
+impl Clipboard for FakeClipboard {
+    fn copy(&self, text: &str, expires: bool) -> Result<(), String> {
+        self.copied
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .push((text.to_owned(), expires));
+        Ok(())
+    }
+}
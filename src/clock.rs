@@ -0,0 +1,26 @@
+use std::time::SystemTime;
+
+/// Source of the current time, e.g. for one-time passwords
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// Time of the operating system
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Clock that stands still at a given time
+#[cfg(test)]
+pub struct FixedClock(pub SystemTime);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
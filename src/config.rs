@@ -0,0 +1,125 @@
+use anyhow::Context;
+use passepartout::PasswordStore;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+use crate::backend::BackendKind;
+
+/// Settings that are saved between sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Name of the store that was opened last
+    #[serde(default)]
+    pub active_store: Option<String>,
+    #[serde(default)]
+    pub stores: Vec<StoreConfig>,
+    /// Whether the entries of all stores are shown together
+    #[serde(default)]
+    pub unified_view: bool,
+    /// State of the dashboard when all stores are shown together
+    #[serde(default)]
+    pub unified_state: StoreState,
+    #[serde(default)]
+    pub passphrases: Passphrases,
+    /// Downloaded list of breached password hashes of Have I Been Pwned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breach_list: Option<PathBuf>,
+}
+
+/// How passphrases of secret keys are entered and cached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Passphrases {
+    /// Whether passphrases are asked for by partout instead of the pinentry of gpg-agent
+    #[serde(default)]
+    pub loopback_pinentry: bool,
+    /// Whether the passphrase cache of gpg-agent is cleared when partout quits
+    #[serde(default)]
+    pub forget_on_quit: bool,
+    /// Whether the passphrase cache of gpg-agent is cleared when partout is locked
+    #[serde(default)]
+    pub forget_on_lock: bool,
+}
+
+/// A password store directory configured by the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreConfig {
+    pub name: String,
+    pub path: PathBuf,
+    /// Encryption used by the store
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Identity file for decrypting age stores
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identities: Option<PathBuf>,
+    #[serde(default)]
+    pub state: StoreState,
+}
+
+/// State of the dashboard that is remembered per store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoreState {
+    #[serde(default)]
+    pub last_search: String,
+    #[serde(default)]
+    pub expanded_folders: BTreeSet<String>,
+}
+
+impl StoreConfig {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+            backend: BackendKind::default(),
+            identities: None,
+            state: StoreState::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration file, a store in the default location
+    /// is added when no stores are configured
+    pub fn load() -> anyhow::Result<Self> {
+        let mut config = match config_file() {
+            Some(path) if path.exists() => {
+                let contents = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                toml::from_str(&contents)
+                    .with_context(|| format!("failed to parse {}", path.display()))?
+            }
+            _ => Config::default(),
+        };
+        config.ensure_store();
+        Ok(config)
+    }
+
+    /// Adds a store in the default location when no stores are configured
+    pub fn ensure_store(&mut self) {
+        if self.stores.is_empty() {
+            self.stores
+                .push(StoreConfig::new("Default", PasswordStore::get_store_dir()));
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = config_file().context("could not determine configuration directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let contents = toml::to_string_pretty(self)?;
+        fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Returns the index of the store that was opened last
+    pub fn active_index(&self) -> usize {
+        self.active_store
+            .as_ref()
+            .and_then(|name| self.stores.iter().position(|store| &store.name == name))
+            .unwrap_or(0)
+    }
+}
+
+fn config_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("partout").join("config.toml"))
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{
+    backend::{self, Backend},
+    clipboard::{Clipboard, SystemClipboard},
+    clock::{Clock, SystemClock},
+    config::StoreConfig,
+    gpg::{KeyStore, SystemKeyStore},
+};
+
+/// Returns the crypto backend of a configured store
+pub type BackendFor = dyn Fn(&StoreConfig) -> Arc<dyn Backend> + Send + Sync;
+
+/// Connections of the app to the outside world, replaced by fakes in tests
+#[derive(Clone)]
+pub struct Environment {
+    pub backend: Arc<BackendFor>,
+    pub clipboard: Arc<dyn Clipboard>,
+    pub clock: Arc<dyn Clock>,
+    pub keys: Arc<dyn KeyStore>,
+    /// Whether changes to the configuration are written to the configuration file
+    pub save_config: bool,
+}
+
+impl Environment {
+    /// Keyring, clipboard and clock of the desktop session
+    pub fn system() -> Self {
+        Self {
+            backend: Arc::new(backend::for_store),
+            clipboard: Arc::new(SystemClipboard),
+            clock: Arc::new(SystemClock),
+            keys: Arc::new(SystemKeyStore),
+            save_config: true,
+        }
+    }
+}
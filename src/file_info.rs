@@ -0,0 +1,45 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use crate::{
+    backend::BackendKind,
+    git::{self, Commit},
+    pgp::{self, KeyId},
+    recipients,
+    store::Entry,
+};
+
+/// What is known about a password file without decrypting it
+#[derive(Debug, Clone)]
+pub struct FileInfo {
+    /// Size in bytes
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Keys the file is actually encrypted for according to its packet headers,
+    /// `None` for age files which do not name their recipients
+    pub key_ids: Option<Result<Vec<KeyId>, String>>,
+    /// Recipients file that the entry should be encrypted for
+    pub recipients_file: Option<PathBuf>,
+    /// Last commit that changed the file, `None` if the store is no git repository
+    pub last_commit: Option<Result<Option<Commit>, String>>,
+}
+
+/// Reads the metadata, packet headers and last commit of the password file of the entry
+pub fn read(entry: &Entry) -> Result<FileInfo, String> {
+    let file_path = entry.file_path();
+    let metadata = fs::metadata(&file_path).map_err(|e| e.to_string())?;
+    let backend = entry.store.backend.as_ref();
+    let folder = entry
+        .pass_id
+        .rsplit_once('/')
+        .map(|(folder, _)| folder)
+        .unwrap_or_default();
+    Ok(FileInfo {
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+        key_ids: (backend.kind() == BackendKind::Gpg)
+            .then(|| pgp::recipients(&file_path).map_err(|e| e.to_string())),
+        recipients_file: recipients::governing_file(backend, &entry.store.dir, folder),
+        last_commit: git::is_repository(&entry.store.dir)
+            .then(|| git::last_commit(&entry.store.dir, &file_path)),
+    })
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Commit that changed a password file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    pub author: String,
+    pub time: SystemTime,
+    pub subject: String,
+    /// Path of the file in the commit relative to the store, differs after the file was moved
+    pub path: PathBuf,
+}
+
+impl Commit {
+    /// Abbreviated hash like git shows it
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+/// Verification status of a commit signature as `git log` reports it with `%G?`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signature {
+    Unsigned,
+    Good,
+    /// Good signature of a key whose validity is unknown
+    Untrusted,
+    Bad,
+    /// Good signature that has expired or was made by a key that has expired
+    Expired,
+    /// Good signature made by a revoked key
+    Revoked,
+    /// The signature cannot be checked, usually because the key is missing
+    Unverifiable,
+}
+
+impl Signature {
+    fn from_code(code: &str) -> Self {
+        match code {
+            "G" => Signature::Good,
+            "U" => Signature::Untrusted,
+            "B" => Signature::Bad,
+            "X" | "Y" => Signature::Expired,
+            "R" => Signature::Revoked,
+            "E" => Signature::Unverifiable,
+            _ => Signature::Unsigned,
+        }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Signature::Unsigned => write!(f, "unsigned"),
+            Signature::Good => write!(f, "good signature"),
+            Signature::Untrusted => write!(f, "good signature of an untrusted key"),
+            Signature::Bad => write!(f, "bad signature"),
+            Signature::Expired => write!(f, "expired signature"),
+            Signature::Revoked => write!(f, "signature of a revoked key"),
+            Signature::Unverifiable => write!(f, "signature cannot be checked"),
+        }
+    }
+}
+
+/// How a commit changed a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(PathBuf),
+    Modified(PathBuf),
+    Deleted(PathBuf),
+    /// Moved from the first to the second path
+    Renamed(PathBuf, PathBuf),
+}
+
+impl Change {
+    /// Path of the file after the commit, or before it when it was deleted
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added(path)
+            | Change::Modified(path)
+            | Change::Deleted(path)
+            | Change::Renamed(_, path) => path,
+        }
+    }
+}
+
+/// Commit of the store with the files it changed
+#[derive(Debug, Clone)]
+pub struct StoreCommit {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    pub time: SystemTime,
+    pub subject: String,
+    pub signature: Signature,
+    /// Name of the signer, empty for unsigned commits
+    pub signer: String,
+    /// Paths are relative to the store directory
+    pub changes: Vec<Change>,
+}
+
+impl StoreCommit {
+    /// Abbreviated hash like git shows it
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+}
+
+/// Whether the password store is a git repository like `pass git init` creates it
+pub fn is_repository(store_dir: &Path) -> bool {
+    store_dir.join(".git").exists()
+}
+
+/// Commits the given files, changes to other files are left alone
+pub fn commit(store_dir: &Path, paths: &[PathBuf], message: &str) -> Result<(), String> {
+    run(store_dir, ["add", "--"].map(OsStr::new), paths)?;
+    run(
+        store_dir,
+        ["commit", "--quiet", "-m", message, "--"].map(OsStr::new),
+        paths,
+    )?;
+    Ok(())
+}
+
+/// Returns when each file of the store was last changed by a commit,
+/// files that were never committed are left out
+pub fn last_changes(store_dir: &Path) -> Result<HashMap<PathBuf, SystemTime>, String> {
+    let log = run(
+        store_dir,
+        ["log", "-z", "--relative", "--name-only", "--format=%x1e%ct"].map(OsStr::new),
+        &[],
+    )?;
+    let mut changes = HashMap::new();
+    for (timestamp, files) in records(&log) {
+        let time = UNIX_EPOCH + Duration::from_secs(timestamp.trim().parse().unwrap_or_default());
+        for file in files {
+            // The log starts with the newest commit
+            changes.entry(store_dir.join(file)).or_insert(time);
+        }
+    }
+    Ok(changes)
+}
+
+/// Returns the commits that changed the file, newest first,
+/// following it across renames and leaving out the commit that deleted it
+pub fn file_history(store_dir: &Path, file_path: &Path) -> Result<Vec<Commit>, String> {
+    file_log(store_dir, file_path, &["--follow", "--diff-filter=d"])
+}
+
+/// Returns the last commit that changed the file, `None` if it was never committed
+pub fn last_commit(store_dir: &Path, file_path: &Path) -> Result<Option<Commit>, String> {
+    file_log(store_dir, file_path, &["--max-count=1"]).map(|commits| commits.into_iter().next())
+}
+
+fn file_log(store_dir: &Path, file_path: &Path, options: &[&str]) -> Result<Vec<Commit>, String> {
+    let args = ["log", "-z", "--relative", "--name-only"]
+        .iter()
+        .chain(options)
+        .chain(&["--format=%x1e%H%x1f%an%x1f%ct%x1f%s", "--"])
+        .map(OsStr::new);
+    let log = run(store_dir, args, &[file_path.to_path_buf()])?;
+    let commits = records(&log)
+        .map(|(header, mut files)| {
+            let mut fields = header.split('\x1f');
+            let mut field = || fields.next().unwrap_or_default().to_owned();
+            let (hash, author, time, subject) = (field(), field(), field(), field());
+            Commit {
+                hash,
+                author,
+                time: UNIX_EPOCH + Duration::from_secs(time.parse().unwrap_or_default()),
+                subject,
+                path: files.next().map(PathBuf::from).unwrap_or_default(),
+            }
+        })
+        .collect();
+    Ok(commits)
+}
+
+/// Returns all commits of the store, newest first,
+/// with the files they changed and the verification status of their signatures
+pub fn log(store_dir: &Path) -> Result<Vec<StoreCommit>, String> {
+    let log = run(
+        store_dir,
+        [
+            "log",
+            "-z",
+            "--relative",
+            "--name-status",
+            "--format=%x1e%H%x1f%an%x1f%ae%x1f%ct%x1f%G?%x1f%GS%x1f%s",
+        ]
+        .map(OsStr::new),
+        &[],
+    )?;
+    let commits = records(&log)
+        .map(|(header, mut files)| {
+            let mut fields = header.split('\x1f');
+            let mut field = || fields.next().unwrap_or_default().to_owned();
+            let (hash, author, email, time) = (field(), field(), field(), field());
+            let (signature, signer, subject) = (field(), field(), field());
+            StoreCommit {
+                hash,
+                author,
+                email,
+                time: UNIX_EPOCH + Duration::from_secs(time.parse().unwrap_or_default()),
+                subject,
+                signature: Signature::from_code(&signature),
+                signer,
+                changes: std::iter::from_fn(|| parse_change(&mut files)).collect(),
+            }
+        })
+        .collect();
+    Ok(commits)
+}
+
+/// Parses the next change of `--name-status -z` like `M\0path` or `R100\0old\0new`
+fn parse_change<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Option<Change> {
+    let status = fields.next()?;
+    let path = PathBuf::from(fields.next()?);
+    Some(match status.chars().next()? {
+        'A' => Change::Added(path),
+        // Copies name the source first
+        'C' => Change::Added(PathBuf::from(fields.next()?)),
+        'D' => Change::Deleted(path),
+        'R' => Change::Renamed(path, PathBuf::from(fields.next()?)),
+        _ => Change::Modified(path),
+    })
+}
+
+/// Returns the contents of a file as it was after the commit,
+/// the path is relative to the store directory
+pub fn show(store_dir: &Path, hash: &str, path: &Path) -> Result<Vec<u8>, String> {
+    let object = format!("{hash}:./{}", path.display());
+    output(store_dir, ["show", &object].map(OsStr::new), &[])
+}
+
+/// Splits the output of `git log -z` whose format starts with `%x1e` into the
+/// header of each commit and the NUL separated fields of the files it changed,
+/// which keeps paths with unusual characters from being quoted
+fn records(log: &str) -> impl Iterator<Item = (&str, impl Iterator<Item = &str>)> {
+    log.split('\x1e').skip(1).map(|record| {
+        let (header, files) = record.split_once('\0').unwrap_or((record, ""));
+        let files = files
+            .trim_start_matches('\n')
+            .split('\0')
+            .filter(|field| !field.is_empty());
+        (header, files)
+    })
+}
+
+/// Runs git in the store directory and returns its output
+fn run<'a>(
+    store_dir: &Path,
+    args: impl IntoIterator<Item = &'a OsStr>,
+    paths: &[PathBuf],
+) -> Result<String, String> {
+    output(store_dir, args, paths).map(|stdout| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Runs git in the store directory and returns its raw output
+fn output<'a>(
+    store_dir: &Path,
+    args: impl IntoIterator<Item = &'a OsStr>,
+    paths: &[PathBuf],
+) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(store_dir)
+        .args(args)
+        .args(paths)
+        .output()
+        .map_err(|e| format!("git could not be run: {e}"))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Runs git in the directory and panics when it fails
+    fn git(dir: &Path, args: &[&str], date: Option<&str>) {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(dir).args(args);
+        if let Some(date) = date {
+            command
+                .env("GIT_AUTHOR_DATE", date)
+                .env("GIT_COMMITTER_DATE", date);
+        }
+        let status = command.status().expect("git can be run");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    /// Writes the files and commits them at the given date
+    fn commit(dir: &Path, files: &[(&str, &str)], date: &str) {
+        for (file, contents) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().expect("file is in the repository"))
+                .expect("folder can be created");
+            fs::write(path, contents).expect("file can be written");
+        }
+        git(dir, &["add", "--all"], None);
+        git(
+            dir,
+            &["commit", "--quiet", "-m", "Change entries"],
+            Some(date),
+        );
+    }
+
+    #[test]
+    fn files_are_dated_by_their_last_commit() {
+        let dir = tempfile::tempdir().expect("temporary directory can be created");
+        let dir = dir.path();
+        git(dir, &["init", "--quiet"], None);
+        git(dir, &["config", "user.name", "Alice"], None);
+        git(dir, &["config", "user.email", "alice@example.com"], None);
+        commit(
+            dir,
+            &[("reisen/münchen.age", ""), ("bank.age", "")],
+            "2024-03-01T10:00:00Z",
+        );
+        commit(dir, &[("bank.age", "changed")], "2024-03-05T10:00:00Z");
+        fs::write(dir.join("new.age"), "").expect("file can be written");
+
+        let changes = last_changes(dir).expect("log can be read");
+        let at = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(
+            changes.get(&dir.join("reisen/münchen.age")).copied(),
+            at(1_709_287_200)
+        );
+        assert_eq!(
+            changes.get(&dir.join("bank.age")).copied(),
+            at(1_709_632_800)
+        );
+        assert_eq!(changes.len(), 2);
+    }
+}
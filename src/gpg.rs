@@ -0,0 +1,452 @@
+use gpgme::{
+    Context, EncryptFlags, ExportMode, Key, PassphraseRequest, PinentryMode, Protocol, Validity,
+};
+use std::{
+    collections::HashSet, fmt, fs, io::Write, path::Path, process::Command, time::SystemTime,
+};
+
+use crate::{backend::Encrypt, pinentry};
+
+/// Key found in the keyring for a recipient of a `.gpg-id` file
+#[derive(Debug, Clone)]
+pub struct KeyInfo {
+    /// Recipient as written in the `.gpg-id` file
+    pub recipient: String,
+    pub fingerprint: String,
+    pub user_ids: Vec<String>,
+    /// Key IDs of the primary key and all subkeys
+    pub key_ids: Vec<String>,
+    pub problem: Option<KeyProblem>,
+}
+
+/// Reason why encrypting for a recipient fails or should not be relied on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyProblem {
+    /// No key for the recipient is in the keyring
+    Missing,
+    Expired,
+    Revoked,
+    Disabled,
+    Invalid,
+    /// The key has no subkey that can be used for encryption
+    NoEncryptionKey,
+    /// None of the user IDs of the key is trusted
+    Untrusted,
+}
+
+impl fmt::Display for KeyProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            KeyProblem::Missing => "missing from the keyring",
+            KeyProblem::Expired => "expired",
+            KeyProblem::Revoked => "revoked",
+            KeyProblem::Disabled => "disabled",
+            KeyProblem::Invalid => "invalid",
+            KeyProblem::NoEncryptionKey => "not usable for encryption",
+            KeyProblem::Untrusted => "not trusted",
+        };
+        write!(f, "{text}")
+    }
+}
+
+impl KeyProblem {
+    /// Whether password files cannot be encrypted for the key
+    pub fn is_fatal(self) -> bool {
+        self != KeyProblem::Untrusted
+    }
+
+    fn of(key: &Key) -> Option<Self> {
+        if key.is_revoked() {
+            Some(KeyProblem::Revoked)
+        } else if key.is_expired() {
+            Some(KeyProblem::Expired)
+        } else if key.is_disabled() {
+            Some(KeyProblem::Disabled)
+        } else if key.is_invalid() {
+            Some(KeyProblem::Invalid)
+        } else if !key.can_encrypt() {
+            let expired = key
+                .subkeys()
+                .any(|subkey| subkey.can_encrypt() && subkey.is_expired());
+            Some(if expired {
+                KeyProblem::Expired
+            } else {
+                KeyProblem::NoEncryptionKey
+            })
+        } else if !key.user_ids().any(|user_id| {
+            matches!(
+                user_id.validity(),
+                Validity::Marginal | Validity::Full | Validity::Ultimate
+            )
+        }) {
+            Some(KeyProblem::Untrusted)
+        } else {
+            None
+        }
+    }
+}
+
+impl KeyInfo {
+    fn new(recipient: &str, key: &Key) -> Self {
+        Self {
+            recipient: recipient.to_owned(),
+            fingerprint: key.fingerprint().unwrap_or_default().to_owned(),
+            user_ids: key
+                .user_ids()
+                .filter_map(|user_id| user_id.id().ok().map(str::to_owned))
+                .collect(),
+            key_ids: key
+                .subkeys()
+                .filter_map(|subkey| subkey.id().ok().map(str::to_owned))
+                .collect(),
+            problem: KeyProblem::of(key),
+        }
+    }
+
+    /// Whether the key is usable for encryption
+    pub fn can_encrypt(&self) -> bool {
+        self.problem.is_none_or(|problem| !problem.is_fatal())
+    }
+}
+
+fn context() -> gpgme::Result<Context> {
+    Context::from_protocol(Protocol::OpenPgp)
+}
+
+/// Looks up the keys of the given recipients, recipients without a key are left out
+pub fn resolve_keys(recipients: &[String]) -> gpgme::Result<Vec<KeyInfo>> {
+    let mut ctx = context()?;
+    let mut keys = Vec::new();
+    for recipient in recipients {
+        let found = ctx.find_keys([recipient.as_str()])?.flatten().next();
+        if let Some(key) = found {
+            keys.push(KeyInfo::new(recipient, &key));
+        }
+    }
+    Ok(keys)
+}
+
+/// Keys of the keyring that the recipients of the stores are looked up in
+pub trait KeyStore: Send + Sync {
+    /// Looks up the keys of the given recipients, recipients without a key are left out
+    fn resolve_keys(&self, recipients: &[String]) -> Result<Vec<KeyInfo>, String>;
+    /// Lists all public keys together with the availability of their secret keys
+    fn keyring(&self) -> Result<Vec<KeyringKey>, String>;
+    fn import_keys(&self, file_path: &Path) -> Result<Imported, String>;
+    fn export_key(&self, fingerprint: &str, file_path: &Path) -> Result<(), String>;
+}
+
+/// Keyring of GnuPG
+pub struct SystemKeyStore;
+
+impl KeyStore for SystemKeyStore {
+    fn resolve_keys(&self, recipients: &[String]) -> Result<Vec<KeyInfo>, String> {
+        resolve_keys(recipients).map_err(|e| e.to_string())
+    }
+
+    fn keyring(&self) -> Result<Vec<KeyringKey>, String> {
+        keyring().map_err(|e| e.to_string())
+    }
+
+    fn import_keys(&self, file_path: &Path) -> Result<Imported, String> {
+        import_keys(file_path)
+    }
+
+    fn export_key(&self, fingerprint: &str, file_path: &Path) -> Result<(), String> {
+        export_key(fingerprint, file_path)
+    }
+}
+
+/// Keyring that only holds the keys it was given
+///
+/// Key files hold one key per line, its fingerprint followed by its user ID.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeKeyStore {
+    pub keys: std::sync::Mutex<Vec<KeyringKey>>,
+}
+
+#[cfg(test)]
+impl FakeKeyStore {
+    fn keys(&self) -> std::sync::MutexGuard<'_, Vec<KeyringKey>> {
+        self.keys
+            .lock()
+            .expect("another thread holding the lock panicked")
+    }
+}
+
+#[cfg(test)]
+impl KeyStore for FakeKeyStore {
+    /// Recipients are found by fingerprint or by a part of a user ID, like GnuPG does
+    fn resolve_keys(&self, recipients: &[String]) -> Result<Vec<KeyInfo>, String> {
+        let keys = self.keys();
+        Ok(recipients
+            .iter()
+            .filter_map(|recipient| {
+                let key = keys.iter().find(|key| {
+                    key.fingerprint == *recipient
+                        || key
+                            .user_ids
+                            .iter()
+                            .any(|id| id.contains(recipient.as_str()))
+                })?;
+                Some(KeyInfo {
+                    recipient: recipient.clone(),
+                    fingerprint: key.fingerprint.clone(),
+                    user_ids: key.user_ids.clone(),
+                    key_ids: vec![
+                        key.fingerprint[key.fingerprint.len().saturating_sub(16)..].to_owned()
+                    ],
+                    problem: key.problem,
+                })
+            })
+            .collect())
+    }
+
+    fn keyring(&self) -> Result<Vec<KeyringKey>, String> {
+        Ok(self.keys().clone())
+    }
+
+    fn import_keys(&self, file_path: &Path) -> Result<Imported, String> {
+        let contents = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+        let mut keys = self.keys();
+        let mut imported = Imported {
+            considered: 0,
+            imported: 0,
+            unchanged: 0,
+            secret_imported: 0,
+        };
+        for line in contents.lines() {
+            let (fingerprint, user_id) = line
+                .split_once(' ')
+                .ok_or_else(|| "no valid OpenPGP data found".to_owned())?;
+            imported.considered += 1;
+            if keys.iter().any(|key| key.fingerprint == fingerprint) {
+                imported.unchanged += 1;
+                continue;
+            }
+            imported.imported += 1;
+            keys.push(KeyringKey {
+                fingerprint: fingerprint.to_owned(),
+                user_ids: vec![user_id.to_owned()],
+                expires: None,
+                can_encrypt: true,
+                can_sign: true,
+                can_certify: true,
+                can_authenticate: false,
+                secret: false,
+                problem: None,
+            });
+        }
+        Ok(imported)
+    }
+
+    fn export_key(&self, fingerprint: &str, file_path: &Path) -> Result<(), String> {
+        let keys = self.keys();
+        let key = keys
+            .iter()
+            .find(|key| key.fingerprint == fingerprint)
+            .ok_or_else(|| "no public key found".to_owned())?;
+        let user_id = key.user_ids.first().map_or("", String::as_str);
+        fs::write(file_path, format!("{fingerprint} {user_id}\n")).map_err(|e| e.to_string())
+    }
+}
+
+/// Lists all public keys in the keyring
+pub fn list_keys() -> gpgme::Result<Vec<KeyInfo>> {
+    let mut ctx = context()?;
+    let keys = ctx
+        .keys()?
+        .flatten()
+        .map(|key| KeyInfo::new(key.fingerprint().unwrap_or_default(), &key))
+        .collect();
+    Ok(keys)
+}
+
+/// Key of the keyring with the details shown in the key management
+#[derive(Debug, Clone)]
+pub struct KeyringKey {
+    pub fingerprint: String,
+    pub user_ids: Vec<String>,
+    /// Expiration of the primary key
+    pub expires: Option<SystemTime>,
+    pub can_encrypt: bool,
+    pub can_sign: bool,
+    pub can_certify: bool,
+    pub can_authenticate: bool,
+    /// Whether the secret key is in the keyring
+    pub secret: bool,
+    pub problem: Option<KeyProblem>,
+}
+
+/// Counts of an import of keys
+#[derive(Debug, Clone, Copy)]
+pub struct Imported {
+    pub considered: u32,
+    pub imported: u32,
+    pub unchanged: u32,
+    pub secret_imported: u32,
+}
+
+/// Lists all public keys in the keyring together with the availability of their secret keys
+pub fn keyring() -> gpgme::Result<Vec<KeyringKey>> {
+    let mut ctx = context()?;
+    let secret: HashSet<String> = ctx
+        .secret_keys()?
+        .flatten()
+        .filter_map(|key| key.fingerprint().ok().map(str::to_owned))
+        .collect();
+    let keys = ctx
+        .keys()?
+        .flatten()
+        .map(|key| {
+            let fingerprint = key.fingerprint().unwrap_or_default().to_owned();
+            KeyringKey {
+                secret: secret.contains(&fingerprint),
+                fingerprint,
+                user_ids: key
+                    .user_ids()
+                    .filter_map(|user_id| user_id.id().ok().map(str::to_owned))
+                    .collect(),
+                expires: key
+                    .primary_key()
+                    .and_then(|primary| primary.expiration_time()),
+                can_encrypt: key.can_encrypt(),
+                can_sign: key.can_sign(),
+                can_certify: key.can_certify(),
+                can_authenticate: key.can_authenticate(),
+                problem: KeyProblem::of(&key),
+            }
+        })
+        .collect();
+    Ok(keys)
+}
+
+/// Imports the keys of an armored or binary key file into the keyring
+pub fn import_keys(file_path: &Path) -> Result<Imported, String> {
+    let data = fs::read(file_path).map_err(|e| e.to_string())?;
+    let result = context()
+        .and_then(|mut ctx| ctx.import(&data))
+        .map_err(|e| e.to_string())?;
+    Ok(Imported {
+        considered: result.considered(),
+        imported: result.imported(),
+        unchanged: result.unchanged(),
+        secret_imported: result.secret_imported(),
+    })
+}
+
+/// Writes the armored public key with the given fingerprint to a file
+pub fn export_key(fingerprint: &str, file_path: &Path) -> Result<(), String> {
+    let mut ctx = context().map_err(|e| e.to_string())?;
+    ctx.set_armor(true);
+    let key = ctx.get_key(fingerprint).map_err(|e| e.to_string())?;
+    let mut armored = Vec::new();
+    ctx.export_keys([&key], ExportMode::empty(), &mut armored)
+        .map_err(|e| e.to_string())?;
+    fs::write(file_path, armored).map_err(|e| e.to_string())
+}
+
+/// Makes gpg-agent forget all cached passphrases by reloading it
+pub fn forget_passphrases() -> Result<(), String> {
+    let output = Command::new("gpgconf")
+        .args(["--reload", "gpg-agent"])
+        .output()
+        .map_err(|e| format!("gpgconf could not be run: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+/// Decrypts a password file, the passphrase is asked for in the app
+/// when the loopback pinentry is enabled
+pub fn decrypt(file_path: &Path) -> Result<Vec<u8>, String> {
+    let ciphertext = fs::read(file_path).map_err(|e| e.to_string())?;
+    decrypt_bytes(&ciphertext)
+}
+
+/// Decrypts data that is not read from a file
+pub fn decrypt_bytes(ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut plaintext = Vec::new();
+    let mut ctx = context().map_err(|e| e.to_string())?;
+    if !pinentry::is_enabled() {
+        ctx.decrypt(ciphertext, &mut plaintext)
+            .map_err(|e| e.to_string())?;
+        return Ok(plaintext);
+    }
+
+    ctx.set_pinentry_mode(PinentryMode::Loopback)
+        .map_err(|e| format!("loopback pinentry is not supported: {e}"))?;
+    let mut attempt = 0;
+    let provider = move |request: PassphraseRequest<'_>, out: &mut dyn Write| {
+        attempt += 1;
+        if attempt > pinentry::MAX_ATTEMPTS {
+            return Err(gpgme::Error::BAD_PASSPHRASE);
+        }
+        let key = request
+            .user_id_hint()
+            .map(|hint| hint.split_once(' ').map_or(hint, |(_, user_id)| user_id))
+            .unwrap_or("unknown key")
+            .to_owned();
+        let passphrase = pinentry::ask(key, attempt, request.prev_attempt_failed)
+            .ok_or(gpgme::Error::CANCELED)?;
+        out.write_all(passphrase.as_bytes())?;
+        out.write_all(b"\n")?;
+        Ok(())
+    };
+    ctx.with_passphrase_provider(provider, |ctx| ctx.decrypt(ciphertext, &mut plaintext))
+        .map_err(|e| match e.code() {
+            code if code == gpgme::Error::BAD_PASSPHRASE.code() => "bad passphrase".to_owned(),
+            code if code == gpgme::Error::CANCELED.code() => {
+                "passphrase entry was cancelled".to_owned()
+            }
+            _ => e.to_string(),
+        })?;
+    Ok(plaintext)
+}
+
+/// Encrypts password files for a fixed set of recipients
+pub struct Encrypter {
+    ctx: Context,
+    keys: Vec<Key>,
+}
+
+impl Encrypter {
+    /// Looks up a usable encryption key for every recipient
+    pub fn new(recipients: &[String]) -> Result<Self, String> {
+        let mut ctx = context().map_err(|e| e.to_string())?;
+        let mut keys = Vec::new();
+        for recipient in recipients {
+            let key = ctx
+                .find_keys([recipient.as_str()])
+                .map_err(|e| e.to_string())?
+                .flatten()
+                .find(|key| KeyInfo::new(recipient, key).can_encrypt())
+                .ok_or_else(|| format!("no usable key found for {recipient}"))?;
+            keys.push(key);
+        }
+        Ok(Self { ctx, keys })
+    }
+
+    /// Whether the output is ASCII armored instead of binary
+    pub fn set_armor(&mut self, armor: bool) {
+        self.ctx.set_armor(armor);
+    }
+}
+
+impl Encrypt for Encrypter {
+    fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut ciphertext = Vec::new();
+        self.ctx
+            .encrypt_with_flags(
+                &self.keys,
+                plaintext,
+                &mut ciphertext,
+                EncryptFlags::ALWAYS_TRUST | EncryptFlags::NO_ENCRYPT_TO,
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(ciphertext)
+    }
+}
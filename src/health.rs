@@ -0,0 +1,316 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    backend::Backend,
+    breach::BreachList,
+    git, otp, recipients,
+    service::Progress,
+    strength::{self, Strength},
+};
+
+/// Passwords that were not changed for longer are reported as old
+pub const MAX_AGE: Duration = Duration::from_secs(365 * 86_400);
+
+/// Fields that hold the login when it is not on the second line
+const LOGIN_FIELDS: &[&str] = &["login", "username", "user", "email"];
+
+/// Where the age of a password comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeSource {
+    /// Last commit that changed the password file
+    Git,
+    /// Modification time of the password file
+    Modified,
+}
+
+/// Problem with an entry that the security team wants to know about
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    Weak {
+        strength: Strength,
+        bits: f64,
+    },
+    /// The password appeared the given number of times in data breaches
+    Breached(u64),
+    /// The same password is used by the other entries
+    Reused(Vec<String>),
+    Old {
+        days: u64,
+        source: AgeSource,
+    },
+    MissingLogin,
+}
+
+impl Issue {
+    pub fn kind(&self) -> IssueKind {
+        match self {
+            Issue::Weak { .. } => IssueKind::Weak,
+            Issue::Breached(_) => IssueKind::Breached,
+            Issue::Reused(_) => IssueKind::Reused,
+            Issue::Old { .. } => IssueKind::Old,
+            Issue::MissingLogin => IssueKind::MissingLogin,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Weak { strength, bits } => {
+                write!(f, "{strength} password, about {bits:.0} bits")
+            }
+            Issue::Breached(count) => write!(f, "appeared {count} times in data breaches"),
+            Issue::Reused(others) => write!(f, "same password as {}", others.join(", ")),
+            Issue::Old { days, source } => {
+                let source = match source {
+                    AgeSource::Git => "committed",
+                    AgeSource::Modified => "modified",
+                };
+                write!(f, "last {source} {days} days ago")
+            }
+            Issue::MissingLogin => write!(f, "no login"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    Weak,
+    Breached,
+    Reused,
+    Old,
+    MissingLogin,
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 5] = [
+        IssueKind::Weak,
+        IssueKind::Breached,
+        IssueKind::Reused,
+        IssueKind::Old,
+        IssueKind::MissingLogin,
+    ];
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::Weak => write!(f, "Weak"),
+            IssueKind::Breached => write!(f, "Breached"),
+            IssueKind::Reused => write!(f, "Reused"),
+            IssueKind::Old => write!(f, "Old"),
+            IssueKind::MissingLogin => write!(f, "Missing login"),
+        }
+    }
+}
+
+/// Entry with at least one issue
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub pass_id: String,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Number of password entries that were checked
+    pub audited: usize,
+    pub findings: Vec<Finding>,
+    /// Pass IDs with the reason why they could not be decrypted
+    pub failures: Vec<(String, String)>,
+    /// Whether the passwords were looked up in a breach list
+    pub breaches_checked: bool,
+    /// Why the breach list could not be used
+    pub breach_error: Option<String>,
+}
+
+impl Report {
+    /// Pass IDs of the entries whose password was found in the breach list
+    pub fn breached(&self) -> impl Iterator<Item = &str> {
+        self.findings
+            .iter()
+            .filter(|finding| {
+                finding
+                    .issues
+                    .iter()
+                    .any(|issue| issue.kind() == IssueKind::Breached)
+            })
+            .map(|finding| finding.pass_id.as_str())
+    }
+
+    /// Number of entries with an issue of the given kind
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.issues.iter().any(|issue| issue.kind() == kind))
+            .count()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Progress { done: usize, total: usize },
+    Finished(Report),
+}
+
+/// Fields of a password file that are audited
+struct Audited {
+    pass_id: String,
+    password: String,
+    has_login: bool,
+    age: Option<(Duration, AgeSource)>,
+}
+
+/// Decrypts all password files of the store and checks their passwords,
+/// also against the breach list if one is given,
+/// nothing is reported when the scan is cancelled
+pub fn scan(
+    backend: &dyn Backend,
+    store_dir: &Path,
+    now: SystemTime,
+    breach_list: Option<&Path>,
+    progress: &Progress<Event>,
+) {
+    let files = recipients::password_files(backend, store_dir);
+    let total = files.len();
+    // Without history the modification time is the best guess
+    let commits = if git::is_repository(store_dir) {
+        git::last_changes(store_dir).unwrap_or_default()
+    } else {
+        HashMap::new()
+    };
+
+    let mut report = Report::default();
+    let mut breach_list = match breach_list.map(BreachList::open) {
+        Some(Ok(list)) => Some(list),
+        Some(Err(e)) => {
+            report.breach_error = Some(e);
+            None
+        }
+        None => None,
+    };
+    report.breaches_checked = breach_list.is_some();
+    let mut audited = Vec::with_capacity(total);
+    for (done, file_path) in files.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            return;
+        }
+        progress.report(Event::Progress { done, total });
+        let pass_id = file_path
+            .strip_prefix(store_dir)
+            .unwrap_or(&file_path)
+            .with_extension("")
+            .to_string_lossy()
+            .into_owned();
+        let contents = match backend.decrypt(&file_path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                report.failures.push((pass_id, e));
+                continue;
+            }
+        };
+        // Attachments are no password entries
+        let Ok(contents) = String::from_utf8(contents) else {
+            continue;
+        };
+        let changed = commits
+            .get(&file_path)
+            .map(|time| (*time, AgeSource::Git))
+            .or_else(|| {
+                fs::metadata(&file_path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .map(|time| (time, AgeSource::Modified))
+            });
+        let mut lines = contents.lines();
+        let password = lines.next().unwrap_or_default().to_owned();
+        audited.push(Audited {
+            pass_id,
+            password,
+            has_login: has_login(lines),
+            age: changed
+                .and_then(|(time, source)| now.duration_since(time).ok().map(|age| (age, source))),
+        });
+    }
+    report.audited = audited.len();
+
+    let mut by_password: HashMap<&str, Vec<&str>> = HashMap::new();
+    for entry in audited.iter().filter(|entry| !entry.password.is_empty()) {
+        by_password
+            .entry(&entry.password)
+            .or_default()
+            .push(&entry.pass_id);
+    }
+    let mut findings = BTreeMap::new();
+    for entry in &audited {
+        let mut issues = Vec::new();
+        if !entry.password.is_empty() {
+            let bits = strength::entropy(&entry.password);
+            let strength = Strength::of(bits);
+            if strength.is_weak() {
+                issues.push(Issue::Weak { strength, bits });
+            }
+            if let Some(list) = &mut breach_list {
+                match list.count(&entry.password) {
+                    Ok(Some(count)) => issues.push(Issue::Breached(count)),
+                    Ok(None) => (),
+                    Err(e) => {
+                        report.breach_error = Some(e);
+                        report.breaches_checked = false;
+                        breach_list = None;
+                    }
+                }
+            }
+            let others: Vec<String> = by_password[entry.password.as_str()]
+                .iter()
+                .filter(|pass_id| **pass_id != entry.pass_id)
+                .map(|pass_id| pass_id.to_string())
+                .collect();
+            if !others.is_empty() {
+                issues.push(Issue::Reused(others));
+            }
+        }
+        if let Some((age, source)) = entry.age.filter(|(age, _)| *age > MAX_AGE) {
+            issues.push(Issue::Old {
+                days: age.as_secs() / 86_400,
+                source,
+            });
+        }
+        if !entry.has_login {
+            issues.push(Issue::MissingLogin);
+        }
+        if !issues.is_empty() {
+            findings.insert(entry.pass_id.clone(), issues);
+        }
+    }
+    report.findings = findings
+        .into_iter()
+        .map(|(pass_id, issues)| Finding { pass_id, issues })
+        .collect();
+    progress.report(Event::Finished(report));
+}
+
+/// Whether the lines after the password name a login,
+/// either on the second line or in a field like `username:`
+fn has_login<'a>(mut lines: impl Iterator<Item = &'a str>) -> bool {
+    let is_field = |line: &str| {
+        line.split_once(':').is_some_and(|(key, _)| {
+            !key.is_empty() && !key.contains(char::is_whitespace) && !key.contains('/')
+        })
+    };
+    match lines.next() {
+        Some(line) if !line.trim().is_empty() && !otp::is_url(line) && !is_field(line) => true,
+        Some(line) => std::iter::once(line).chain(lines).any(|line| {
+            line.split_once(':').is_some_and(|(key, value)| {
+                LOGIN_FIELDS.contains(&key.trim().to_lowercase().as_str())
+                    && !value.trim().is_empty()
+            })
+        }),
+        None => false,
+    }
+}
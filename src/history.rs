@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use crate::{
+    backend,
+    git::{self, Commit},
+    recipients,
+    store::Entry,
+};
+
+/// Line of a comparison between two versions of a password file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    /// Only in the current version
+    Added(String),
+    /// Only in the old version
+    Removed(String),
+}
+
+/// Commits that changed the password file of the entry, newest first
+pub fn commits(entry: &Entry) -> Result<Vec<Commit>, String> {
+    git::file_history(&entry.store.dir, &entry.file_path())
+}
+
+/// Decrypts the password file of the entry as it was after the commit
+pub fn decrypt(entry: &Entry, commit: &Commit) -> Result<String, String> {
+    let ciphertext = git::show(&entry.store.dir, &commit.hash, &commit.path)?;
+    let plaintext = entry.store.backend.decrypt_bytes(&ciphertext)?;
+    String::from_utf8(plaintext).map_err(|_| "password file is not valid UTF-8".to_owned())
+}
+
+/// Writes the version of the commit back for the current recipients and commits it
+pub fn restore(entry: &Entry, commit: &Commit) -> Result<(), String> {
+    let plaintext = decrypt(entry, commit)?;
+    let backend = entry.store.backend.as_ref();
+    let folder = Path::new(&entry.pass_id)
+        .parent()
+        .map(|folder| folder.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let recipients_file = recipients::governing_file(backend, &entry.store.dir, &folder)
+        .ok_or_else(|| format!("no {} file found", backend.recipients_file()))?;
+    let recipients = recipients::read(&recipients_file).map_err(|e| e.to_string())?;
+    let ciphertext = backend
+        .encrypter(&recipients)?
+        .encrypt(plaintext.as_bytes())?;
+
+    let file_path = entry.file_path();
+    backend::write_atomically(&file_path, &ciphertext)?;
+    git::commit(
+        &entry.store.dir,
+        &[file_path],
+        &format!(
+            "Restore {} to the version of {}",
+            entry.pass_id,
+            commit.short_hash()
+        ),
+    )
+}
+
+/// Compares two versions line by line, following the longest common subsequence
+pub fn diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Length of the longest common subsequence of the remaining lines
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i].to_owned()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_owned()));
+            j += 1;
+        }
+    }
+    lines.extend(
+        old[i..]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    lines.extend(
+        new[j..]
+            .iter()
+            .map(|line| DiffLine::Added(line.to_string())),
+    );
+    lines
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::{hash_map, BTreeSet, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    authenticator::{self, Kind, Token},
+    backend::{self, Backend, Encrypt},
+    otp, recipients,
+    service::Progress,
+    share, store,
+};
+
+mod csv;
+mod kdbx;
+
+/// Password manager exports that can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// CSV export of Bitwarden, KeePassXC, 1Password or a browser
+    Csv,
+    /// KeePass database in the KDBX 3.1 or 4 format
+    Kdbx,
+    /// Unencrypted backup of Aegis or andOTP, which only holds OTP secrets
+    Authenticator,
+    /// Entries that someone shared with the key of the store
+    Bundle,
+}
+
+impl Source {
+    pub const ALL: [Source; 4] = [
+        Source::Csv,
+        Source::Kdbx,
+        Source::Authenticator,
+        Source::Bundle,
+    ];
+
+    /// Whether the file is encrypted and has to be opened with [`Credentials`]
+    pub fn is_encrypted(self) -> bool {
+        self == Source::Kdbx
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Csv => write!(f, "CSV export"),
+            Source::Kdbx => write!(f, "KeePass database (.kdbx)"),
+            Source::Authenticator => write!(f, "Aegis or andOTP backup (.json)"),
+            Source::Bundle => write!(f, "Shared bundle (.asc or .age)"),
+        }
+    }
+}
+
+/// Master password and key file of an encrypted export
+#[derive(Clone, Default)]
+pub struct Credentials {
+    pub password: String,
+    pub key_file: Option<PathBuf>,
+}
+
+/// Entry read from an export, converted to the layout of a pass file
+#[derive(Debug, Clone, Default)]
+pub struct ImportEntry {
+    /// Folder within the export, separated by slashes
+    pub folder: String,
+    pub title: String,
+    pub password: String,
+    pub login: String,
+    pub url: String,
+    pub notes: String,
+    pub otpauth: Option<String>,
+    /// Further fields that are written as `key: value` lines
+    pub fields: Vec<(String, String)>,
+    /// Contents of an attached file or a shared password file,
+    /// which is written as is instead of the fields
+    pub attachment: Option<Vec<u8>>,
+}
+
+impl ImportEntry {
+    /// Path of the entry relative to the target folder
+    pub fn pass_path(&self) -> String {
+        let title = sanitize(&self.title);
+        let title = if title.is_empty() {
+            "untitled".to_owned()
+        } else {
+            title
+        };
+        let folders: Vec<String> = self
+            .folder
+            .split('/')
+            .map(sanitize)
+            .filter(|folder| !folder.is_empty())
+            .collect();
+        if folders.is_empty() {
+            title
+        } else {
+            format!("{}/{title}", folders.join("/"))
+        }
+    }
+
+    /// Contents of the pass file: password, login, fields, OTP URL and notes
+    pub fn contents(&self) -> Vec<u8> {
+        if let Some(attachment) = &self.attachment {
+            return attachment.clone();
+        }
+        let mut lines = vec![self.password.clone(), self.login.clone()];
+        if !self.url.is_empty() {
+            lines.push(format!("url: {}", self.url));
+        }
+        lines.extend(
+            self.fields
+                .iter()
+                .map(|(key, value)| format!("{key}: {}", value.replace('\n', " "))),
+        );
+        if let Some(otpauth) = &self.otpauth {
+            lines.push(otpauth.clone());
+        }
+        if !self.notes.is_empty() {
+            lines.push(self.notes.clone());
+        }
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        contents.into_bytes()
+    }
+}
+
+/// TOTP settings for a base32 secret with the defaults of authenticator apps,
+/// labeled with the title and login
+fn token(secret: &str, title: &str, login: &str) -> Token {
+    let (issuer, name) = if login.is_empty() {
+        ("", title)
+    } else {
+        (title, login)
+    };
+    Token {
+        kind: Kind::Totp,
+        issuer: issuer.to_owned(),
+        name: name.to_owned(),
+        secret: secret
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '=')
+            .collect::<String>()
+            .to_uppercase(),
+        algorithm: "SHA1".to_owned(),
+        digits: 6,
+        period: 30,
+        counter: 0,
+    }
+}
+
+/// Replaces characters that cannot be part of a file name
+fn sanitize(name: &str) -> String {
+    name.trim()
+        .replace(['/', '\\'], "-")
+        .trim_start_matches('.')
+        .to_owned()
+}
+
+/// Why an entry would replace or collide with another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    /// A password file with the same path is already in the store
+    Exists,
+    /// An earlier entry of the export has the same path
+    Duplicate,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Exists => write!(f, "already exists in the store"),
+            Conflict::Duplicate => write!(f, "appears more than once in the export"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedEntry {
+    /// ID of the password file in the store
+    pub pass_id: String,
+    pub entry: ImportEntry,
+    pub conflict: Option<Conflict>,
+    /// Whether the OTP URL is added to the existing entry instead of writing a new one
+    pub append: bool,
+}
+
+/// Entries of an export with their target paths in the store
+#[derive(Debug, Clone)]
+pub struct Plan {
+    /// Name of the format that was recognized
+    pub format: String,
+    pub entries: Vec<PlannedEntry>,
+}
+
+impl Plan {
+    /// Maps the entries to pass IDs below the target folder and detects conflicts
+    pub fn new(
+        format: String,
+        entries: Vec<ImportEntry>,
+        backend: &dyn Backend,
+        store_dir: &Path,
+        folder: &str,
+    ) -> Self {
+        let folder = folder.trim().trim_matches('/');
+        let mut seen = BTreeSet::new();
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let path = entry.pass_path();
+                let pass_id = if folder.is_empty() {
+                    path
+                } else {
+                    format!("{folder}/{path}")
+                };
+                let conflict = if !seen.insert(pass_id.clone()) {
+                    Some(Conflict::Duplicate)
+                } else if store::entry_path(store_dir, &pass_id, backend.extension()).exists() {
+                    Some(Conflict::Exists)
+                } else {
+                    None
+                };
+                PlannedEntry {
+                    pass_id,
+                    entry,
+                    conflict,
+                    append: false,
+                }
+            })
+            .collect();
+        Self { format, entries }
+    }
+
+    /// Adds the tokens of an authenticator backup to the entries named after their issuer,
+    /// tokens without a matching entry become new entries below the target folder
+    fn with_tokens(
+        format: String,
+        tokens: Vec<Token>,
+        backend: &dyn Backend,
+        store_dir: &Path,
+        folder: &str,
+    ) -> Self {
+        let pass_ids: Vec<String> = store::password_infos(store_dir, backend.extension())
+            .into_iter()
+            .map(|info| info.pass_id)
+            .collect();
+        let mut appended = BTreeSet::new();
+        let mut entries = Vec::new();
+        let mut created = Vec::new();
+        for token in tokens {
+            let title = if token.issuer.is_empty() {
+                token.name.clone()
+            } else {
+                token.issuer.clone()
+            };
+            let matching = pass_ids.iter().find(|pass_id| {
+                let name = pass_id.rsplit('/').next().unwrap_or_default();
+                name.eq_ignore_ascii_case(title.trim())
+            });
+            let entry = ImportEntry {
+                title,
+                login: token.name.clone(),
+                otpauth: Some(token.to_url()),
+                ..ImportEntry::default()
+            };
+            match matching {
+                Some(pass_id) => entries.push(PlannedEntry {
+                    pass_id: pass_id.clone(),
+                    entry,
+                    conflict: (!appended.insert(pass_id.clone())).then_some(Conflict::Duplicate),
+                    append: true,
+                }),
+                None => created.push(entry),
+            }
+        }
+        let mut plan = Self::new(format, created, backend, store_dir, folder);
+        entries.append(&mut plan.entries);
+        plan.entries = entries;
+        plan
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.conflict.is_some())
+            .count()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Planned(Result<Plan, String>),
+    Progress { done: usize, total: usize },
+    Finished(Summary),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub written: usize,
+    pub skipped: usize,
+    /// Pass IDs with the reason why they could not be written
+    pub failures: Vec<(String, String)>,
+    pub cancelled: bool,
+}
+
+/// Reads an export and plans where its entries go
+pub fn plan(
+    source: Source,
+    file_path: &Path,
+    credentials: &Credentials,
+    backend: &dyn Backend,
+    store_dir: &Path,
+    folder: &str,
+    progress: &Progress<Event>,
+) -> Result<Plan, String> {
+    let (format, entries) = match source {
+        Source::Csv => csv::read(file_path)?,
+        Source::Kdbx => kdbx::read(file_path, credentials, progress)?,
+        Source::Authenticator => {
+            let (format, tokens) = authenticator::read(file_path)?;
+            return Ok(Plan::with_tokens(
+                format!("{format} backup"),
+                tokens,
+                backend,
+                store_dir,
+                folder,
+            ));
+        }
+        Source::Bundle => {
+            let entries = share::read(backend, file_path)?
+                .into_iter()
+                .map(|(pass_id, contents)| {
+                    let (folder, title) = pass_id.rsplit_once('/').unwrap_or(("", &pass_id));
+                    ImportEntry {
+                        folder: folder.to_owned(),
+                        title: title.to_owned(),
+                        attachment: Some(contents),
+                        ..ImportEntry::default()
+                    }
+                })
+                .collect();
+            ("Partout".to_owned(), entries)
+        }
+    };
+    Ok(Plan::new(format, entries, backend, store_dir, folder))
+}
+
+/// Encrypts the planned entries for the recipients of their folders,
+/// existing password files are only replaced when `overwrite` is set
+pub fn write(
+    backend: &dyn Backend,
+    store_dir: &Path,
+    plan: Plan,
+    overwrite: bool,
+    progress: &Progress<Event>,
+) {
+    let total = plan.entries.len();
+    let mut summary = Summary::default();
+    // Encrypters are shared by all entries governed by the same recipients file
+    let mut encrypters: HashMap<PathBuf, Box<dyn Encrypt>> = HashMap::new();
+
+    for (done, planned) in plan.entries.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        progress.report(Event::Progress { done, total });
+
+        let skip = match planned.conflict {
+            Some(Conflict::Duplicate) => true,
+            Some(Conflict::Exists) => !overwrite,
+            None => false,
+        };
+        if skip {
+            summary.skipped += 1;
+            continue;
+        }
+        match write_entry(backend, store_dir, &planned, &mut encrypters) {
+            Ok(true) => summary.written += 1,
+            Ok(false) => summary.skipped += 1,
+            Err(e) => summary.failures.push((planned.pass_id, e)),
+        }
+    }
+    progress.report(Event::Finished(summary));
+}
+
+fn write_entry(
+    backend: &dyn Backend,
+    store_dir: &Path,
+    planned: &PlannedEntry,
+    encrypters: &mut HashMap<PathBuf, Box<dyn Encrypt>>,
+) -> Result<bool, String> {
+    let file_path = store::entry_path(store_dir, &planned.pass_id, backend.extension());
+    let plaintext = if planned.append {
+        match append_otp(backend, &file_path, &planned.entry)? {
+            Some(plaintext) => plaintext,
+            None => return Ok(false),
+        }
+    } else {
+        planned.entry.contents()
+    };
+    let folder = Path::new(&planned.pass_id)
+        .parent()
+        .map(|folder| folder.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let recipients_file = recipients::governing_file(backend, store_dir, &folder)
+        .ok_or_else(|| format!("no {} file found", backend.recipients_file()))?;
+
+    let encrypter = match encrypters.entry(recipients_file) {
+        hash_map::Entry::Occupied(entry) => entry.into_mut(),
+        hash_map::Entry::Vacant(entry) => {
+            let recipients = recipients::read(entry.key()).map_err(|e| e.to_string())?;
+            entry.insert(backend.encrypter(&recipients)?)
+        }
+    };
+    let ciphertext = encrypter.encrypt(&plaintext)?;
+
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    backend::write_atomically(&file_path, &ciphertext).map(|()| true)
+}
+
+/// Adds the OTP URL of the entry to an existing password file,
+/// `None` if the file already has a URL with the same secret
+fn append_otp(
+    backend: &dyn Backend,
+    file_path: &Path,
+    entry: &ImportEntry,
+) -> Result<Option<Vec<u8>>, String> {
+    let url = entry.otpauth.as_deref().unwrap_or_default();
+    let secret = Token::from_url(url).map(|token| token.secret);
+    let mut contents = String::from_utf8(backend.decrypt(file_path)?).map_err(|e| e.to_string())?;
+    if contents
+        .lines()
+        .filter(|line| otp::is_url(line))
+        .any(|line| Token::from_url(line).map(|token| token.secret) == secret)
+    {
+        return Ok(None);
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(url);
+    contents.push('\n');
+    Ok(Some(contents.into_bytes()))
+}
@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use super::ImportEntry;
+
+/// Columns of the supported exports, matched case-insensitively
+const TITLE: &[&str] = &["title", "name"];
+const FOLDER: &[&str] = &["folder", "group"];
+const PASSWORD: &[&str] = &["password", "login_password"];
+const LOGIN: &[&str] = &["username", "login_username", "login"];
+const URL: &[&str] = &["url", "login_uri", "website", "uri"];
+const NOTES: &[&str] = &["notes", "note", "extra"];
+const OTP: &[&str] = &["totp", "login_totp", "otpauth", "otp"];
+/// Bitwarden writes custom fields as `name: value` lines into one column
+const CUSTOM_FIELDS: &[&str] = &["fields"];
+
+/// Reads a CSV export and returns the name of the recognized format with its entries
+pub fn read(file_path: &Path) -> Result<(String, Vec<ImportEntry>), String> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(file_path)
+        .map_err(|e| e.to_string())?;
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+    let columns = Columns {
+        title: column(TITLE),
+        folder: column(FOLDER),
+        password: column(PASSWORD),
+        login: column(LOGIN),
+        url: column(URL),
+        notes: column(NOTES),
+        otp: column(OTP),
+        custom_fields: column(CUSTOM_FIELDS),
+    };
+    if columns.password.is_none() && columns.notes.is_none() {
+        return Err("no password column found".to_owned());
+    }
+    let format = format(&headers);
+    // Every other column is kept as a field so that nothing is lost
+    let known: Vec<usize> = [
+        columns.title,
+        columns.folder,
+        columns.password,
+        columns.login,
+        columns.url,
+        columns.notes,
+        columns.otp,
+        columns.custom_fields,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let get = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .unwrap_or_default()
+                .trim()
+                .to_owned()
+        };
+
+        let url = get(columns.url);
+        let mut title = get(columns.title);
+        if title.is_empty() {
+            // Browser exports only name entries by their URL
+            title = host(&url).to_owned();
+        }
+        let mut folder = get(columns.folder);
+        if format == Format::KeePassXc {
+            // Groups start with the name of the root group
+            folder = folder
+                .split_once('/')
+                .map(|(_, folder)| folder.to_owned())
+                .unwrap_or_default();
+        }
+
+        let mut fields: Vec<(String, String)> = get(columns.custom_fields)
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+            .collect();
+        fields.extend(
+            headers
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !known.contains(index))
+                .filter_map(|(index, header)| {
+                    let value = record.get(index)?.trim();
+                    (!value.is_empty() && !format.ignores(header))
+                        .then(|| (header.clone(), value.to_owned()))
+                }),
+        );
+
+        let otp = get(columns.otp);
+        entries.push(ImportEntry {
+            otpauth: (!otp.is_empty()).then(|| otpauth(&otp, &title, &get(columns.login))),
+            folder,
+            title,
+            password: get(columns.password),
+            login: get(columns.login),
+            url,
+            notes: get(columns.notes),
+            fields,
+            attachment: None,
+        });
+    }
+    Ok((format.to_string(), entries))
+}
+
+struct Columns {
+    title: Option<usize>,
+    folder: Option<usize>,
+    password: Option<usize>,
+    login: Option<usize>,
+    url: Option<usize>,
+    notes: Option<usize>,
+    otp: Option<usize>,
+    custom_fields: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Bitwarden,
+    KeePassXc,
+    OnePassword,
+    Chrome,
+    Firefox,
+    Generic,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Format::Bitwarden => "Bitwarden",
+            Format::KeePassXc => "KeePassXC",
+            Format::OnePassword => "1Password",
+            Format::Chrome => "Chrome",
+            Format::Firefox => "Firefox",
+            Format::Generic => "CSV",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Format {
+    /// Columns with bookkeeping of the password manager that are not imported
+    fn ignores(self, header: &str) -> bool {
+        let ignored: &[&str] = match self {
+            Format::Bitwarden => &["favorite", "type", "reprompt"],
+            Format::KeePassXc => &["icon", "last modified", "created"],
+            Format::OnePassword => &["favorite", "archived"],
+            Format::Firefox => &[
+                "httprealm",
+                "formactionorigin",
+                "guid",
+                "timecreated",
+                "timelastused",
+                "timepasswordchanged",
+            ],
+            Format::Chrome | Format::Generic => &[],
+        };
+        ignored.contains(&header)
+    }
+}
+
+/// Recognizes the exporting password manager by its characteristic columns
+fn format(headers: &[String]) -> Format {
+    let has = |name: &str| headers.iter().any(|header| header == name);
+    if has("login_password") {
+        Format::Bitwarden
+    } else if has("group") && has("title") {
+        Format::KeePassXc
+    } else if has("otpauth") || has("archived") {
+        Format::OnePassword
+    } else if has("guid") || has("httprealm") {
+        Format::Firefox
+    } else if has("name") && has("url") && has("username") {
+        Format::Chrome
+    } else {
+        Format::Generic
+    }
+}
+
+/// Host name of a URL, used as title when an entry has none
+fn host(url: &str) -> &str {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+}
+
+/// Turns a TOTP column into an `otpauth://` URL, plain secrets are wrapped into one
+fn otpauth(otp: &str, title: &str, login: &str) -> String {
+    if otp.starts_with("otpauth://") {
+        otp.to_owned()
+    } else {
+        super::token(otp, title, login).to_url()
+    }
+}
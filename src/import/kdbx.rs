@@ -0,0 +1,910 @@
+//! Reader for KeePass databases in the KDBX 3.1 and 4 formats,
+//! see <https://keepass.info/help/kb/kdbx_4.html>
+
+use aes::cipher::{
+    block_padding::Pkcs7, generic_array::GenericArray, BlockDecryptMut, BlockEncrypt, KeyInit,
+    KeyIvInit, StreamCipher,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::read::GzDecoder;
+use hmac::{Hmac, Mac};
+use roxmltree::{Document, Node, NodeId};
+use sha2::{Digest, Sha256, Sha512};
+use std::{collections::HashMap, fs, io::Read, path::Path};
+
+use super::{Credentials, Event, ImportEntry};
+use crate::{authenticator::Kind, service::Progress};
+
+const SIGNATURE: [u8; 8] = [0x03, 0xd9, 0xa2, 0x9a, 0x67, 0xfb, 0x4b, 0xb5];
+
+const CIPHER_AES256: [u8; 16] = uuid(0x31c1f2e6_bf71_4350_be58_05216afc5aff);
+const CIPHER_TWOFISH: [u8; 16] = uuid(0xad68f29f_576f_4bb9_a36a_d47af965346c);
+const CIPHER_CHACHA20: [u8; 16] = uuid(0xd6038a2b_8b6f_4cb5_a524_339a31dbb59a);
+
+const KDF_AES_KDBX3: [u8; 16] = uuid(0xc9d9f39a_628a_4460_bf74_0d08c18a4fea);
+const KDF_AES_KDBX4: [u8; 16] = uuid(0x7c02bb82_79a7_4ac0_927d_114a00648238);
+const KDF_ARGON2D: [u8; 16] = uuid(0xef636ddf_8c29_444b_91f7_a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid(0x9e298b19_56db_4773_b23d_fc3ec6f0a1e6);
+
+/// Nonce of the Salsa20 stream that protects values in KDBX 3.1 databases
+const SALSA20_NONCE: [u8; 8] = [0xe8, 0x30, 0x09, 0x4b, 0x97, 0x20, 0x5d, 0x2a];
+
+const WRONG_KEY: &str = "wrong master password or key file";
+
+/// Limits of the key derivation parameters, far above what KeePass and KeePassXC choose,
+/// so that a crafted database cannot keep the worker busy for hours or exhaust the memory
+const MAX_AES_ROUNDS: u64 = 1_000_000_000;
+const MAX_ARGON2_ITERATIONS: u64 = 1000;
+const MAX_ARGON2_MEMORY: u64 = 4 << 30;
+const MAX_ARGON2_PARALLELISM: u32 = 64;
+/// AES rounds between two checks whether the import was cancelled
+const AES_ROUNDS_PER_CHECK: u64 = 1_000_000;
+
+const fn uuid(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+/// Opens a database and returns its version with the entries of all groups except the recycle bin
+///
+/// Deriving the key takes a while, it stops when the import is cancelled.
+pub fn read(
+    file_path: &Path,
+    credentials: &Credentials,
+    progress: &Progress<Event>,
+) -> Result<(String, Vec<ImportEntry>), String> {
+    let data = fs::read(file_path).map_err(|e| e.to_string())?;
+    let mut reader = Reader::new(&data);
+    if reader.take(8)? != SIGNATURE {
+        return Err("not a KeePass database".to_owned());
+    }
+    let minor = reader.u16()?;
+    let major = reader.u16()?;
+    if !(3..=4).contains(&major) {
+        return Err(format!("KDBX {major}.{minor} databases are not supported"));
+    }
+    let header = Header::read(&mut reader, major)?;
+    let header_bytes = &data[..reader.position];
+
+    let composite_key = composite_key(credentials)?;
+    let transformed_key = header.kdf.transform(&composite_key, progress)?;
+    let cipher_key: [u8; 32] =
+        Sha256::digest([header.master_seed.as_slice(), &transformed_key].concat()).into();
+
+    let (xml, inner) = if major == 3 {
+        let payload = decrypt(header.cipher, &cipher_key, &header.iv, reader.rest())?;
+        let blocks = payload
+            .strip_prefix(header.stream_start_bytes.as_slice())
+            .ok_or(WRONG_KEY)?;
+        let xml = decompress(header.compressed, hashed_blocks(blocks)?)?;
+        let inner = InnerHeader {
+            stream: header.inner_stream,
+            stream_key: header.protected_stream_key,
+            binaries: Vec::new(),
+        };
+        (xml, inner)
+    } else {
+        let hmac_key: [u8; 64] =
+            Sha512::digest([header.master_seed.as_slice(), &transformed_key, &[1]].concat()).into();
+        let hash = reader.take(32)?;
+        if Sha256::digest(header_bytes).as_slice() != hash {
+            return Err("the header of the database is corrupted".to_owned());
+        }
+        block_hmac(&hmac_key, u64::MAX)
+            .chain_update(header_bytes)
+            .verify_slice(reader.take(32)?)
+            .map_err(|_| WRONG_KEY)?;
+        let payload = decrypt(
+            header.cipher,
+            &cipher_key,
+            &header.iv,
+            &hmac_blocks(reader.rest(), &hmac_key)?,
+        )?;
+        let payload = decompress(header.compressed, payload)?;
+        let mut reader = Reader::new(&payload);
+        let inner = InnerHeader::read(&mut reader)?;
+        (reader.rest().to_vec(), inner)
+    };
+
+    let xml = String::from_utf8(xml).map_err(|e| e.to_string())?;
+    let document = Document::parse(&xml).map_err(|e| e.to_string())?;
+    let database = Database::new(&document, inner)?;
+    Ok((format!("KDBX {major}.{minor}"), database.entries()))
+}
+
+/// Cursor over the little-endian fields of the database
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + len)
+            .ok_or("the database is truncated")?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self
+            .take(N)?
+            .try_into()
+            .expect("slice has the requested length"))
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(
+        bytes.try_into().map_err(|_| "invalid integer field")?,
+    ))
+}
+
+fn le_u64(bytes: &[u8]) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(
+        bytes.try_into().map_err(|_| "invalid integer field")?,
+    ))
+}
+
+#[derive(Clone, Copy)]
+enum Cipher {
+    Aes256,
+    Twofish,
+    ChaCha20,
+}
+
+enum Kdf {
+    Aes {
+        seed: Vec<u8>,
+        rounds: u64,
+    },
+    Argon2 {
+        algorithm: argon2::Algorithm,
+        version: u32,
+        salt: Vec<u8>,
+        iterations: u64,
+        /// Memory in bytes
+        memory: u64,
+        parallelism: u32,
+        secret: Option<Vec<u8>>,
+    },
+}
+
+impl Kdf {
+    /// Reads the parameters of KDBX 4, which are stored in a variant dictionary
+    fn from_parameters(data: &[u8]) -> Result<Self, String> {
+        let mut parameters = variant_dictionary(data)?;
+        let mut take = |key: &str| {
+            parameters
+                .remove(key)
+                .ok_or_else(|| format!("key derivation parameter {key} is missing"))
+        };
+        let id = take("$UUID")?;
+        if id == KDF_AES_KDBX3 || id == KDF_AES_KDBX4 {
+            Ok(Kdf::Aes {
+                seed: take("S")?,
+                rounds: le_u64(&take("R")?)?,
+            })
+        } else if id == KDF_ARGON2D || id == KDF_ARGON2ID {
+            Ok(Kdf::Argon2 {
+                algorithm: if id == KDF_ARGON2D {
+                    argon2::Algorithm::Argon2d
+                } else {
+                    argon2::Algorithm::Argon2id
+                },
+                version: le_u32(&take("V")?)?,
+                salt: take("S")?,
+                iterations: le_u64(&take("I")?)?,
+                memory: le_u64(&take("M")?)?,
+                parallelism: le_u32(&take("P")?)?,
+                secret: take("K").ok(),
+            })
+        } else {
+            Err("unsupported key derivation function".to_owned())
+        }
+    }
+
+    /// Rejects parameters that would take unreasonably long or need too much memory
+    fn check_limits(&self) -> Result<(), String> {
+        let exceeded = match self {
+            Kdf::Aes { rounds, .. } => (*rounds > MAX_AES_ROUNDS).then_some("rounds"),
+            Kdf::Argon2 {
+                iterations,
+                memory,
+                parallelism,
+                ..
+            } => {
+                if *iterations > MAX_ARGON2_ITERATIONS {
+                    Some("iterations")
+                } else if *memory > MAX_ARGON2_MEMORY {
+                    Some("memory")
+                } else if *parallelism > MAX_ARGON2_PARALLELISM {
+                    Some("parallelism")
+                } else {
+                    None
+                }
+            }
+        };
+        match exceeded {
+            Some(parameter) => Err(format!(
+                "the database asks for more key derivation {parameter} than supported"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Derives the key of the database from the composite key of the credentials
+    fn transform(
+        &self,
+        composite_key: &[u8; 32],
+        progress: &Progress<Event>,
+    ) -> Result<[u8; 32], String> {
+        self.check_limits()?;
+        match self {
+            Kdf::Aes { seed, rounds } => {
+                let cipher =
+                    aes::Aes256::new_from_slice(seed).map_err(|_| "invalid transform seed")?;
+                let mut blocks = [
+                    *GenericArray::from_slice(&composite_key[..16]),
+                    *GenericArray::from_slice(&composite_key[16..]),
+                ];
+                for round in 0..*rounds {
+                    if round % AES_ROUNDS_PER_CHECK == 0 && progress.is_cancelled() {
+                        return Err("the import was cancelled".to_owned());
+                    }
+                    cipher.encrypt_blocks(&mut blocks);
+                }
+                Ok(Sha256::digest(blocks.concat()).into())
+            }
+            Kdf::Argon2 {
+                algorithm,
+                version,
+                salt,
+                iterations,
+                memory,
+                parallelism,
+                secret,
+            } => {
+                let params = argon2::Params::new(
+                    u32::try_from(memory / 1024).map_err(|e| e.to_string())?,
+                    u32::try_from(*iterations).map_err(|e| e.to_string())?,
+                    *parallelism,
+                    Some(32),
+                )
+                .map_err(|e| e.to_string())?;
+                let version = argon2::Version::try_from(*version).map_err(|e| e.to_string())?;
+                let argon2 = match secret {
+                    Some(secret) => {
+                        argon2::Argon2::new_with_secret(secret, *algorithm, version, params)
+                            .map_err(|e| e.to_string())?
+                    }
+                    None => argon2::Argon2::new(*algorithm, version, params),
+                };
+                let mut key = [0; 32];
+                argon2
+                    .hash_password_into(composite_key, salt, &mut key)
+                    .map_err(|e| e.to_string())?;
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// Unencrypted outer header of the database
+struct Header {
+    cipher: Cipher,
+    compressed: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    kdf: Kdf,
+    /// Fields of KDBX 3.1 that moved to the inner header or the HMACs in KDBX 4
+    protected_stream_key: Vec<u8>,
+    stream_start_bytes: Vec<u8>,
+    inner_stream: u32,
+}
+
+impl Header {
+    fn read(reader: &mut Reader, major: u16) -> Result<Self, String> {
+        let mut cipher = None;
+        let mut compressed = false;
+        let mut master_seed = Vec::new();
+        let mut iv = Vec::new();
+        let mut kdf = None;
+        let mut transform_seed = Vec::new();
+        let mut transform_rounds = 0;
+        let mut protected_stream_key = Vec::new();
+        let mut stream_start_bytes = Vec::new();
+        let mut inner_stream = 0;
+
+        loop {
+            let id = reader.u8()?;
+            let len = if major == 3 {
+                usize::from(reader.u16()?)
+            } else {
+                reader.u32()? as usize
+            };
+            let value = reader.take(len)?;
+            match id {
+                0 => break,
+                2 => {
+                    cipher = Some(match value {
+                        _ if value == CIPHER_AES256 => Cipher::Aes256,
+                        _ if value == CIPHER_TWOFISH => Cipher::Twofish,
+                        _ if value == CIPHER_CHACHA20 => Cipher::ChaCha20,
+                        _ => return Err("unsupported cipher".to_owned()),
+                    })
+                }
+                3 => compressed = le_u32(value)? == 1,
+                4 => master_seed = value.to_vec(),
+                5 => transform_seed = value.to_vec(),
+                6 => transform_rounds = le_u64(value)?,
+                7 => iv = value.to_vec(),
+                8 => protected_stream_key = value.to_vec(),
+                9 => stream_start_bytes = value.to_vec(),
+                10 => inner_stream = le_u32(value)?,
+                11 => kdf = Some(Kdf::from_parameters(value)?),
+                _ => (),
+            }
+        }
+
+        let kdf = match kdf {
+            Some(kdf) => kdf,
+            None if major == 3 => Kdf::Aes {
+                seed: transform_seed,
+                rounds: transform_rounds,
+            },
+            None => return Err("key derivation parameters are missing".to_owned()),
+        };
+        Ok(Self {
+            cipher: cipher.ok_or("cipher is missing")?,
+            compressed,
+            master_seed,
+            iv,
+            kdf,
+            protected_stream_key,
+            stream_start_bytes,
+            inner_stream,
+        })
+    }
+}
+
+/// Reads a variant dictionary of KDBX 4 into raw little-endian values by key
+fn variant_dictionary(data: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let mut reader = Reader::new(data);
+    let version = reader.u16()?;
+    if version >> 8 != 1 {
+        return Err("unsupported variant dictionary".to_owned());
+    }
+    let mut values = HashMap::new();
+    loop {
+        if reader.u8()? == 0 {
+            return Ok(values);
+        }
+        let len = reader.u32()? as usize;
+        let key = String::from_utf8_lossy(reader.take(len)?).into_owned();
+        let len = reader.u32()? as usize;
+        values.insert(key, reader.take(len)?.to_vec());
+    }
+}
+
+/// Hashes the master password and the key of the key file
+fn composite_key(credentials: &Credentials) -> Result<[u8; 32], String> {
+    let mut composite = Sha256::new();
+    if !credentials.password.is_empty() || credentials.key_file.is_none() {
+        composite.update(Sha256::digest(credentials.password.as_bytes()));
+    }
+    if let Some(key_file) = &credentials.key_file {
+        let data = fs::read(key_file).map_err(|e| format!("{}: {e}", key_file.display()))?;
+        composite.update(key_file_key(&data)?);
+    }
+    Ok(composite.finalize().into())
+}
+
+/// Key of a key file, which is either an XML key file, 32 raw bytes, 64 hex digits,
+/// or any other file that is hashed
+fn key_file_key(data: &[u8]) -> Result<[u8; 32], String> {
+    if let Some(key) = std::str::from_utf8(data)
+        .ok()
+        .and_then(|xml| Document::parse(xml).ok())
+        .and_then(|document| {
+            document
+                .descendants()
+                .find(|node| node.has_tag_name("Data"))
+                .map(|node| (document.root_element(), node))
+                .map(|(root, node)| xml_key(root, node))
+        })
+    {
+        return key;
+    }
+    if let Ok(key) = <[u8; 32]>::try_from(data) {
+        return Ok(key);
+    }
+    if let Some(key) = std::str::from_utf8(data)
+        .ok()
+        .and_then(|hex| decode_hex(hex.trim()))
+        .and_then(|key| <[u8; 32]>::try_from(key).ok())
+    {
+        return Ok(key);
+    }
+    Ok(Sha256::digest(data).into())
+}
+
+/// Key of an XML key file, base64 in version 1.0 and hex in version 2.0
+fn xml_key(root: Node, data: Node) -> Result<[u8; 32], String> {
+    let version = root
+        .descendants()
+        .find(|node| node.has_tag_name("Version"))
+        .and_then(|node| node.text())
+        .unwrap_or("1.0");
+    let text = data.text().unwrap_or_default();
+    let key = if version.starts_with("2.") {
+        let hex: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        decode_hex(&hex)
+    } else {
+        BASE64.decode(text.trim()).ok()
+    };
+    key.and_then(|key| <[u8; 32]>::try_from(key).ok())
+        .ok_or_else(|| "invalid key file".to_owned())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn decrypt(cipher: Cipher, key: &[u8; 32], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    match cipher {
+        Cipher::Aes256 => cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+            .map_err(|_| "invalid encryption IV")?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+            .map_err(|_| WRONG_KEY.to_owned()),
+        Cipher::Twofish => cbc::Decryptor::<twofish::Twofish>::new_from_slices(key, iv)
+            .map_err(|_| "invalid encryption IV")?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+            .map_err(|_| WRONG_KEY.to_owned()),
+        Cipher::ChaCha20 => {
+            let mut data = data.to_vec();
+            chacha20::ChaCha20::new_from_slices(key, iv)
+                .map_err(|_| "invalid encryption IV")?
+                .apply_keystream(&mut data);
+            Ok(data)
+        }
+    }
+}
+
+fn decompress(compressed: bool, data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if !compressed {
+        return Ok(data);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data.as_slice())
+        .read_to_end(&mut decompressed)
+        .map_err(|e| e.to_string())?;
+    Ok(decompressed)
+}
+
+/// Joins the blocks of KDBX 3.1, each is checked with its SHA-256 hash
+fn hashed_blocks(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = Reader::new(data);
+    let mut joined = Vec::new();
+    loop {
+        let _index = reader.u32()?;
+        let hash = reader.take(32)?;
+        let len = reader.u32()? as usize;
+        if len == 0 {
+            return Ok(joined);
+        }
+        let block = reader.take(len)?;
+        if Sha256::digest(block).as_slice() != hash {
+            return Err("a data block of the database is corrupted".to_owned());
+        }
+        joined.extend_from_slice(block);
+    }
+}
+
+/// Joins the blocks of KDBX 4, each is authenticated with an HMAC
+fn hmac_blocks(data: &[u8], hmac_key: &[u8; 64]) -> Result<Vec<u8>, String> {
+    let mut reader = Reader::new(data);
+    let mut joined = Vec::new();
+    for index in 0.. {
+        let mac = reader.take(32)?;
+        let len = reader.take(4)?;
+        let block = reader.take(le_u32(len)? as usize)?;
+        block_hmac(hmac_key, index)
+            .chain_update(index.to_le_bytes())
+            .chain_update(len)
+            .chain_update(block)
+            .verify_slice(mac)
+            .map_err(|_| "a data block of the database is corrupted")?;
+        if block.is_empty() {
+            break;
+        }
+        joined.extend_from_slice(block);
+    }
+    Ok(joined)
+}
+
+fn block_hmac(hmac_key: &[u8; 64], index: u64) -> Hmac<Sha256> {
+    let key = Sha512::digest([index.to_le_bytes().as_slice(), hmac_key].concat());
+    <Hmac<Sha256> as Mac>::new_from_slice(&key).expect("HMAC accepts keys of any length")
+}
+
+/// Encrypted inner header of KDBX 4, which holds the attachments
+struct InnerHeader {
+    stream: u32,
+    stream_key: Vec<u8>,
+    binaries: Vec<Vec<u8>>,
+}
+
+impl InnerHeader {
+    fn read(reader: &mut Reader) -> Result<Self, String> {
+        let mut header = Self {
+            stream: 0,
+            stream_key: Vec::new(),
+            binaries: Vec::new(),
+        };
+        loop {
+            let id = reader.u8()?;
+            let len = reader.u32()? as usize;
+            let value = reader.take(len)?;
+            match id {
+                0 => return Ok(header),
+                1 => header.stream = le_u32(value)?,
+                2 => header.stream_key = value.to_vec(),
+                // The first byte holds flags of the attachment
+                3 => header
+                    .binaries
+                    .push(value.get(1..).unwrap_or_default().to_vec()),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Key stream that protects passwords and other sensitive values inside the XML
+enum InnerStream {
+    None,
+    Salsa20(salsa20::Salsa20),
+    ChaCha20(chacha20::ChaCha20),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Self, String> {
+        match id {
+            0 => Ok(InnerStream::None),
+            2 => {
+                let key = Sha256::digest(key);
+                Ok(InnerStream::Salsa20(salsa20::Salsa20::new(
+                    &key,
+                    &SALSA20_NONCE.into(),
+                )))
+            }
+            3 => {
+                let key = Sha512::digest(key);
+                Ok(InnerStream::ChaCha20(chacha20::ChaCha20::new(
+                    GenericArray::from_slice(&key[..32]),
+                    GenericArray::from_slice(&key[32..44]),
+                )))
+            }
+            _ => Err("unsupported protection of values".to_owned()),
+        }
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        match self {
+            InnerStream::None => (),
+            InnerStream::Salsa20(cipher) => cipher.apply_keystream(data),
+            InnerStream::ChaCha20(cipher) => cipher.apply_keystream(data),
+        }
+    }
+}
+
+/// Decrypted XML document of the database
+struct Database<'a, 'input> {
+    document: &'a Document<'input>,
+    /// Protected values by their node, decrypted in document order
+    protected: HashMap<NodeId, Vec<u8>>,
+    binaries: HashMap<String, Vec<u8>>,
+}
+
+impl<'a, 'input> Database<'a, 'input> {
+    fn new(document: &'a Document<'input>, inner: InnerHeader) -> Result<Self, String> {
+        let mut stream = InnerStream::new(inner.stream, &inner.stream_key)?;
+        let mut protected = HashMap::new();
+        for node in document.descendants().filter(|node| {
+            (node.has_tag_name("Value") || node.has_tag_name("Binary"))
+                && node.attribute("Protected") == Some("True")
+        }) {
+            let mut value = BASE64
+                .decode(node.text().unwrap_or_default().trim())
+                .map_err(|e| e.to_string())?;
+            stream.apply(&mut value);
+            protected.insert(node.id(), value);
+        }
+
+        let mut binaries: HashMap<String, Vec<u8>> = inner
+            .binaries
+            .into_iter()
+            .enumerate()
+            .map(|(id, data)| (id.to_string(), data))
+            .collect();
+        // KDBX 3.1 keeps the attachments in the metadata
+        for node in document.descendants().filter(|node| {
+            node.has_tag_name("Binary")
+                && node
+                    .parent()
+                    .is_some_and(|parent| parent.has_tag_name("Binaries"))
+        }) {
+            let Some(id) = node.attribute("ID") else {
+                continue;
+            };
+            let data = match protected.remove(&node.id()) {
+                Some(data) => data,
+                None => decompress(
+                    node.attribute("Compressed") == Some("True"),
+                    BASE64
+                        .decode(node.text().unwrap_or_default().trim())
+                        .map_err(|e| e.to_string())?,
+                )?,
+            };
+            binaries.insert(id.to_owned(), data);
+        }
+
+        Ok(Self {
+            document,
+            protected,
+            binaries,
+        })
+    }
+
+    fn entries(&self) -> Vec<ImportEntry> {
+        let recycle_bin = self
+            .document
+            .descendants()
+            .find(|node| node.has_tag_name("RecycleBinUUID"))
+            .and_then(|node| node.text());
+        let mut entries = Vec::new();
+        // The name of the root group is the name of the database
+        if let Some(root) = self
+            .document
+            .descendants()
+            .find(|node| node.has_tag_name("Root"))
+            .and_then(|root| root.children().find(|node| node.has_tag_name("Group")))
+        {
+            self.read_group(root, "", recycle_bin, &mut entries);
+        }
+        entries
+    }
+
+    fn read_group(
+        &self,
+        group: Node,
+        folder: &str,
+        recycle_bin: Option<&str>,
+        entries: &mut Vec<ImportEntry>,
+    ) {
+        for child in group.children() {
+            if child.has_tag_name("Entry") {
+                self.read_entry(child, folder, entries);
+            } else if child.has_tag_name("Group") {
+                if recycle_bin.is_some() && child_text(child, "UUID") == recycle_bin {
+                    continue;
+                }
+                let name = child_text(child, "Name")
+                    .unwrap_or_default()
+                    .replace('/', "-");
+                let folder = if folder.is_empty() {
+                    name
+                } else {
+                    format!("{folder}/{name}")
+                };
+                self.read_group(child, &folder, recycle_bin, entries);
+            }
+        }
+    }
+
+    fn read_entry(&self, node: Node, folder: &str, entries: &mut Vec<ImportEntry>) {
+        let mut entry = ImportEntry {
+            folder: folder.to_owned(),
+            ..ImportEntry::default()
+        };
+        let mut fields = Vec::new();
+        for string in node.children().filter(|node| node.has_tag_name("String")) {
+            let key = child_text(string, "Key").unwrap_or_default().to_owned();
+            let value = string
+                .children()
+                .find(|node| node.has_tag_name("Value"))
+                .map(|value| match self.protected.get(&value.id()) {
+                    Some(data) => String::from_utf8_lossy(data).into_owned(),
+                    None => value.text().unwrap_or_default().to_owned(),
+                })
+                .unwrap_or_default();
+            match key.as_str() {
+                "Title" => entry.title = value,
+                "UserName" => entry.login = value,
+                "Password" => entry.password = value,
+                "URL" => entry.url = value,
+                "Notes" => entry.notes = value,
+                _ if value.is_empty() => (),
+                _ => fields.push((key, value)),
+            }
+        }
+        entry.otpauth = otpauth(&mut fields, &entry.title, &entry.login);
+        entry.fields = fields;
+
+        // Attachments become entries in a folder named after the entry
+        for binary in node.children().filter(|node| node.has_tag_name("Binary")) {
+            let Some(data) = binary
+                .children()
+                .find(|node| node.has_tag_name("Value"))
+                .and_then(|value| value.attribute("Ref"))
+                .and_then(|id| self.binaries.get(id))
+            else {
+                continue;
+            };
+            entries.push(ImportEntry {
+                folder: format!("{folder}/{}", entry.title.replace('/', "-")),
+                title: child_text(binary, "Key").unwrap_or_default().to_owned(),
+                attachment: Some(data.clone()),
+                ..ImportEntry::default()
+            });
+        }
+        entries.push(entry);
+    }
+}
+
+fn child_text<'a>(node: Node<'a, '_>, tag_name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(tag_name))
+        .and_then(|child| child.text())
+}
+
+/// Removes the TOTP settings of KeePassXC or KeePass from the fields
+/// and turns them into an `otpauth://` URL
+fn otpauth(fields: &mut Vec<(String, String)>, title: &str, login: &str) -> Option<String> {
+    let mut take = |key: &str| {
+        let index = fields.iter().position(|(field, _)| field == key)?;
+        Some(fields.remove(index).1)
+    };
+
+    // KeePassXC
+    if let Some(url) = take("otp") {
+        return Some(url);
+    }
+    if let Some(seed) = take("TOTP Seed") {
+        let mut token = super::token(&seed, title, login);
+        if let Some((period, digits)) = take("TOTP Settings")
+            .as_deref()
+            .and_then(|settings| settings.split_once(';'))
+        {
+            token.period = period.parse().unwrap_or(token.period);
+            if digits == "S" {
+                token.kind = Kind::Steam;
+            } else {
+                token.digits = digits.parse().unwrap_or(token.digits);
+            }
+        }
+        return Some(token.to_url());
+    }
+
+    // KeePass 2.47 and later
+    let secret = if let Some(secret) = take("TimeOtp-Secret-Base32") {
+        secret
+    } else {
+        let raw = if let Some(secret) = take("TimeOtp-Secret") {
+            secret.into_bytes()
+        } else if let Some(secret) = take("TimeOtp-Secret-Hex") {
+            decode_hex(&secret.replace(' ', ""))?
+        } else {
+            BASE64.decode(take("TimeOtp-Secret-Base64")?.trim()).ok()?
+        };
+        let totp_rs::Secret::Encoded(secret) = totp_rs::Secret::Raw(raw).to_encoded() else {
+            return None;
+        };
+        secret
+    };
+    let mut token = super::token(&secret, title, login);
+    if let Some(digits) = take("TimeOtp-Length").and_then(|digits| digits.parse().ok()) {
+        token.digits = digits;
+    }
+    if let Some(period) = take("TimeOtp-Period").and_then(|period| period.parse().ok()) {
+        token.period = period;
+    }
+    if let Some(algorithm) = take("TimeOtp-Algorithm") {
+        token.algorithm = algorithm.replace("HMAC-", "").replace('-', "");
+    }
+    Some(token.to_url())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::channel::mpsc;
+    use std::time::{Duration, Instant};
+
+    fn detached() -> Progress<Event> {
+        Progress::detached(mpsc::unbounded().0)
+    }
+
+    fn argon2(iterations: u64, memory: u64, parallelism: u32) -> Kdf {
+        Kdf::Argon2 {
+            algorithm: argon2::Algorithm::Argon2id,
+            version: 0x13,
+            salt: vec![0; 32],
+            iterations,
+            memory,
+            parallelism,
+            secret: None,
+        }
+    }
+
+    #[test]
+    fn excessive_key_derivation_parameters_are_rejected() {
+        let aes = Kdf::Aes {
+            seed: vec![0; 32],
+            rounds: u64::MAX,
+        };
+        for (kdf, parameter) in [
+            (aes, "rounds"),
+            (argon2(u64::MAX, 64 << 20, 2), "iterations"),
+            (argon2(2, 1 << 40, 2), "memory"),
+            (argon2(2, 64 << 20, u32::MAX), "parallelism"),
+        ] {
+            let started = Instant::now();
+            let error = kdf
+                .transform(&[0; 32], &detached())
+                .expect_err("parameters exceed the limits");
+            assert!(error.contains(parameter), "{error}");
+            assert!(started.elapsed() < Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn aes_key_derivation_stops_when_the_import_is_cancelled() {
+        let kdf = Kdf::Aes {
+            seed: vec![0; 32],
+            rounds: MAX_AES_ROUNDS,
+        };
+        let progress = detached();
+        progress.cancel();
+        let started = Instant::now();
+        assert_eq!(
+            kdf.transform(&[0; 32], &progress),
+            Err("the import was cancelled".to_owned())
+        );
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let kdf = Kdf::Aes {
+            seed: vec![0; 32],
+            rounds: 0,
+        };
+        let key: [u8; 32] = Sha256::digest([0; 32]).into();
+        assert_eq!(kdf.transform(&[0; 32], &detached()), Ok(key));
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{Backend, Gpg},
+    gpg::{KeyProblem, KeyStore},
+    recipients,
+    service::Progress,
+};
+
+/// Recipient of `.gpg-id` files whose key has a problem
+#[derive(Debug, Clone)]
+pub struct KeyWarning {
+    pub recipient: String,
+    /// First user ID of the key, if it is in the keyring
+    pub name: Option<String>,
+    pub problem: KeyProblem,
+    /// Folders whose `.gpg-id` file lists the recipient
+    pub folders: Vec<String>,
+}
+
+impl KeyWarning {
+    pub fn text(&self) -> String {
+        match &self.name {
+            Some(name) => format!("Key of {name} ({}) is {}", self.recipient, self.problem),
+            None => format!("Key {} is {}", self.recipient, self.problem),
+        }
+    }
+}
+
+/// Result of the check of one store, identified by its index
+#[derive(Debug, Clone)]
+pub struct Checked {
+    pub store: usize,
+    pub warnings: Result<Vec<KeyWarning>, String>,
+}
+
+/// Inspects the keys of all recipients of the given stores
+pub fn check(keys: &dyn KeyStore, stores: Vec<(usize, PathBuf)>, progress: &Progress<Checked>) {
+    for (store, store_dir) in stores {
+        if progress.is_cancelled() {
+            return;
+        }
+        progress.report(Checked {
+            store,
+            warnings: check_store(keys, &store_dir),
+        });
+    }
+}
+
+fn check_store(keys: &dyn KeyStore, store_dir: &Path) -> Result<Vec<KeyWarning>, String> {
+    let mut folders: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for gpg_id in recipients::files(&Gpg, store_dir) {
+        let folder = gpg_id
+            .parent()
+            .and_then(|dir| dir.strip_prefix(store_dir).ok())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for recipient in recipients::read(&gpg_id).unwrap_or_default() {
+            folders.entry(recipient).or_default().push(folder.clone());
+        }
+    }
+
+    let recipients: Vec<String> = folders.keys().cloned().collect();
+    let keys = keys.resolve_keys(&recipients)?;
+    Ok(folders
+        .into_iter()
+        .filter_map(|(recipient, folders)| {
+            let key = keys.iter().find(|key| key.recipient == recipient);
+            let problem = match key {
+                Some(key) => key.problem?,
+                None => KeyProblem::Missing,
+            };
+            Some(KeyWarning {
+                name: key.and_then(|key| key.user_ids.first().cloned()),
+                recipient,
+                problem,
+                folders,
+            })
+        })
+        .collect())
+}
+
+/// Returns the folder whose recipients file governs an entry
+pub fn governing_folder(backend: &dyn Backend, store_dir: &Path, pass_id: &str) -> Option<String> {
+    let folder = pass_id.rsplit_once('/').map_or("", |(folder, _)| folder);
+    let file = recipients::governing_file(backend, store_dir, folder)?;
+    file.parent()
+        .and_then(|dir| dir.strip_prefix(store_dir).ok())
+        .map(|dir| dir.to_string_lossy().into_owned())
+}
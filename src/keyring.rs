@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    backend::Gpg,
+    gpg::{Imported, KeyStore, KeyringKey},
+    recipients,
+    service::Progress,
+};
+
+/// Key of the keyring together with its role in a store
+#[derive(Debug, Clone)]
+pub struct Key {
+    pub info: KeyringKey,
+    /// Folders whose `.gpg-id` file lists the key
+    pub folders: Vec<String>,
+}
+
+impl Key {
+    /// Whether password files of the store can be decrypted with the key
+    pub fn can_decrypt(&self) -> bool {
+        self.info.secret && !self.folders.is_empty()
+    }
+
+    pub fn name(&self) -> &str {
+        self.info
+            .user_ids
+            .first()
+            .map_or(self.info.fingerprint.as_str(), String::as_str)
+    }
+}
+
+/// Keys of the keyring as seen from a store
+#[derive(Debug, Clone)]
+pub struct Keyring {
+    /// Keys with secret keys first, then ordered by name
+    pub keys: Vec<Key>,
+    /// Number of folders of the store with a `.gpg-id` file
+    pub folder_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Loaded(Result<Keyring, String>),
+    Imported(Result<Imported, String>),
+    Exported(Result<PathBuf, String>),
+}
+
+/// Lists the keys of the keyring and the folders of the store they are recipients of
+pub fn load(keys: &dyn KeyStore, store_dir: &Path) -> Result<Keyring, String> {
+    let mut folders: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let files = recipients::files(&Gpg, store_dir);
+    for gpg_id in &files {
+        let folder = gpg_id
+            .parent()
+            .and_then(|dir| dir.strip_prefix(store_dir).ok())
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let recipients = recipients::read(gpg_id).unwrap_or_default();
+        // Recipients may be given as user IDs or key IDs
+        for key in keys.resolve_keys(&recipients)? {
+            folders
+                .entry(key.fingerprint)
+                .or_default()
+                .insert(folder.clone());
+        }
+    }
+
+    let mut keys: Vec<Key> = keys
+        .keyring()?
+        .into_iter()
+        .map(|info| Key {
+            folders: folders
+                .remove(&info.fingerprint)
+                .map(|folders| folders.into_iter().collect())
+                .unwrap_or_default(),
+            info,
+        })
+        .collect();
+    keys.sort_by(|a, b| {
+        b.info
+            .secret
+            .cmp(&a.info.secret)
+            .then_with(|| a.name().cmp(b.name()))
+    });
+    Ok(Keyring {
+        keys,
+        folder_count: files.len(),
+    })
+}
+
+/// Imports a key file and lists the keys again
+pub fn import(keys: &dyn KeyStore, store_dir: &Path, file_path: &Path, progress: &Progress<Event>) {
+    progress.report(Event::Imported(keys.import_keys(file_path)));
+    progress.report(Event::Loaded(load(keys, store_dir)));
+}
+
+/// Exports the public key with the given fingerprint
+pub fn export(
+    keys: &dyn KeyStore,
+    fingerprint: &str,
+    file_path: PathBuf,
+    progress: &Progress<Event>,
+) {
+    let result = keys.export_key(fingerprint, &file_path).map(|_| file_path);
+    progress.report(Event::Exported(result));
+}
+
+/// Formats the date of a point in time as `YYYY-MM-DD` in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let days = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => (duration.as_secs() / 86_400) as i64,
+        Err(e) => -(e.duration().as_secs().div_ceil(86_400) as i64),
+    };
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Formats a point in time as `YYYY-MM-DD HH:MM UTC`
+pub fn format_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    format!(
+        "{} {:02}:{:02} UTC",
+        format_date(time),
+        seconds / 3600 % 24,
+        seconds / 60 % 60
+    )
+}
+
+/// Parses a date like `2024-12-31` as the start of the day in UTC
+pub fn parse_date(date: &str) -> Option<SystemTime> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Inverse of the civil date calculation in `format_date`
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    let seconds = days.checked_mul(86_400)?;
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> SystemTime {
+        match u64::try_from(seconds) {
+            Ok(seconds) => UNIX_EPOCH + Duration::from_secs(seconds),
+            Err(_) => UNIX_EPOCH - Duration::from_secs(seconds.unsigned_abs()),
+        }
+    }
+
+    #[test]
+    fn dates_are_formatted_in_utc() {
+        assert_eq!(format_date(at(0)), "1970-01-01");
+        // Keys created before the epoch
+        assert_eq!(format_date(at(-1)), "1969-12-31");
+        assert_eq!(format_date(at(-86_400)), "1969-12-31");
+        assert_eq!(format_date(at(-86_401)), "1969-12-30");
+        // Leap days, including the one of a year divisible by 400
+        assert_eq!(format_date(at(951_782_400)), "2000-02-29");
+        assert_eq!(format_date(at(1_709_251_199)), "2024-02-29");
+        assert_eq!(format_date(at(1_709_251_200)), "2024-03-01");
+        assert_eq!(format_date(at(4_102_444_799)), "2099-12-31");
+        assert_eq!(format_time(at(1_709_287_260)), "2024-03-01 10:01 UTC");
+    }
+}
@@ -1,11 +1,39 @@
 use iced::advanced::graphics::image::image_rs::ImageFormat;
 
+mod access;
 mod app;
+mod authenticator;
+mod backend;
+mod breach;
+mod clipboard;
+mod clock;
+mod config;
+mod environment;
+mod file_info;
+mod git;
+mod gpg;
+mod health;
+mod history;
 mod icon;
+mod import;
+mod key_check;
+mod keyring;
 mod macros;
+mod notification;
+mod otp;
+mod pgp;
+mod pinentry;
+mod recipients;
+mod rotation;
 mod screen;
+mod service;
+mod share;
 mod sidebar;
+mod store;
+mod strength;
 mod theme;
+mod timeline;
+mod watcher;
 
 use app::App;
 
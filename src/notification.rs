@@ -0,0 +1,243 @@
+use iced::alignment::Vertical::Center;
+use iced::time::{self, Duration, Instant};
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text, Column};
+use iced::{color, Bottom, Color, Element, Fill, Right, Subscription, Theme};
+use std::collections::VecDeque;
+
+/// Maximum number of notifications that are stacked on screen at once
+const STACK_LIMIT: usize = 4;
+/// Maximum number of notifications that are kept in the history
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// Time after which a notification of this level is dismissed,
+    /// errors stay until they are dismissed manually
+    fn timeout(self) -> Option<Duration> {
+        match self {
+            Level::Info | Level::Success => Some(Duration::from_secs(4)),
+            Level::Warning => Some(Duration::from_secs(8)),
+            Level::Error => None,
+        }
+    }
+
+    fn color(self, theme: &Theme) -> Color {
+        let palette = theme.extended_palette();
+        match self {
+            Level::Info => palette.primary.strong.color,
+            Level::Success => palette.success.strong.color,
+            Level::Warning => color!(0xe5a50a),
+            Level::Error => palette.danger.strong.color,
+        }
+    }
+}
+
+/// A message that is surfaced to the user by any part of the app
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub level: Level,
+    pub text: String,
+}
+
+impl Notification {
+    pub fn new(level: Level, text: impl Into<String>) -> Self {
+        Self {
+            level,
+            text: text.into(),
+        }
+    }
+
+    pub fn info(text: impl Into<String>) -> Self {
+        Self::new(Level::Info, text)
+    }
+
+    pub fn success(text: impl Into<String>) -> Self {
+        Self::new(Level::Success, text)
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self::new(Level::Warning, text)
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        Self::new(Level::Error, text)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Tick(Instant),
+    Dismiss(u64),
+    ToggleHistory,
+    ClearHistory,
+}
+
+struct Toast {
+    id: u64,
+    notification: Notification,
+    deadline: Option<Instant>,
+}
+
+/// App-wide notification layer with auto-dismissed toasts and a history
+pub struct Notifications {
+    next_id: u64,
+    toasts: Vec<Toast>,
+    history: VecDeque<Notification>,
+    show_history: bool,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            toasts: Vec::new(),
+            history: VecDeque::new(),
+            show_history: false,
+        }
+    }
+
+    pub fn push(&mut self, notification: Notification) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_back();
+        }
+        self.history.push_front(notification.clone());
+
+        if self.toasts.len() == STACK_LIMIT {
+            self.toasts.remove(0);
+        }
+        let deadline = notification
+            .level
+            .timeout()
+            .map(|timeout| Instant::now() + timeout);
+        self.toasts.push(Toast {
+            id,
+            notification,
+            deadline,
+        });
+    }
+
+    /// Returns all notifications, the latest first
+    #[cfg(test)]
+    pub fn history(&self) -> impl Iterator<Item = &Notification> {
+        self.history.iter()
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::Tick(now) => self
+                .toasts
+                .retain(|toast| toast.deadline.is_none_or(|deadline| deadline > now)),
+            Message::Dismiss(id) => self.toasts.retain(|toast| toast.id != id),
+            Message::ToggleHistory => self.show_history = !self.show_history,
+            Message::ClearHistory => self.history.clear(),
+        }
+    }
+
+    pub fn view(&self) -> Option<Element<Message>> {
+        if self.toasts.is_empty() && !self.show_history {
+            return None;
+        }
+
+        let toasts = Column::with_children(self.toasts.iter().map(|toast| {
+            notification_box(
+                &toast.notification,
+                button("×")
+                    .style(button::text)
+                    .on_press(Message::Dismiss(toast.id)),
+            )
+        }))
+        .spacing(5)
+        .width(300);
+
+        let mut content = column![].spacing(10).align_x(Right);
+        if self.show_history {
+            content = content.push(self.history_panel());
+        }
+        content = content.push(toasts);
+
+        Some(
+            container(content)
+                .width(Fill)
+                .height(Fill)
+                .padding(15)
+                .align_x(Right)
+                .align_y(Bottom)
+                .into(),
+        )
+    }
+
+    fn history_panel(&self) -> Element<Message> {
+        let entries = if self.history.is_empty() {
+            Column::new().push(text("No notifications yet"))
+        } else {
+            Column::with_children(
+                self.history
+                    .iter()
+                    .map(|notification| notification_box(notification, horizontal_space())),
+            )
+            .spacing(5)
+        };
+
+        container(
+            column![
+                row![
+                    text("Notifications").width(Fill),
+                    button("Clear").on_press(Message::ClearHistory),
+                    button("Close").on_press(Message::ToggleHistory),
+                ]
+                .spacing(10)
+                .align_y(Center),
+                scrollable(entries).height(250),
+            ]
+            .spacing(10),
+        )
+        .width(300)
+        .padding(10)
+        .style(container::bordered_box)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.toasts.iter().any(|toast| toast.deadline.is_some()) {
+            time::every(Duration::from_millis(500)).map(Message::Tick)
+        } else {
+            Subscription::none()
+        }
+    }
+}
+
+fn notification_box<'a>(
+    notification: &'a Notification,
+    trailing: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    let level = notification.level;
+    container(
+        row![text(&notification.text).width(Fill), trailing.into()]
+            .spacing(10)
+            .align_y(Center),
+    )
+    .width(Fill)
+    .padding(10)
+    .style(move |theme: &Theme| {
+        let accent = level.color(theme);
+        container::Style {
+            border: iced::Border {
+                color: accent,
+                width: 2.0,
+                radius: 2.0.into(),
+            },
+            ..container::rounded_box(theme)
+        }
+    })
+    .into()
+}
@@ -0,0 +1,23 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use totp_rs::TOTP;
+
+/// Whether a line of a password file holds an `otpauth://` URL
+pub fn is_url(line: &str) -> bool {
+    line.starts_with("otpauth://")
+}
+
+/// Returns the first `otpauth://` URL of a password file
+pub fn find_url(file_contents: &str) -> Option<&str> {
+    file_contents.lines().find(|line| is_url(line))
+}
+
+/// Generates the one-time password that is valid at the given time
+/// from the `otpauth://` URL in a password file
+pub fn generate(file_contents: &str, now: SystemTime) -> Result<String, String> {
+    let url = find_url(file_contents).ok_or_else(|| "no OTP URL found".to_owned())?;
+    let totp = TOTP::from_url(url).map_err(|e| e.to_string())?;
+    let time = now
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("failed to generate OTP: {e}"))?;
+    Ok(totp.generate(time.as_secs()))
+}
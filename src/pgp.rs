@@ -0,0 +1,256 @@
+//! Minimal reader for the headers of OpenPGP messages
+//!
+//! Only the session key packets at the start of a message are parsed,
+//! they reveal which keys a password file is encrypted for without decrypting it.
+
+use std::{fmt, fs, io, path::Path};
+
+/// Packet tags as defined in RFC 9580
+const PUBLIC_KEY_SESSION_KEY: u8 = 1;
+const SYMMETRIC_KEY_SESSION_KEY: u8 = 3;
+const MARKER: u8 = 10;
+
+/// Tag and body of a packet together with the data that follows it
+type Packet<'a> = (u8, &'a [u8], &'a [u8]);
+
+/// 64 bit key ID in the notation of GnuPG
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyId(String);
+
+impl KeyId {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.iter().map(|byte| format!("{byte:02X}")).collect())
+    }
+
+    /// Whether the recipient was hidden when the message was encrypted
+    pub fn is_wildcard(&self) -> bool {
+        self.0.bytes().all(|byte| byte == b'0')
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Returns the IDs of the keys a password file is encrypted for
+pub fn recipients(file_path: &Path) -> io::Result<Vec<KeyId>> {
+    parse_recipients(&fs::read(file_path)?)
+}
+
+fn parse_recipients(mut data: &[u8]) -> io::Result<Vec<KeyId>> {
+    if data.starts_with(b"-----BEGIN PGP") {
+        return Err(invalid("ASCII armored messages are not supported"));
+    }
+    let mut key_ids = Vec::new();
+    while let Some((tag, body, rest)) = next_packet(data)? {
+        match tag {
+            PUBLIC_KEY_SESSION_KEY => key_ids.extend(session_key_recipient(body)?),
+            SYMMETRIC_KEY_SESSION_KEY | MARKER => (),
+            // The encrypted data follows the session key packets
+            _ => break,
+        }
+        data = rest;
+    }
+    Ok(key_ids)
+}
+
+/// Splits the next packet off the data and returns its tag and body,
+/// packets with a length that is not known upfront end the parsing
+fn next_packet(data: &[u8]) -> io::Result<Option<Packet<'_>>> {
+    let Some((&first, rest)) = data.split_first() else {
+        return Ok(None);
+    };
+    if first & 0x80 == 0 {
+        return Err(invalid("not an OpenPGP message"));
+    }
+
+    let (tag, length, rest) = if first & 0x40 != 0 {
+        // New packet format
+        let tag = first & 0x3f;
+        match rest {
+            [octet, rest @ ..] if *octet < 192 => (tag, *octet as usize, rest),
+            [octet, second, rest @ ..] if *octet < 224 => (
+                tag,
+                ((*octet as usize - 192) << 8) + *second as usize + 192,
+                rest,
+            ),
+            [255, a, b, c, d, rest @ ..] => {
+                (tag, u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest)
+            }
+            // Partial body lengths are only used for data packets
+            [224..=254, ..] => return Ok(None),
+            _ => return Err(invalid("truncated packet")),
+        }
+    } else {
+        // Legacy packet format
+        let tag = (first >> 2) & 0x0f;
+        match (first & 0x03, rest) {
+            (0, [a, rest @ ..]) => (tag, *a as usize, rest),
+            (1, [a, b, rest @ ..]) => (tag, u16::from_be_bytes([*a, *b]) as usize, rest),
+            (2, [a, b, c, d, rest @ ..]) => {
+                (tag, u32::from_be_bytes([*a, *b, *c, *d]) as usize, rest)
+            }
+            // The length of the packet is indeterminate
+            (3, _) => return Ok(None),
+            _ => return Err(invalid("truncated packet")),
+        }
+    };
+
+    if rest.len() < length {
+        return Err(invalid("truncated packet"));
+    }
+    let (body, rest) = rest.split_at(length);
+    Ok(Some((tag, body, rest)))
+}
+
+/// Reads the recipient of a public-key encrypted session key packet
+fn session_key_recipient(body: &[u8]) -> io::Result<Option<KeyId>> {
+    match body {
+        [3, key_id @ ..] if key_id.len() >= 8 => Ok(Some(KeyId::from_bytes(&key_id[..8]))),
+        // An empty fingerprint marks an anonymous recipient
+        [6, 0, ..] => Ok(Some(KeyId::from_bytes(&[0; 8]))),
+        [6, length, key_version, rest @ ..] => {
+            let fingerprint = rest
+                .get(..*length as usize - 1)
+                .ok_or_else(|| invalid("truncated session key packet"))?;
+            Ok(match (key_version, fingerprint.len()) {
+                // The key ID of a version 4 key is the end of its fingerprint
+                (4, 20) => Some(KeyId::from_bytes(&fingerprint[12..])),
+                (6, 32) => Some(KeyId::from_bytes(&fingerprint[..8])),
+                _ => None,
+            })
+        }
+        _ => Err(invalid("unsupported session key packet")),
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encrypted by GnuPG 2.2 for a Curve25519 and an RSA subkey,
+    /// in packets with legacy headers of one and two length octets
+    const TWO_RECIPIENTS: &[u8] = include_bytes!("../tests/fixtures/two-recipients.gpg");
+    /// Encrypted by GnuPG 2.2 with `--throw-keyids`
+    const HIDDEN_RECIPIENT: &[u8] = include_bytes!("../tests/fixtures/hidden-recipient.gpg");
+
+    const CURVE25519_KEY: &str = "C14F8EA815B65481";
+    const RSA_KEY: &str = "C544ADE7E4B6A858";
+
+    fn key_ids(data: &[u8]) -> Vec<String> {
+        parse_recipients(data)
+            .expect("header can be parsed")
+            .into_iter()
+            .map(|key_id| key_id.0)
+            .collect()
+    }
+
+    /// Splits the session key packets of the fixture off its encrypted data
+    fn session_key_packets(data: &[u8]) -> (Vec<&[u8]>, &[u8]) {
+        let mut bodies = Vec::new();
+        let mut rest = data;
+        while let Some((PUBLIC_KEY_SESSION_KEY, body, next)) =
+            next_packet(rest).expect("fixture can be parsed")
+        {
+            bodies.push(body);
+            rest = next;
+        }
+        (bodies, rest)
+    }
+
+    /// Packet with a header in the new format and the given length octets
+    fn new_format(tag: u8, length: &[u8], body: &[u8]) -> Vec<u8> {
+        [&[0xc0 | tag], length, body].concat()
+    }
+
+    #[test]
+    fn version_3_session_keys_name_their_key_ids() {
+        assert_eq!(key_ids(TWO_RECIPIENTS), [CURVE25519_KEY, RSA_KEY]);
+    }
+
+    #[test]
+    fn hidden_recipients_have_wildcard_key_ids() {
+        let key_ids = parse_recipients(HIDDEN_RECIPIENT).expect("header can be parsed");
+        assert_eq!(key_ids.len(), 1);
+        assert!(key_ids[0].is_wildcard());
+        assert!(parse_recipients(TWO_RECIPIENTS)
+            .expect("header can be parsed")
+            .iter()
+            .all(|key_id| !key_id.is_wildcard()));
+    }
+
+    #[test]
+    fn new_format_packet_lengths_are_read() {
+        let (bodies, encrypted_data) = session_key_packets(TWO_RECIPIENTS);
+        let (curve25519, rsa) = (bodies[0], bodies[1]);
+        assert!(curve25519.len() < 192 && (192..8384).contains(&rsa.len()));
+
+        let two_octets = (rsa.len() - 192) as u16 + (192 << 8);
+        let five_octets = [&[255], (curve25519.len() as u32).to_be_bytes().as_slice()].concat();
+        let data = [
+            new_format(
+                PUBLIC_KEY_SESSION_KEY,
+                &[curve25519.len() as u8],
+                curve25519,
+            ),
+            new_format(PUBLIC_KEY_SESSION_KEY, &two_octets.to_be_bytes(), rsa),
+            new_format(PUBLIC_KEY_SESSION_KEY, &five_octets, curve25519),
+            encrypted_data.to_vec(),
+        ]
+        .concat();
+        assert_eq!(key_ids(&data), [CURVE25519_KEY, RSA_KEY, CURVE25519_KEY]);
+    }
+
+    #[test]
+    fn version_6_session_keys_name_v4_and_v6_fingerprints() {
+        // Fingerprint of the Curve25519 subkey of the fixtures
+        let v4_fingerprint = b"\xf1\x39\xbe\xd0\x4e\x06\x8f\xfe\xf8\x5b\
+                               \xc6\xb2\xc1\x4f\x8e\xa8\x15\xb6\x54\x81";
+        let v6_fingerprint: Vec<u8> = (1..=32).collect();
+        // Algorithm X25519 followed by a dummy ephemeral key and session key
+        let encrypted_key = [[25].as_slice(), &[0x55; 32], &[16], &[0xaa; 16]].concat();
+        let session_key = |fingerprint: &[u8], key_version: u8| {
+            let mut body = vec![6, fingerprint.len() as u8 + 1, key_version];
+            body.extend_from_slice(fingerprint);
+            body.extend_from_slice(&encrypted_key);
+            new_format(PUBLIC_KEY_SESSION_KEY, &[body.len() as u8], &body)
+        };
+        let anonymous = [[6, 0].as_slice(), &encrypted_key].concat();
+        let data = [
+            session_key(v4_fingerprint, 4),
+            session_key(&v6_fingerprint, 6),
+            new_format(PUBLIC_KEY_SESSION_KEY, &[anonymous.len() as u8], &anonymous),
+        ]
+        .concat();
+        assert_eq!(
+            key_ids(&data),
+            [CURVE25519_KEY, "0102030405060708", "0000000000000000"]
+        );
+
+        let truncated = [6, 21, 4, 0xf1, 0x39];
+        assert!(session_key_recipient(&truncated).is_err());
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let (bodies, _) = session_key_packets(TWO_RECIPIENTS);
+        // Inside the body of the first packet, after the tag of the second one
+        // and between its two length octets
+        for end in [20, bodies[0].len() + 3, bodies[0].len() + 4] {
+            let error = parse_recipients(&TWO_RECIPIENTS[..end]).expect_err("input is truncated");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+        assert!(parse_recipients(&[]).expect("nothing to parse").is_empty());
+        assert!(parse_recipients(b"hunter2").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use iced::futures::{channel::mpsc, SinkExt, StreamExt};
+use iced::widget::{button, column, container, opaque, row, text, text_input};
+use iced::{stream, Color, Element, Fill, Font, Subscription};
+use std::{
+    collections::VecDeque,
+    sync::{mpsc as std_mpsc, Mutex},
+};
+
+/// Number of passphrases that are tried before decryption is given up
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Forwards passphrase requests of gpgme to the app while the subscription is running
+static PROMPT: Mutex<Option<mpsc::UnboundedSender<PassphraseRequest>>> = Mutex::new(None);
+
+/// Request for the passphrase of a secret key, answered by the passphrase dialog
+#[derive(Debug, Clone)]
+pub struct PassphraseRequest {
+    /// User ID of the key as reported by gpg
+    pub key: String,
+    /// Number of the attempt, starting at 1
+    pub attempt: u32,
+    /// Whether the previous passphrase was wrong
+    pub bad_passphrase: bool,
+    reply: std_mpsc::Sender<Option<String>>,
+}
+
+impl PassphraseRequest {
+    /// Sends the passphrase to the waiting decryption, `None` cancels it
+    fn answer(&self, passphrase: Option<String>) {
+        // The decryption may have been given up in the meantime
+        let _ = self.reply.send(passphrase);
+    }
+}
+
+/// Whether passphrases are asked for in the app instead of by gpg-agent's pinentry
+pub fn is_enabled() -> bool {
+    PROMPT
+        .lock()
+        .expect("another thread holding the lock panicked")
+        .as_ref()
+        .is_some_and(|sender| !sender.is_closed())
+}
+
+/// Asks the app for a passphrase and waits for the answer,
+/// `None` means that the request was cancelled
+pub fn ask(key: String, attempt: u32, bad_passphrase: bool) -> Option<String> {
+    let (reply, answer) = std_mpsc::channel();
+    let request = PassphraseRequest {
+        key,
+        attempt,
+        bad_passphrase,
+        reply,
+    };
+    PROMPT
+        .lock()
+        .expect("another thread holding the lock panicked")
+        .as_ref()?
+        .unbounded_send(request)
+        .ok()?;
+    answer.recv().ok().flatten()
+}
+
+/// Receives passphrase requests, the loopback pinentry is used as long as this is running
+pub fn requests() -> Subscription<PassphraseRequest> {
+    Subscription::run_with_id(
+        "pinentry",
+        stream::channel(1, |mut output| async move {
+            let (sender, mut receiver) = mpsc::unbounded();
+            *PROMPT
+                .lock()
+                .expect("another thread holding the lock panicked") = Some(sender);
+
+            while let Some(request) = receiver.next().await {
+                if output.send(request).await.is_err() {
+                    break;
+                }
+            }
+        }),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PassphraseChanged(String),
+    Submit,
+    Cancel,
+}
+
+/// Modal dialog that asks for the passphrases of pending requests one after another
+pub struct Dialog {
+    requests: VecDeque<PassphraseRequest>,
+    passphrase: String,
+}
+
+impl Dialog {
+    pub fn new() -> Self {
+        Self {
+            requests: VecDeque::new(),
+            passphrase: String::new(),
+        }
+    }
+
+    pub fn push(&mut self, request: PassphraseRequest) {
+        self.requests.push_back(request);
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::PassphraseChanged(passphrase) => self.passphrase = passphrase,
+            Message::Submit => {
+                if let Some(request) = self.requests.pop_front() {
+                    request.answer(Some(std::mem::take(&mut self.passphrase)));
+                }
+            }
+            Message::Cancel => {
+                if let Some(request) = self.requests.pop_front() {
+                    request.answer(None);
+                }
+                self.passphrase.clear();
+            }
+        }
+    }
+
+    /// Cancels all pending requests
+    pub fn cancel_all(&mut self) {
+        for request in self.requests.drain(..) {
+            request.answer(None);
+        }
+        self.passphrase.clear();
+    }
+
+    pub fn view(&self) -> Option<Element<Message>> {
+        let request = self.requests.front()?;
+
+        let mut content = column![
+            text("Passphrase required"),
+            text(format!("Unlock the secret key of {}", request.key)),
+        ]
+        .spacing(10);
+        if request.bad_passphrase {
+            content = content.push(
+                text(format!(
+                    "Bad passphrase, attempt {} of {MAX_ATTEMPTS}",
+                    request.attempt
+                ))
+                .style(text::danger),
+            );
+        }
+        content = content
+            .push(
+                text_input("Passphrase", &self.passphrase)
+                    .secure(true)
+                    .font(Font::MONOSPACE)
+                    .on_input(Message::PassphraseChanged)
+                    .on_submit(Message::Submit),
+            )
+            .push(
+                row![
+                    button("Cancel")
+                        .style(button::secondary)
+                        .on_press(Message::Cancel),
+                    button("Unlock").on_press(Message::Submit),
+                ]
+                .spacing(10),
+            );
+
+        let dialog = container(content)
+            .width(400)
+            .padding(20)
+            .style(container::bordered_box);
+        // Blocks the rest of the app until the request is answered
+        Some(opaque(container(dialog).center(Fill).style(|_| {
+            container::background(Color::from_rgba(0.0, 0.0, 0.0, 0.5))
+        })))
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{self, Backend},
+    service::Progress,
+    store,
+};
+
+/// Returns the recipients file (`.gpg-id` or `.age-recipients`) that governs a folder
+/// of the store, which is the nearest one up the tree
+///
+/// The folder is given relative to the store directory, the store root is empty.
+pub fn governing_file(backend: &dyn Backend, store_dir: &Path, folder: &str) -> Option<PathBuf> {
+    let mut dir = store_dir.join(folder);
+    loop {
+        let file = dir.join(backend.recipients_file());
+        if file.is_file() {
+            return Some(file);
+        }
+        if dir == store_dir || !dir.pop() || !dir.starts_with(store_dir) {
+            return None;
+        }
+    }
+}
+
+/// Reads the recipients of a recipients file, one per line
+pub fn read(file: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(file)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect())
+}
+
+pub fn write(file: &Path, recipients: &[String]) -> io::Result<()> {
+    let mut contents = recipients.join("\n");
+    contents.push('\n');
+    fs::write(file, contents)
+}
+
+/// Lists all folders of the store relative to the store directory,
+/// the store root comes first as an empty string
+pub fn folders(store_dir: &Path) -> Vec<String> {
+    let mut folders = vec![String::new()];
+    collect_folders(store_dir, store_dir, &mut folders);
+    folders.sort();
+    folders
+}
+
+/// Lists all recipients files of the store
+pub fn files(backend: &dyn Backend, store_dir: &Path) -> Vec<PathBuf> {
+    folders(store_dir)
+        .into_iter()
+        .map(|folder| store_dir.join(folder).join(backend.recipients_file()))
+        .filter(|file| file.is_file())
+        .collect()
+}
+
+fn collect_folders(store_dir: &Path, dir: &Path, folders: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || store::is_hidden(&path) {
+            continue;
+        }
+        if let Ok(relative) = path.strip_prefix(store_dir) {
+            folders.push(relative.to_string_lossy().into());
+        }
+        collect_folders(store_dir, &path, folders);
+    }
+}
+
+/// Lists the password files that are governed by the recipients file of a folder
+///
+/// Subfolders with their own recipients file are skipped like `pass init` does.
+pub fn governed_files(backend: &dyn Backend, folder_dir: &Path) -> Vec<PathBuf> {
+    store::password_files(
+        folder_dir,
+        backend.extension(),
+        Some(backend.recipients_file()),
+    )
+}
+
+/// Lists all password files of the store
+pub fn password_files(backend: &dyn Backend, store_dir: &Path) -> Vec<PathBuf> {
+    store::password_files(store_dir, backend.extension(), None)
+}
+
+/// Progress of a re-encryption
+#[derive(Debug, Clone)]
+pub enum Reencryption {
+    Progress { done: usize, total: usize },
+    Finished(Summary),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub reencrypted: usize,
+    pub total: usize,
+    pub failures: Vec<(PathBuf, String)>,
+    pub cancelled: bool,
+}
+
+/// Encrypts the given password files again for the recipients,
+/// stops after the current file when it gets cancelled
+pub fn reencrypt(
+    backend: &dyn Backend,
+    files: Vec<PathBuf>,
+    recipients: Vec<String>,
+    progress: &Progress<Reencryption>,
+) {
+    let total = files.len();
+    let mut summary = Summary {
+        total,
+        ..Summary::default()
+    };
+    let mut encrypter = match backend.encrypter(&recipients) {
+        Ok(encrypter) => encrypter,
+        Err(e) => {
+            summary.failures = files.into_iter().map(|file| (file, e.clone())).collect();
+            progress.report(Reencryption::Finished(summary));
+            return;
+        }
+    };
+
+    for (done, file) in files.into_iter().enumerate() {
+        if progress.is_cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        match backend::reencrypt(backend, encrypter.as_mut(), &file) {
+            Ok(()) => summary.reencrypted += 1,
+            Err(e) => summary.failures.push((file, e)),
+        }
+        progress.report(Reencryption::Progress {
+            done: done + 1,
+            total,
+        });
+    }
+    progress.report(Reencryption::Finished(summary));
+}
@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{self, Backend, Gpg},
+    git,
+    gpg::{self, KeyInfo},
+    recipients,
+    service::Progress,
+};
+
+/// Key that can be selected in the rotation wizard
+#[derive(Debug, Clone)]
+pub struct KeyOption {
+    /// Recipient as written in `.gpg-id` files
+    pub id: String,
+    pub label: String,
+}
+
+impl PartialEq for KeyOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl fmt::Display for KeyOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Keys that can be replaced and keys they can be replaced with
+#[derive(Debug, Clone)]
+pub struct Candidates {
+    /// Recipients of the `.gpg-id` files of the store
+    pub old: Vec<KeyOption>,
+    /// Keys in the keyring that can be used for encryption
+    pub new: Vec<KeyOption>,
+}
+
+/// A `.gpg-id` file that lists the old key
+#[derive(Debug, Clone)]
+pub struct FolderChange {
+    pub gpg_id: PathBuf,
+    pub folder: String,
+    pub recipients: Vec<String>,
+    /// Password files that are governed by the `.gpg-id` file
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub old: KeyOption,
+    pub new: KeyOption,
+    pub changes: Vec<FolderChange>,
+}
+
+impl Plan {
+    pub fn file_count(&self) -> usize {
+        self.changes.iter().map(|change| change.files.len()).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub folders: usize,
+    pub files: usize,
+    /// Result of the git commit, `None` when the store is not a repository
+    pub commit: Option<Result<(), String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Candidates(Result<Candidates, String>),
+    Planned(Result<Plan, String>),
+    Progress { done: usize, total: usize },
+    Finished(Result<Report, String>),
+}
+
+/// Collects the keys that can be selected in the wizard
+pub fn candidates(store_dir: &Path) -> Result<Candidates, String> {
+    let recipients: Vec<String> = recipients::files(&Gpg, store_dir)
+        .iter()
+        .flat_map(|gpg_id| recipients::read(gpg_id).unwrap_or_default())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let resolved = gpg::resolve_keys(&recipients).map_err(|e| e.to_string())?;
+    let old = recipients
+        .into_iter()
+        .map(|recipient| {
+            let label = match resolved.iter().find(|key| key.recipient == recipient) {
+                Some(key) => format!("{} ({recipient})", key_name(key)),
+                None => format!("{recipient} (not in keyring)"),
+            };
+            KeyOption {
+                id: recipient,
+                label,
+            }
+        })
+        .collect();
+
+    let new = gpg::list_keys()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(KeyInfo::can_encrypt)
+        .map(|key| KeyOption {
+            label: format!("{} ({})", key_name(&key), key.fingerprint),
+            id: key.fingerprint,
+        })
+        .collect();
+    Ok(Candidates { old, new })
+}
+
+fn key_name(key: &KeyInfo) -> &str {
+    key.user_ids.first().unwrap_or(&key.fingerprint)
+}
+
+/// Finds the `.gpg-id` files that list the old key and the password files they govern
+///
+/// A recipient matches the old key when it is written the same way
+/// or resolves to the same key in the keyring.
+pub fn plan(store_dir: &Path, old: KeyOption, new: KeyOption) -> Result<Plan, String> {
+    let fingerprint = |recipients: &[String]| -> Result<Vec<Option<String>>, String> {
+        let keys = gpg::resolve_keys(recipients).map_err(|e| e.to_string())?;
+        Ok(recipients
+            .iter()
+            .map(|recipient| {
+                keys.iter()
+                    .find(|key| &key.recipient == recipient)
+                    .map(|key| key.fingerprint.clone())
+            })
+            .collect())
+    };
+    let old_fingerprint = fingerprint(std::slice::from_ref(&old.id))?.pop().flatten();
+
+    let mut changes = Vec::new();
+    for gpg_id in recipients::files(&Gpg, store_dir) {
+        let current = recipients::read(&gpg_id).map_err(|e| e.to_string())?;
+        let fingerprints = fingerprint(&current)?;
+        let is_old = |(recipient, fingerprint): &(&String, &Option<String>)| {
+            **recipient == old.id || (old_fingerprint.is_some() && **fingerprint == old_fingerprint)
+        };
+        if !current.iter().zip(&fingerprints).any(|pair| is_old(&pair)) {
+            continue;
+        }
+
+        let mut recipients = Vec::new();
+        for pair in current.iter().zip(&fingerprints) {
+            let recipient = if is_old(&pair) { &new.id } else { pair.0 };
+            if !recipients.contains(recipient) {
+                recipients.push(recipient.clone());
+            }
+        }
+        let dir = gpg_id.parent().unwrap_or(store_dir);
+        changes.push(FolderChange {
+            folder: dir
+                .strip_prefix(store_dir)
+                .map(|folder| folder.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            files: recipients::governed_files(&Gpg, dir),
+            gpg_id,
+            recipients,
+        });
+    }
+    Ok(Plan { old, new, changes })
+}
+
+/// Rewrites the `.gpg-id` files and re-encrypts the password files of the plan
+/// with the backend of the store
+///
+/// All files are restored when anything fails or the rotation is cancelled.
+/// The changes are committed when the store is a git repository.
+pub fn rotate(backend: &dyn Backend, store_dir: &Path, plan: Plan, progress: &Progress<Event>) {
+    let mut backup = Vec::new();
+    let result =
+        apply(backend, &plan, &mut backup, progress).map_err(|e| match rollback(&backup) {
+            Ok(()) => format!("{e}, all changes were rolled back"),
+            Err(rollback_error) => {
+                format!("{e}, rolling back failed as well: {rollback_error}")
+            }
+        });
+
+    let report = result.map(|()| {
+        let commit = git::is_repository(store_dir).then(|| {
+            let paths: Vec<PathBuf> = backup.iter().map(|(path, _)| path.clone()).collect();
+            let message = format!(
+                "Replace key {} with {} for {} folders",
+                plan.old.id,
+                plan.new.id,
+                plan.changes.len()
+            );
+            git::commit(store_dir, &paths, &message)
+        });
+        Report {
+            folders: plan.changes.len(),
+            files: plan.file_count(),
+            commit,
+        }
+    });
+    progress.report(Event::Finished(report));
+}
+
+/// Applies the plan and keeps the original contents of every file it changes
+fn apply(
+    backend: &dyn Backend,
+    plan: &Plan,
+    backup: &mut Vec<(PathBuf, Vec<u8>)>,
+    progress: &Progress<Event>,
+) -> Result<(), String> {
+    let total = plan.file_count();
+    let mut done = 0;
+    for change in &plan.changes {
+        let mut encrypter = backend.encrypter(&change.recipients)?;
+
+        backup.push((change.gpg_id.clone(), read(&change.gpg_id)?));
+        recipients::write(&change.gpg_id, &change.recipients)
+            .map_err(|e| format!("{}: {e}", change.gpg_id.display()))?;
+
+        for file in &change.files {
+            if progress.is_cancelled() {
+                return Err("The rotation was cancelled".to_owned());
+            }
+            backup.push((file.clone(), read(file)?));
+            backend::reencrypt(backend, encrypter.as_mut(), file)
+                .map_err(|e| format!("{}: {e}", file.display()))?;
+            done += 1;
+            progress.report(Event::Progress { done, total });
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Restores the original contents of all changed files
+fn rollback(backup: &[(PathBuf, Vec<u8>)]) -> Result<(), String> {
+    let errors: Vec<String> = backup
+        .iter()
+        .rev()
+        .filter_map(|(path, contents)| {
+            fs::write(path, contents)
+                .err()
+                .map(|e| format!("{}: {e}", path.display()))
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendKind, Memory};
+    use iced::futures::{channel::mpsc, executor::block_on, StreamExt};
+
+    fn key(id: &str) -> KeyOption {
+        KeyOption {
+            id: id.to_owned(),
+            label: id.to_owned(),
+        }
+    }
+
+    #[test]
+    fn failed_rotations_are_rolled_back() {
+        let store_dir = tempfile::tempdir().expect("temporary directory can be created");
+        let store_dir = store_dir.path();
+        let gpg_id = store_dir.join(".age-recipients");
+        fs::write(&gpg_id, "age1alice\n").expect("file can be written");
+        let (bank, forum) = (store_dir.join("bank.age"), store_dir.join("forum.age"));
+        fs::write(&bank, "").expect("file can be written");
+        fs::write(&forum, "").expect("file can be written");
+        let backend = Memory::new(BackendKind::Age);
+        // The forum cannot be decrypted after the bank was re-encrypted
+        backend.insert(bank.clone(), "bank secret\n");
+        let plan = Plan {
+            old: key("age1alice"),
+            new: key("age1bob"),
+            changes: vec![FolderChange {
+                gpg_id: gpg_id.clone(),
+                folder: String::new(),
+                recipients: vec!["age1bob".to_owned()],
+                files: vec![bank.clone(), forum],
+            }],
+        };
+
+        let (sender, receiver) = mpsc::unbounded();
+        rotate(&backend, store_dir, plan, &Progress::detached(sender));
+        let events: Vec<Event> = block_on(receiver.collect());
+        let [Event::Progress { done: 1, total: 2 }, Event::Finished(Err(e))] = events.as_slice()
+        else {
+            panic!("rotation fails after the first file: {events:?}");
+        };
+        assert!(e.ends_with("all changes were rolled back"), "{e}");
+        assert_eq!(
+            fs::read_to_string(&gpg_id).expect("file exists"),
+            "age1alice\n"
+        );
+        assert_eq!(fs::read_to_string(&bank).expect("file exists"), "");
+    }
+}
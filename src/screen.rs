@@ -1,17 +1,62 @@
 use iced::{widget::horizontal_space, Element};
+use std::{fmt, sync::Arc};
 
+pub mod access;
 pub mod dashboard;
+pub mod health;
+pub mod import;
+pub mod keys;
+pub mod otp_export;
+pub mod recipients;
+pub mod rotation;
 pub mod settings;
+pub mod share;
+pub mod timeline;
 
+use self::access::Access;
 use self::dashboard::Dashboard;
+use self::health::Health;
+use self::import::Import;
+use self::keys::Keys;
+use self::otp_export::OtpExport;
+use self::recipients::Recipients;
+use self::rotation::Rotation;
 use self::settings::Settings;
+use self::share::Share;
+use self::timeline::Timeline;
+use crate::store::StoreInfo;
 
 pub enum Screen {
     Loading,
     Settings(Settings),
-    Dashboard(Dashboard),
+    Dashboard(Box<Dashboard>),
+    Recipients(Recipients),
+    Access(Access),
+    Rotation(Rotation),
+    Keys(Keys),
+    Import(Import),
+    OtpExport(OtpExport),
+    Share(Share),
+    Health(Health),
+    Timeline(Timeline),
 }
 
 pub fn loading<'a, Message: 'a>() -> Element<'a, Message> {
     horizontal_space().into()
 }
+
+/// Option of a store picker on screens that work on a single store
+#[derive(Debug, Clone)]
+pub struct StoreOption(pub Arc<StoreInfo>);
+
+impl PartialEq for StoreOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.index == other.0.index
+    }
+}
+
+impl fmt::Display for StoreOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.name)
+    }
+}
@@ -0,0 +1,338 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable, text,
+    Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{collections::BTreeSet, sync::Arc};
+
+use crate::{
+    access::{self, AccessMatrix, EntryAccess, Scan},
+    backend::BackendKind,
+    notification::Notification,
+    service::{Channel, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum View {
+    ByKey,
+    ByFolder,
+    Mismatches,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    SelectView(View),
+    Rescan,
+    Scanned((RequestId, Scan)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+/// Audit of who can decrypt which folders and entries of a store
+pub struct Access {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    view: View,
+    matrix: Option<AccessMatrix>,
+    scan_request: Option<RequestId>,
+    progress: (usize, usize),
+}
+
+impl Access {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut access = Self {
+            stores,
+            store,
+            view: View::ByKey,
+            matrix: None,
+            scan_request: None,
+            progress: (0, 0),
+        };
+        let task = access.scan(service);
+        (access, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Access".to_owned()
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                self.matrix = None;
+                Action::Run(self.scan(service))
+            }
+            Message::SelectView(view) => {
+                self.view = view;
+                Action::None
+            }
+            Message::Rescan => Action::Run(self.scan(service)),
+            Message::Scanned((id, scan)) => {
+                if self.scan_request != Some(id) {
+                    return Action::None;
+                }
+                match scan {
+                    Scan::Progress { done, total } => {
+                        self.progress = (done, total);
+                        Action::None
+                    }
+                    Scan::Finished(result) => {
+                        self.scan_request = None;
+                        match result {
+                            Ok(matrix) => {
+                                self.matrix = Some(matrix);
+                                Action::None
+                            }
+                            Err(e) => Action::Notify(Notification::error(format!(
+                                "Access could not be determined: {e}"
+                            ))),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Scans the selected store in the background
+    fn scan(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store_dir = self.store.0.dir.clone();
+        let kind = self.store.0.backend.kind();
+        let (id, task) = service.run(Channel::Audit, move |progress| {
+            if kind == BackendKind::Gpg {
+                access::scan(&store_dir, progress)
+            } else {
+                progress.report(Scan::Finished(Err(
+                    "the audit is only available for GPG stores".to_owned(),
+                )))
+            }
+        });
+        self.scan_request = Some(id);
+        self.progress = (0, 0);
+        task.map(Message::Scanned)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let mut header = row![].spacing(10).align_y(Center);
+        if self.stores.len() > 1 {
+            header = header.push(text("Store:")).push(
+                pick_list(
+                    self.stores.as_slice(),
+                    Some(&self.store),
+                    Message::SelectStore,
+                )
+                .width(150),
+            );
+        }
+        let mismatches = self.matrix.as_ref().map_or(0, |m| m.mismatches().count());
+        header = header
+            .push(view_button("By key", View::ByKey, self.view))
+            .push(view_button("By folder", View::ByFolder, self.view))
+            .push(view_button(
+                &format!("Mismatches ({mismatches})"),
+                View::Mismatches,
+                self.view,
+            ))
+            .push(
+                button("Rescan")
+                    .on_press_maybe(self.scan_request.is_none().then_some(Message::Rescan)),
+            );
+
+        let content: Element<Message> = match (&self.matrix, self.scan_request) {
+            (_, Some(_)) => {
+                let (done, total) = self.progress;
+                row![
+                    progress_bar(0.0..=total.max(1) as f32, done as f32).height(20),
+                    text(format!("{done} / {total}")),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+            }
+            (Some(matrix), None) => scrollable(
+                match self.view {
+                    View::ByKey => by_key(matrix),
+                    View::ByFolder => by_folder(matrix),
+                    View::Mismatches => mismatch_list(matrix),
+                }
+                .spacing(10)
+                .padding([0, 12]),
+            )
+            .height(Fill)
+            .into(),
+            (None, None) => text("The store has not been scanned").into(),
+        };
+
+        column![text("Access"), horizontal_rule(38), header, content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+fn view_button(label: &str, view: View, selected: View) -> Element<'static, Message> {
+    button(text(label.to_owned()))
+        .style(if view == selected {
+            button::primary
+        } else {
+            button::secondary
+        })
+        .on_press(Message::SelectView(view))
+        .into()
+}
+
+/// Lists every reader with the folders it is a recipient of and the entries it can decrypt
+fn by_key(matrix: &AccessMatrix) -> Column<Message> {
+    let mut readers: Vec<_> = matrix.readers.values().collect();
+    readers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Column::with_children(readers.into_iter().map(|reader| {
+        let folders = folder_names(matrix.folders_of(&reader.id).map(|f| f.folder.as_str()));
+        let readable = matrix.entries_of(&reader.id).count();
+        let unexpected: Vec<&str> = matrix
+            .entries_of(&reader.id)
+            .filter(|entry| entry.unexpected.contains(&reader.id))
+            .map(|entry| entry.pass_id.as_str())
+            .collect();
+
+        let mut card = column![
+            text(&reader.name),
+            text(&reader.id).font(Font::MONOSPACE).size(12),
+            text(format!("Recipient of: {folders}")),
+            text(format!(
+                "Can decrypt {readable} of {} entries",
+                matrix.entries.len()
+            )),
+        ]
+        .spacing(5);
+        if !reader.known {
+            card = card.push(text("Key is not in the keyring").style(text::danger));
+        }
+        if !unexpected.is_empty() {
+            card = card.push(
+                text(format!(
+                    "Can still decrypt entries outside its folders: {}",
+                    unexpected.join(", ")
+                ))
+                .style(text::danger),
+            );
+        }
+        card_container(card)
+    }))
+}
+
+/// Lists every folder with a `.gpg-id` file and its readers
+fn by_folder(matrix: &AccessMatrix) -> Column<Message> {
+    Column::with_children(matrix.folders.iter().map(|folder| {
+        let entries = matrix.entries_in(&folder.folder).count();
+        let mismatches = matrix
+            .entries_in(&folder.folder)
+            .filter(|entry| entry.is_mismatch())
+            .count();
+
+        let mut card = column![
+            text(format!("{}/", folder.folder)).font(Font::MONOSPACE),
+            text(format!(
+                "Readers: {}",
+                reader_names(matrix, &folder.readers)
+            )),
+            text(format!("{entries} entries")),
+        ]
+        .spacing(5);
+        if mismatches > 0 {
+            card = card.push(
+                text(format!(
+                    "{mismatches} entries are encrypted for other readers"
+                ))
+                .style(text::danger),
+            );
+        }
+        card_container(card)
+    }))
+}
+
+/// Lists the entries that are not encrypted for the recipients of their `.gpg-id` file
+fn mismatch_list(matrix: &AccessMatrix) -> Column<Message> {
+    let entries = matrix
+        .entries
+        .iter()
+        .filter(|entry| entry.is_mismatch() || entry.error.is_some());
+    Column::with_children(entries.map(|entry| card_container(entry_mismatch(matrix, entry))))
+}
+
+fn entry_mismatch<'a>(matrix: &AccessMatrix, entry: &'a EntryAccess) -> Column<'a, Message> {
+    let mut card = column![text(&entry.pass_id).font(Font::MONOSPACE)].spacing(5);
+    if let Some(e) = &entry.error {
+        card = card.push(text(format!("Packet header could not be read: {e}")).style(text::danger));
+    }
+    if entry.governed_by.is_none() {
+        card = card.push(text("No .gpg-id file governs this entry").style(text::danger));
+    }
+    if !entry.unexpected.is_empty() {
+        card = card.push(
+            text(format!(
+                "Readable by readers not in .gpg-id: {}",
+                reader_names(matrix, &entry.unexpected)
+            ))
+            .style(text::danger),
+        );
+    }
+    if !entry.missing.is_empty() {
+        card = card.push(
+            text(format!(
+                "Not readable by: {}",
+                reader_names(matrix, &entry.missing)
+            ))
+            .style(text::danger),
+        );
+    }
+    card
+}
+
+fn card_container<'a>(card: Column<'a, Message>) -> Element<'a, Message> {
+    container(card)
+        .width(Fill)
+        .padding(10)
+        .style(container::rounded_box)
+        .into()
+}
+
+fn reader_names(matrix: &AccessMatrix, readers: &BTreeSet<String>) -> String {
+    readers
+        .iter()
+        .map(|reader| matrix.name(reader))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn folder_names<'a>(folders: impl Iterator<Item = &'a str>) -> String {
+    let folders: Vec<String> = folders.map(|folder| format!("{folder}/")).collect();
+    if folders.is_empty() {
+        "no folders".to_owned()
+    } else {
+        folders.join(", ")
+    }
+}
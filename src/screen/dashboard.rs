@@ -1,37 +1,61 @@
-use iced::widget::{container, row};
-use iced::{Element, Left, Subscription, Task, Top};
-use passepartout::PasswordInfo;
+use iced::alignment::Vertical::Center;
+use iced::widget::{button, column, container, row, text, Column};
+use iced::{Element, Fill, Font, Left, Subscription, Task, Top};
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
 pub mod password_details;
-mod password_list;
+pub mod password_list;
 
 use self::{password_details::PasswordDetails, password_list::PasswordList};
+use crate::{
+    config::StoreState,
+    icon,
+    key_check::KeyWarning,
+    notification::Notification,
+    service::StoreService,
+    store::{Entry, StoreInfo},
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     PasswordList(password_list::Message),
     PasswordDetails(password_details::Message),
+    RevealFolder(String),
 }
 
 pub enum Action {
     None,
     Run(Task<Message>),
-    Back,
+    Notify(Notification),
+    /// Opens the recipients of a folder of the store with the given index
+    ShowRecipients(usize, String),
+    /// Shares the entries of the selected entry or folder with someone else
+    ExportFor(Vec<Entry>, String),
+    /// Whether the password of the entry was found in the breach list
+    BreachChecked(Entry, bool),
 }
 
 pub struct Dashboard {
     password_list: PasswordList,
     password_details: PasswordDetails,
+    key_warnings: Vec<(Arc<StoreInfo>, KeyWarning)>,
 }
 
 impl Dashboard {
-    pub fn new(passwords: Vec<PasswordInfo>) -> (Self, Task<Message>) {
-        let (password_list, _) = PasswordList::new(passwords);
+    /// Creates a dashboard for the entries of one store or,
+    /// with store badges, for the merged entries of several stores
+    pub fn new(
+        passwords: Vec<Entry>,
+        state: StoreState,
+        show_badges: bool,
+    ) -> (Self, Task<Message>) {
+        let (password_list, _) = PasswordList::new(passwords, state, show_badges);
         let (password_details, _) = PasswordDetails::new();
         (
             Self {
                 password_list,
                 password_details,
+                key_warnings: Vec::new(),
             },
             Task::none(),
         )
@@ -41,38 +65,91 @@ impl Dashboard {
         "Partout".to_owned()
     }
 
-    pub fn update(&mut self, message: Message) -> Action {
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
         match message {
             Message::PasswordList(message) => {
                 let action = self.password_list.update(message);
                 match action {
                     password_list::Action::SelectEntry(entry) => {
                         // should I really create a new message here?
-                        match self
-                            .password_details
-                            .update(password_details::Message::SelectEntry(entry))
-                        {
-                            password_details::Action::Run(task) => {
-                                Action::Run(task.map(Message::PasswordDetails))
-                            }
-                            _ => Action::None,
-                        }
+                        details_action(
+                            self.password_details
+                                .update(password_details::Message::SelectEntry(entry), service),
+                        )
+                    }
+                    password_list::Action::ExportFor(entries, selection) => {
+                        Action::ExportFor(entries, selection)
                     }
                     _ => Action::None,
                 }
             }
+            Message::RevealFolder(folder) => {
+                self.password_list.reveal_folder(&folder);
+                Action::None
+            }
             Message::PasswordDetails(message) => {
-                let action = self.password_details.update(message);
-                match action {
-                    password_details::Action::Run(task) => {
-                        Action::Run(task.map(Message::PasswordDetails))
-                    }
-                    _ => Action::None,
+                let action = self.password_details.update(message, service);
+                if let password_details::Action::BreachChecked(entry, breached) = &action {
+                    self.password_list.set_breached(entry, *breached);
                 }
+                details_action(action)
             }
         }
     }
 
+    /// Replaces the warnings about recipient keys of the shown stores
+    pub fn set_key_warnings(&mut self, warnings: Vec<(Arc<StoreInfo>, KeyWarning)>) {
+        self.password_details.set_key_warnings(warnings.clone());
+        self.key_warnings = warnings;
+    }
+
+    /// Sets the breach list that the passwords of shown entries are looked up in
+    pub fn set_breach_list(&mut self, breach_list: Option<PathBuf>) {
+        self.password_details.set_breach_list(breach_list);
+    }
+
+    /// Replaces the entries that are known to have breached passwords,
+    /// given by store index and pass ID
+    pub fn set_breached(&mut self, breached: BTreeSet<(usize, String)>) {
+        self.password_list.set_all_breached(breached);
+    }
+
+    /// Returns the state that is remembered for the store
+    pub fn state(&self) -> StoreState {
+        self.password_list.state()
+    }
+
+    #[cfg(test)]
+    pub fn details(&self) -> &PasswordDetails {
+        &self.password_details
+    }
+
+    /// Updates the entries after the password store has changed on disk
+    pub fn store_changed(
+        &mut self,
+        passwords: Vec<Entry>,
+        changed: &[PathBuf],
+        service: &mut StoreService,
+    ) -> Action {
+        let selected = self
+            .password_details
+            .selected()
+            .map(Entry::file_path)
+            .filter(|file_path| changed.contains(file_path));
+        let entry = selected.as_ref().map(|file_path| {
+            passwords
+                .iter()
+                .find(|entry| &entry.file_path() == file_path)
+                .cloned()
+        });
+        self.password_list.set_passwords(passwords);
+
+        match entry {
+            Some(entry) => details_action(self.password_details.refresh(entry, service)),
+            None => Action::None,
+        }
+    }
+
     pub fn view(&self) -> Element<Message> {
         let password_list = container(
             self.password_list
@@ -92,10 +169,68 @@ impl Dashboard {
         .align_x(Left)
         .align_y(Top);
 
-        row![password_list, password_details].spacing(5).into()
+        let content = row![password_list, password_details].spacing(5);
+        match self.key_warnings_view() {
+            Some(warnings) => column![warnings, content].spacing(5).into(),
+            None => content.into(),
+        }
+    }
+
+    /// Lists the recipient keys with problems and links to the folders that use them
+    fn key_warnings_view(&self) -> Option<Element<Message>> {
+        if self.key_warnings.is_empty() {
+            return None;
+        }
+        let show_store = self
+            .key_warnings
+            .iter()
+            .any(|(store, _)| store.index != self.key_warnings[0].0.index);
+        let warnings = Column::with_children(self.key_warnings.iter().map(|(store, warning)| {
+            let message = if show_store {
+                format!("{}: {}", store.name, warning.text())
+            } else {
+                warning.text()
+            };
+            let folders = warning.folders.iter().map(|folder| {
+                button(text!("{folder}/").font(Font::MONOSPACE))
+                    .style(button::text)
+                    .padding([0, 5])
+                    .on_press(Message::RevealFolder(folder.clone()))
+                    .into()
+            });
+            row![icon::alert(), text(message).style(text::danger)]
+                .extend(folders)
+                .spacing(5)
+                .align_y(Center)
+                .into()
+        }))
+        .spacing(5);
+        Some(
+            container(warnings)
+                .width(Fill)
+                .padding(10)
+                .style(container::rounded_box)
+                .into(),
+        )
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::none()
+        self.password_details
+            .subscription()
+            .map(Message::PasswordDetails)
+    }
+}
+
+fn details_action(action: password_details::Action) -> Action {
+    match action {
+        password_details::Action::Run(task) => Action::Run(task.map(Message::PasswordDetails)),
+        password_details::Action::Notify(notification) => Action::Notify(notification),
+        password_details::Action::ShowRecipients(store, folder) => {
+            Action::ShowRecipients(store, folder)
+        }
+        password_details::Action::BreachChecked(entry, breached) => {
+            Action::BreachChecked(entry, breached)
+        }
+        password_details::Action::None => Action::None,
     }
 }
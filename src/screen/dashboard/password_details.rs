@@ -1,47 +1,143 @@
 use iced::alignment::Vertical::Center;
-use iced::widget::{button, column, container, horizontal_space, row, text, Button, Column};
-use iced::{color, Element, Fill, Left, Right, Task, Top};
-use std::path::PathBuf;
+use iced::time::{self, Duration, Instant};
+use iced::widget::{
+    button, column, container, horizontal_space, mouse_area, progress_bar, row, scrollable, text,
+    Button, Column, Row,
+};
+use iced::{color, mouse, Element, Fill, Font, Left, Right, Subscription, Task, Theme, Top};
 
-use passepartout::{PasswordInfo, PasswordStore};
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
 
-use crate::icon;
+use crate::{
+    breach,
+    file_info::FileInfo,
+    git::{self, Commit},
+    history::{self, DiffLine},
+    icon,
+    key_check::{self, KeyWarning},
+    keyring,
+    notification::Notification,
+    otp,
+    service::{self, Channel, Outcome, Request, RequestId, Response, StoreService},
+    store::{Entry, PasswordFile, StoreInfo},
+    strength::{self, CharClass, Strength},
+};
+
+/// Revealed secrets are hidden again after this time
+pub const REVEAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Entropy in bits at which the strength meter is full
+const FULL_METER_BITS: f32 = 128.0;
+
+/// Number of characters that are shown together in the character breakdown
+const GROUP_SIZE: usize = 4;
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    SelectEntry(PasswordInfo),
-    ShowSecrets,
-    HideSecrets,
-    CopyId(PasswordInfo),
-    ShowFile,
-    CopyPassword(PasswordInfo),
-    CopyLogin(PasswordInfo),
-    FetchOtp(PasswordInfo),
-    CopyOtp(PasswordInfo),
-    EntryFetched(String, String),
-    OtpFetched(String, String),
-    // TODO: Use proper results
-    IdCopied(bool),
-    PasswordCopied(bool),
-    LoginCopied(bool),
-    OtpCopied(bool),
+    SelectEntry(Entry),
+    Reveal(Secret),
+    Hide(Secret),
+    RevealAll,
+    HideAll,
+    /// Shows a secret while the mouse button is held down on it
+    Peek(Secret),
+    EndPeek,
+    Tick(Instant),
+    CopyId(Entry),
+    CopyPassword(Entry),
+    CopyLogin(Entry),
+    FetchOtp(Entry),
+    CopyOtp(Entry),
+    ManageRecipients(Entry),
+    EntryFetched(Outcome),
+    OtpFetched(Outcome),
+    IdCopied(Outcome),
+    PasswordCopied(Outcome),
+    LoginCopied(Outcome),
+    OtpCopied(Outcome),
+    BreachChecked(Outcome),
+    SelectTab(Tab),
+    HistoryLoaded(Outcome),
+    ViewVersion(Commit),
+    VersionDecrypted(Outcome),
+    CloseVersion,
+    /// Switches between the contents of the old version and its changes
+    ToggleDiff,
+    RestoreVersion(Commit),
+    VersionRestored(Outcome),
+    FileInfoRead(Outcome),
 }
 
 pub enum Action {
     None,
     Run(Task<Message>),
+    Notify(Notification),
+    /// Opens the recipients of a folder of the store with the given index
+    ShowRecipients(usize, String),
+    /// Whether the password of the entry was found in the breach list
+    BreachChecked(Entry, bool),
+}
+
+/// Secret of an entry that is revealed on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Secret {
+    Password,
+    Login,
+    Otp,
+    File,
+}
+
+/// Part of the details that is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tab {
+    Details,
+    /// Commits that changed the password file
+    History,
+}
+
+/// Old version of the password file from the history
+struct Version {
+    commit: Commit,
+    /// Decrypted contents, `None` while the version is decrypted
+    contents: Option<String>,
+    show_diff: bool,
 }
 
 pub struct PasswordDetails {
-    entry: Option<PasswordInfo>,
-    show_secrets: bool,
+    entry: Option<Entry>,
+    revealed: BTreeSet<Secret>,
+    peeking: Option<Secret>,
+    /// When the revealed secrets are hidden again
+    hide_deadline: Option<Instant>,
     file_contents: Option<String>,
     line_count: Option<String>,
     password: Option<String>,
+    /// Estimated entropy of the password in bits
+    entropy: Option<f64>,
     login: Option<String>,
     otp: Option<String>,
-    show_file: bool,
-    status: Option<String>,
+    entry_request: Option<RequestId>,
+    otp_request: Option<RequestId>,
+    key_warnings: Vec<(Arc<StoreInfo>, KeyWarning)>,
+    /// Folder whose `.gpg-id` file governs the selected entry
+    governing_folder: Option<String>,
+    entry_warnings: Vec<KeyWarning>,
+    /// Downloaded list of breached password hashes
+    breach_list: Option<PathBuf>,
+    /// How often the password appeared in breaches
+    breaches: Option<u64>,
+    breach_request: Option<RequestId>,
+    tab: Tab,
+    /// Whether the store of the entry is a git repository
+    has_history: bool,
+    /// Commits that changed the selected entry, `None` until they are loaded
+    history: Option<Result<Vec<Commit>, String>>,
+    version: Option<Version>,
+    history_request: Option<RequestId>,
+    restoring: bool,
+    /// Metadata of the password file, `None` while it is read
+    file_info: Option<Result<FileInfo, String>>,
+    file_info_request: Option<RequestId>,
 }
 
 impl PasswordDetails {
@@ -49,14 +145,31 @@ impl PasswordDetails {
         (
             Self {
                 entry: None,
-                show_secrets: false,
+                revealed: BTreeSet::new(),
+                peeking: None,
+                hide_deadline: None,
                 file_contents: None,
                 line_count: None,
                 password: None,
+                entropy: None,
                 login: None,
                 otp: None,
-                show_file: false,
-                status: None,
+                entry_request: None,
+                otp_request: None,
+                key_warnings: Vec::new(),
+                governing_folder: None,
+                entry_warnings: Vec::new(),
+                breach_list: None,
+                breaches: None,
+                breach_request: None,
+                tab: Tab::Details,
+                has_history: false,
+                history: None,
+                version: None,
+                history_request: None,
+                restoring: false,
+                file_info: None,
+                file_info_request: None,
             },
             Task::none(),
         )
@@ -67,124 +180,147 @@ impl PasswordDetails {
         let mut content = Column::new().width(Fill);
 
         if let Some(ref entry) = self.entry {
-            header = if self.show_secrets {
+            if self.has_history {
+                header = header.push(
+                    row![
+                        tab_button("Details", Tab::Details, self.tab),
+                        tab_button("History", Tab::History, self.tab),
+                    ]
+                    .spacing(5),
+                );
+            }
+            if self.tab == Tab::History {
+                return column![header.spacing(10).padding([10, 10]), self.history_view()]
+                    .spacing(10)
+                    .into();
+            }
+
+            header = if self.revealed.is_empty() {
                 header.push(
                     button(
-                        row!["Hide secrets", horizontal_space(), icon::visible()]
+                        row!["Reveal all", horizontal_space(), icon::hidden()]
                             .width(125)
                             .align_y(Center),
                     )
-                    .on_press(Message::HideSecrets),
+                    .on_press(Message::RevealAll),
                 )
             } else {
                 header.push(
                     button(
-                        row!["Show secrets", horizontal_space(), icon::hidden()]
+                        row!["Hide all", horizontal_space(), icon::visible()]
                             .width(125)
                             .align_y(Center),
                     )
-                    .on_press(Message::ShowSecrets),
+                    .on_press(Message::HideAll),
                 )
             };
 
+            content = content.push(password_field("Store", entry.store.name.as_str(), vec![]));
+            if !self.entry_warnings.is_empty() {
+                let warnings = self.entry_warnings.iter().map(|warning| {
+                    row![icon::alert(), text(warning.text()).style(text::danger)]
+                        .spacing(5)
+                        .align_y(Center)
+                        .into()
+                });
+                content = content.push(
+                    row![
+                        Column::with_children(warnings).spacing(5).width(Fill),
+                        button("Manage recipients")
+                            .on_press(Message::ManageRecipients(entry.clone())),
+                    ]
+                    .spacing(10)
+                    .align_y(Center),
+                );
+            }
             content = content.push(password_field(
                 "Password File",
-                &entry.pass_id,
+                entry.pass_id.as_str(),
                 vec![
                     button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
                         .on_press(Message::CopyId(entry.clone())),
                 ],
             ));
             if let Some(ref line_count) = self.line_count {
-                let file_button = if self.show_file {
+                let file_button = if self.is_visible(Secret::File) {
                     button(
                         row!["Hide file", icon::document()]
                             .spacing(8)
                             .align_y(Center),
                     )
-                    .on_press(Message::ShowFile)
+                    .on_press(Message::Hide(Secret::File))
                 } else {
                     button(
                         row!["Show file", icon::document()]
                             .spacing(8)
                             .align_y(Center),
                     )
-                    .on_press(Message::ShowFile)
+                    .on_press(Message::Reveal(Secret::File))
                 };
                 content = content.push(password_field(
                     "Number of lines",
-                    line_count,
+                    line_count.as_str(),
                     vec![file_button],
                 ));
             }
-            let password = if self.show_secrets {
-                self.password.as_ref().map_or("********", |p| p.as_str())
-            } else {
-                "********"
-            };
+            let password = self.password.as_deref().unwrap_or("********");
             content = content.push(password_field(
                 "Password",
-                password,
+                self.secret_value(Secret::Password, password, "********"),
                 vec![
+                    self.reveal_button(Secret::Password),
                     button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
                         .on_press(Message::CopyPassword(entry.clone())),
                 ],
             ));
-            if let Some(ref login) = self.login {
-                content = if self.show_secrets {
-                    content.push(password_field(
-                        "Login",
-                        login,
-                        vec![
-                            button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
-                                .on_press(Message::CopyLogin(entry.clone())),
-                        ],
-                    ))
-                } else {
-                    content.push(password_field(
-                        "Login",
-                        "####",
-                        vec![
-                            button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
-                                .on_press(Message::CopyLogin(entry.clone())),
-                        ],
-                    ))
+            if let (Some(password), Some(bits)) = (&self.password, self.entropy) {
+                content = content.push(strength_meter(password, bits));
+                if self.is_visible(Secret::Password) {
+                    content = content.push(character_breakdown(password));
                 }
             }
-            if let Some(ref otp) = self.otp {
-                content = if self.show_secrets {
-                    content.push(password_field(
-                        "One-time password (OTP)",
-                        otp,
-                        vec![
-                            button(row!["Refresh", icon::refresh()].spacing(8).align_y(Center))
-                                .on_press(Message::FetchOtp(entry.clone())),
-                            button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
-                                .on_press(Message::CopyOtp(entry.clone())),
-                        ],
-                    ))
-                } else {
-                    content.push(password_field(
-                        "One-time password (OTP)",
-                        "******",
-                        vec![
-                            button(row!["Refresh", icon::refresh()].spacing(8).align_y(Center))
-                                .on_press(Message::FetchOtp(entry.clone())),
-                            button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
-                                .on_press(Message::CopyOtp(entry.clone())),
-                        ],
-                    ))
-                }
+            if let Some(breaches) = self.breaches {
+                content = content.push(
+                    row![
+                        icon::alert(),
+                        text(format!(
+                            "This password appeared {breaches} times in data breaches, \
+                             change it everywhere it is used"
+                        ))
+                        .style(text::danger),
+                    ]
+                    .spacing(5)
+                    .align_y(Center),
+                );
             }
-
-            // Status
-            if let Some(ref status) = self.status {
-                content = content.push(text(status).color(color!(0x0000ff)));
+            if let Some(ref login) = self.login {
+                content = content.push(password_field(
+                    "Login",
+                    self.secret_value(Secret::Login, login, "####"),
+                    vec![
+                        self.reveal_button(Secret::Login),
+                        button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
+                            .on_press(Message::CopyLogin(entry.clone())),
+                    ],
+                ));
+            }
+            if let Some(ref otp) = self.otp {
+                content = content.push(password_field(
+                    "One-time password (OTP)",
+                    self.secret_value(Secret::Otp, otp, "******"),
+                    vec![
+                        self.reveal_button(Secret::Otp),
+                        button(row!["Refresh", icon::refresh()].spacing(8).align_y(Center))
+                            .on_press(Message::FetchOtp(entry.clone())),
+                        button(row!["Copy", icon::clipboard()].spacing(8).align_y(Center))
+                            .on_press(Message::CopyOtp(entry.clone())),
+                    ],
+                ));
             }
 
             // File
             let mut file_area = None;
-            if self.show_secrets && self.show_file {
+            if self.is_visible(Secret::File) {
                 if let Some(file_contents) = &self.file_contents {
                     file_area = Some(container(
                         container(text(file_contents))
@@ -200,6 +336,12 @@ impl PasswordDetails {
                     .padding(10)
                     .style(container::rounded_box),
             ]
+            .push_maybe(self.file_info_view().map(|info| {
+                container(info)
+                    .width(Fill)
+                    .padding(10)
+                    .style(container::rounded_box)
+            }))
             .push_maybe(file_area)
             .spacing(10)
             .into()
@@ -208,232 +350,684 @@ impl PasswordDetails {
         }
     }
 
-    pub fn update(&mut self, message: Message) -> Action {
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
         match message {
             Message::SelectEntry(entry) => {
-                self.show_secrets = false;
+                self.hide_secrets();
                 self.select(entry.clone());
-                self.status = Some("Fetching password entry...".to_string());
-
-                let pass_id = entry.pass_id.clone();
-                // TODO:The password store directory should not be read each time
-                let store_dir = PasswordStore::get_store_dir();
-                let file_path = PathBuf::from(store_dir).join(format!("{}.gpg", pass_id));
-                async fn helper_function(file_path: PathBuf, pass_id: String) -> (String, String) {
-                    match passepartout::decrypt_password_file(&file_path) {
-                        Ok(file_contents) => (pass_id, file_contents),
-                        Err(_) => (String::new(), String::new()),
+
+                let file = entry.file();
+                let (id, task) = service.submit(Channel::Entry, Request::Decrypt(file));
+                self.entry_request = Some(id);
+                Action::Run(Task::batch([
+                    task.map(Message::EntryFetched),
+                    self.load_history(service),
+                    self.load_file_info(service),
+                ]))
+            }
+            Message::EntryFetched((id, result)) => {
+                if self.entry_request != Some(id) {
+                    // Response for an entry that is no longer selected
+                    return Action::None;
+                }
+                self.entry_request = None;
+                match result {
+                    Ok(Response::Decrypted(file_contents)) => {
+                        self.update_fields(file_contents, service)
                     }
+                    Ok(_) | Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => Action::Notify(Notification::error(format!(
+                        "Failed to decrypt password file: {e}"
+                    ))),
                 }
-                Action::Run(Task::perform(
-                    helper_function(file_path, entry.pass_id),
-                    |(id, result)| Message::EntryFetched(id, result),
-                ))
             }
-            Message::EntryFetched(id, result) => self.update_fields(id, result),
-            Message::ShowSecrets => {
-                self.show_secrets();
-                // Refresh OTP
-                if let Some(entry) = &self.entry {
-                    let pass_id = entry.pass_id.clone();
-                    run_generate_otp(pass_id)
-                } else {
-                    Action::None
-                }
+            Message::Reveal(secret) => {
+                self.revealed.insert(secret);
+                self.hide_deadline = Some(Instant::now() + REVEAL_TIMEOUT);
+                self.refresh_otp(secret, service)
+            }
+            Message::Hide(secret) => {
+                self.revealed.remove(&secret);
+                Action::None
             }
-            Message::HideSecrets => {
+            Message::RevealAll => {
+                self.revealed
+                    .extend([Secret::Password, Secret::Login, Secret::Otp]);
+                self.hide_deadline = Some(Instant::now() + REVEAL_TIMEOUT);
+                self.refresh_otp(Secret::Otp, service)
+            }
+            Message::HideAll => {
                 self.hide_secrets();
                 Action::None
             }
-            Message::CopyId(entry) => {
-                self.status = Some("Copying password ID to clipboard...".to_string());
-                Action::Run(Task::perform(
-                    async move { passepartout::copy_id(entry.pass_id).is_ok() },
-                    Message::IdCopied,
-                ))
+            Message::Peek(secret) => {
+                self.peeking = Some(secret);
+                self.refresh_otp(secret, service)
             }
-            Message::IdCopied(success) => {
-                if success {
-                    self.status = Some("Password ID copied to clipboard".to_string());
-                } else {
-                    self.status = Some("Something went wrong".to_string());
-                }
+            Message::EndPeek => {
+                self.peeking = None;
                 Action::None
             }
-            Message::ShowFile => {
-                if self.show_file {
-                    self.show_file = false;
-                } else {
-                    self.show_file = true;
-                    self.show_secrets = true
+            Message::Tick(now) => {
+                if self.hide_deadline.is_some_and(|deadline| now >= deadline) {
+                    self.hide_secrets();
                 }
                 Action::None
             }
+            Message::CopyId(entry) => {
+                let (_, task) = service.submit(Channel::Clipboard, Request::CopyId(entry.pass_id));
+                Action::Run(task.map(Message::IdCopied))
+            }
+            Message::IdCopied((_, result)) => copied_notification("Password ID", result),
             Message::CopyPassword(entry) => {
-                self.status = Some("Copying password to clipboard...".to_string());
-
-                let pass_id = entry.pass_id.clone();
-                // TODO:The password store directory should not be read each time
-                let store_dir = PasswordStore::get_store_dir();
-                let file_path = PathBuf::from(store_dir).join(format!("{}.gpg", pass_id));
-                Action::Run(Task::perform(
-                    async move { passepartout::copy_password(&file_path).is_ok() },
-                    Message::PasswordCopied,
-                ))
+                let file = entry.file();
+                let (_, task) = service.submit(Channel::Clipboard, Request::CopyPassword(file));
+                Action::Run(task.map(Message::PasswordCopied))
             }
-            Message::PasswordCopied(success) => {
-                if success {
-                    self.status = Some("Password copied to clipboard".to_string());
-                } else {
-                    self.status = Some("Something went wrong".to_string());
+            Message::PasswordCopied((_, result)) => copied_notification("Password", result),
+            Message::CopyLogin(entry) => {
+                let file = entry.file();
+                let (_, task) = service.submit(Channel::Clipboard, Request::CopyLogin(file));
+                Action::Run(task.map(Message::LoginCopied))
+            }
+            Message::LoginCopied((_, result)) => copied_notification("Login", result),
+            Message::CopyOtp(entry) => {
+                let file = entry.file();
+                let (_, task) = service.submit(Channel::Clipboard, Request::CopyOtp(file));
+                Action::Run(task.map(Message::OtpCopied))
+            }
+            Message::OtpCopied((_, result)) => {
+                copied_notification("One-time password (OTP)", result)
+            }
+            Message::ManageRecipients(entry) => match &self.governing_folder {
+                Some(folder) => Action::ShowRecipients(entry.store.index, folder.clone()),
+                None => Action::None,
+            },
+            Message::BreachChecked((id, result)) => {
+                if self.breach_request != Some(id) {
+                    return Action::None;
+                }
+                self.breach_request = None;
+                match result {
+                    Ok(Response::Breached(breaches)) => {
+                        self.breaches = breaches;
+                        match &self.entry {
+                            Some(entry) => Action::BreachChecked(entry.clone(), breaches.is_some()),
+                            None => Action::None,
+                        }
+                    }
+                    Ok(_) | Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => Action::Notify(Notification::warning(format!(
+                        "Password could not be checked for breaches: {e}"
+                    ))),
                 }
-                Action::None
             }
-            Message::CopyLogin(entry) => {
-                self.status = Some("Copying login to clipboard...".to_string());
-
-                let pass_id = entry.pass_id.clone();
-                // TODO:The password store directory should not be read each time
-                let store_dir = PasswordStore::get_store_dir();
-                let file_path = PathBuf::from(store_dir).join(format!("{}.gpg", pass_id));
-                Action::Run(Task::perform(
-                    async move { passepartout::copy_login(&file_path).is_ok() },
-                    Message::LoginCopied,
-                ))
+            Message::SelectTab(tab) => {
+                self.tab = tab;
+                Action::Run(self.load_history(service))
             }
-            Message::LoginCopied(success) => {
-                if success {
-                    self.status = Some("Login copied to clipboard".to_string());
-                } else {
-                    self.status = Some("Something went wrong".to_string());
+            Message::HistoryLoaded((id, result)) => {
+                if self.history_request != Some(id) {
+                    return Action::None;
+                }
+                self.history_request = None;
+                match result {
+                    Ok(Response::History(commits)) => self.history = Some(Ok(commits)),
+                    Ok(_) | Err(service::Error::Cancelled) => (),
+                    Err(e) => self.history = Some(Err(e.to_string())),
                 }
                 Action::None
             }
-            Message::CopyOtp(entry) => {
-                self.status = Some("Copying one-time password to clipboard...".to_string());
-
-                // TODO:The password store directory should not be read each time
-                let store_dir = PasswordStore::get_store_dir();
-                let file_path = PathBuf::from(store_dir).join(format!("{}.gpg", entry.pass_id));
-                Action::Run(Task::perform(
-                    async move { passepartout::copy_otp(&file_path).is_ok() },
-                    Message::OtpCopied,
-                ))
+            Message::ViewVersion(commit) => {
+                let Some(entry) = &self.entry else {
+                    return Action::None;
+                };
+                let (id, task) = service.submit(
+                    Channel::History,
+                    Request::DecryptVersion(entry.clone(), commit.clone()),
+                );
+                self.history_request = Some(id);
+                self.version = Some(Version {
+                    commit,
+                    contents: None,
+                    show_diff: false,
+                });
+                Action::Run(task.map(Message::VersionDecrypted))
             }
-            Message::OtpCopied(success) => {
-                if success {
-                    self.status = Some("One-time password (OTP) copied to clipboard".to_string());
-                } else {
-                    self.status = Some("Something went wrong".to_string());
+            Message::VersionDecrypted((id, result)) => {
+                if self.history_request != Some(id) {
+                    return Action::None;
+                }
+                self.history_request = None;
+                match result {
+                    Ok(Response::Decrypted(contents)) => {
+                        if let Some(version) = &mut self.version {
+                            version.contents = Some(contents);
+                        }
+                        Action::None
+                    }
+                    Ok(_) | Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => {
+                        self.version = None;
+                        Action::Notify(Notification::error(format!(
+                            "Failed to decrypt the old version: {e}"
+                        )))
+                    }
                 }
+            }
+            Message::CloseVersion => {
+                self.version = None;
                 Action::None
             }
-            Message::FetchOtp(entry) => {
-                self.status = Some("Fetching one-time password...".to_string());
-
-                let pass_id = entry.pass_id.clone();
-                run_generate_otp(pass_id)
+            Message::ToggleDiff => {
+                if let Some(version) = &mut self.version {
+                    version.show_diff = !version.show_diff;
+                }
+                Action::None
             }
-            Message::OtpFetched(id, otp) => {
-                if let Some(ref entry) = self.entry {
-                    if entry.pass_id == id {
-                        self.otp = Some(otp);
+            Message::RestoreVersion(commit) => {
+                let Some(entry) = &self.entry else {
+                    return Action::None;
+                };
+                let (id, task) = service.submit(
+                    Channel::History,
+                    Request::RestoreVersion(entry.clone(), commit),
+                );
+                self.history_request = Some(id);
+                self.restoring = true;
+                Action::Run(task.map(Message::VersionRestored))
+            }
+            Message::VersionRestored((id, result)) => {
+                if self.history_request != Some(id) {
+                    return Action::None;
+                }
+                self.history_request = None;
+                self.restoring = false;
+                match result {
+                    // The watcher reloads the entry and its history
+                    Ok(_) => {
+                        self.version = None;
+                        Action::Notify(Notification::success(
+                            "Old version restored as a new commit",
+                        ))
                     }
-                    self.status = None;
-                } else {
-                    self.status = Some("Something went wrong".to_string());
+                    Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => Action::Notify(Notification::error(format!(
+                        "Failed to restore the old version: {e}"
+                    ))),
+                }
+            }
+            Message::FileInfoRead((id, result)) => {
+                if self.file_info_request != Some(id) {
+                    return Action::None;
+                }
+                self.file_info_request = None;
+                match result {
+                    Ok(Response::FileInfo(info)) => self.file_info = Some(Ok(info)),
+                    Ok(_) | Err(service::Error::Cancelled) => (),
+                    Err(e) => self.file_info = Some(Err(e.to_string())),
                 }
                 Action::None
             }
+            Message::FetchOtp(entry) => self.run_generate_otp(entry.file(), service),
+            Message::OtpFetched((id, result)) => {
+                if self.otp_request != Some(id) {
+                    return Action::None;
+                }
+                self.otp_request = None;
+                match result {
+                    Ok(Response::Otp(otp)) => {
+                        self.otp = Some(otp);
+                        Action::None
+                    }
+                    Ok(_) | Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => Action::Notify(Notification::error(format!(
+                        "Failed to generate one-time password: {e}"
+                    ))),
+                }
+            }
         }
     }
 
-    fn select(&mut self, entry: PasswordInfo) {
+    /// Reloads the selected entry after its file has changed on disk,
+    /// `None` means that the file was removed
+    pub fn refresh(&mut self, entry: Option<Entry>, service: &mut StoreService) -> Action {
+        let Some(ref selected) = self.entry else {
+            return Action::None;
+        };
+        match entry {
+            Some(entry) => {
+                self.select(entry.clone());
+
+                let file = entry.file();
+                let (id, task) = service.submit(Channel::Entry, Request::Decrypt(file));
+                self.entry_request = Some(id);
+                Action::Run(Task::batch([
+                    task.map(Message::EntryFetched),
+                    self.load_history(service),
+                    self.load_file_info(service),
+                ]))
+            }
+            None => {
+                let notification = Notification::warning(format!(
+                    "Password file {} was removed",
+                    selected.pass_id
+                ));
+                service.cancel(Channel::Entry);
+                service.cancel(Channel::Otp);
+                service.cancel(Channel::History);
+                service.cancel(Channel::FileInfo);
+                self.entry = None;
+                self.entry_request = None;
+                self.otp_request = None;
+                self.hide_secrets();
+                Action::Notify(notification)
+            }
+        }
+    }
+
+    /// Replaces the warnings about recipient keys of the shown stores
+    pub fn set_key_warnings(&mut self, warnings: Vec<(Arc<StoreInfo>, KeyWarning)>) {
+        self.key_warnings = warnings;
+        self.update_entry_warnings();
+    }
+
+    /// Collects the warnings for the keys the selected entry is encrypted for
+    fn update_entry_warnings(&mut self) {
+        self.governing_folder = self.entry.as_ref().and_then(|entry| {
+            key_check::governing_folder(
+                entry.store.backend.as_ref(),
+                &entry.store.dir,
+                &entry.pass_id,
+            )
+        });
+        self.entry_warnings = match (&self.entry, &self.governing_folder) {
+            (Some(entry), Some(folder)) => self
+                .key_warnings
+                .iter()
+                .filter(|(store, warning)| {
+                    store.index == entry.store.index && warning.folders.contains(folder)
+                })
+                .map(|(_, warning)| warning.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Sets the breach list that passwords are looked up in when an entry is shown
+    pub fn set_breach_list(&mut self, breach_list: Option<PathBuf>) {
+        self.breach_list = breach_list;
+    }
+
+    /// Returns the selected entry
+    pub fn selected(&self) -> Option<&Entry> {
+        self.entry.as_ref()
+    }
+
+    #[cfg(test)]
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+
+    #[cfg(test)]
+    pub fn login(&self) -> Option<&str> {
+        self.login.as_deref()
+    }
+
+    #[cfg(test)]
+    pub fn breaches(&self) -> Option<u64> {
+        self.breaches
+    }
+
+    #[cfg(test)]
+    pub fn is_revealed(&self, secret: Secret) -> bool {
+        self.is_visible(secret)
+    }
+
+    #[cfg(test)]
+    pub fn history(&self) -> Option<&[Commit]> {
+        self.history.as_ref()?.as_deref().ok()
+    }
+
+    #[cfg(test)]
+    pub fn version_contents(&self) -> Option<&str> {
+        self.version.as_ref()?.contents.as_deref()
+    }
+
+    #[cfg(test)]
+    pub fn file_info(&self) -> Option<&FileInfo> {
+        self.file_info.as_ref()?.as_ref().ok()
+    }
+
+    #[cfg(test)]
+    pub fn otp(&self) -> Option<&str> {
+        self.otp.as_deref()
+    }
+
+    fn select(&mut self, entry: Entry) {
         self.entry = Some(entry);
+        self.update_entry_warnings();
         self.file_contents = None;
         self.line_count = None;
         self.password = None;
+        self.entropy = None;
         self.login = None;
         self.otp = None;
-        self.status = None;
+        self.otp_request = None;
+        self.breaches = None;
+        self.breach_request = None;
+        self.has_history = self
+            .entry
+            .as_ref()
+            .is_some_and(|entry| git::is_repository(&entry.store.dir));
+        self.history = None;
+        self.version = None;
+        self.history_request = None;
+        self.restoring = false;
+        self.file_info = None;
+        self.file_info_request = None;
+        if !self.has_history {
+            self.tab = Tab::Details;
+        }
     }
 
-    fn show_secrets(&mut self) {
-        self.show_secrets = true;
+    /// Lists the commits of the selected entry when the history is shown
+    fn load_history(&mut self, service: &mut StoreService) -> Task<Message> {
+        match &self.entry {
+            Some(entry) if self.tab == Tab::History && self.history.is_none() => {
+                let (id, task) = service.submit(Channel::History, Request::History(entry.clone()));
+                self.history_request = Some(id);
+                task.map(Message::HistoryLoaded)
+            }
+            _ => Task::none(),
+        }
     }
 
-    fn hide_secrets(&mut self) {
-        self.show_secrets = false;
+    /// Reads the metadata of the selected password file
+    fn load_file_info(&mut self, service: &mut StoreService) -> Task<Message> {
+        let Some(entry) = &self.entry else {
+            return Task::none();
+        };
+        let (id, task) = service.submit(Channel::FileInfo, Request::FileInfo(entry.clone()));
+        self.file_info_request = Some(id);
+        task.map(Message::FileInfoRead)
     }
 
-    fn update_fields(&mut self, id: String, file_contents: String) -> Action {
-        if let Some(ref entry) = self.entry {
-            let pass_id = entry.pass_id.clone();
-            if id != pass_id {
-                self.status = Some("Something went wrong".to_string());
-                return Action::None;
-            }
-            self.status = None;
-            self.file_contents = Some(file_contents.clone());
-
-            let mut lines = file_contents.lines();
-            let mut count = 0;
-            if let Some(password) = lines.next() {
-                self.password = Some(password.to_string());
-                count += 1;
-            }
-            if let Some(login) = lines.next() {
-                self.login = Some(login.to_string());
-                count += 1;
-            }
-
-            let mut next_line = lines.next();
-            let mut has_otp = false;
-            while let Some(line) = next_line {
-                // One-time password (OTP)
-                if line.starts_with("otpauth://") {
-                    has_otp = true;
-                }
-                count += 1;
-                next_line = lines.next();
+    /// Size, recipients and last commit of the password file
+    fn file_info_view(&self) -> Option<Element<Message>> {
+        let info = match self.file_info.as_ref()? {
+            Ok(info) => info,
+            Err(e) => {
+                return Some(
+                    text(format!("File information could not be read: {e}"))
+                        .style(text::danger)
+                        .into(),
+                )
+            }
+        };
+        let entry = self.entry.as_ref()?;
+        let mut rows = column![
+            info_row("File size", text(format!("{} bytes", info.size))),
+            info_row(
+                "Modified",
+                text(
+                    info.modified
+                        .map_or("unknown".to_owned(), keyring::format_time)
+                ),
+            ),
+        ]
+        .spacing(5);
+        if let Some(key_ids) = &info.key_ids {
+            let value: Element<Message> = match key_ids {
+                Ok(key_ids) if key_ids.is_empty() => text("no public keys").into(),
+                Ok(key_ids) => Column::with_children(key_ids.iter().map(|key_id| {
+                    let label = if key_id.is_wildcard() {
+                        "hidden recipient".to_owned()
+                    } else {
+                        key_id.to_string()
+                    };
+                    text(label).font(Font::MONOSPACE).into()
+                }))
+                .into(),
+                Err(e) => text(e).style(text::danger).into(),
+            };
+            rows = rows.push(info_row("Encrypted for", value));
+        }
+        let recipients_file = info.recipients_file.as_ref().map_or_else(
+            || format!("no {} file", entry.store.backend.recipients_file()),
+            |path| {
+                path.strip_prefix(&entry.store.dir)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            },
+        );
+        rows = rows.push(info_row(
+            "Recipients file",
+            text(recipients_file).font(Font::MONOSPACE),
+        ));
+        if let Some(last_commit) = &info.last_commit {
+            let value = match last_commit {
+                Ok(Some(commit)) => text(format!(
+                    "{} {} by {} on {}",
+                    commit.short_hash(),
+                    commit.subject,
+                    commit.author,
+                    keyring::format_time(commit.time)
+                )),
+                Ok(None) => text("not committed yet"),
+                Err(e) => text(e).style(text::danger),
+            };
+            rows = rows.push(info_row("Last commit", value));
+        }
+        Some(rows.into())
+    }
+
+    fn history_view(&self) -> Element<Message> {
+        let commits: Element<Message> = match &self.history {
+            None => text("Loading history...").into(),
+            Some(Err(e)) => text(format!("History could not be loaded: {e}"))
+                .style(text::danger)
+                .into(),
+            Some(Ok(commits)) if commits.is_empty() => {
+                text("The password file was not committed yet").into()
+            }
+            Some(Ok(commits)) => {
+                let selected = self.version.as_ref().map(|version| &version.commit);
+                container(scrollable(
+                    Column::with_children(
+                        commits
+                            .iter()
+                            .map(|commit| commit_row(commit, selected == Some(commit))),
+                    )
+                    .spacing(10)
+                    .padding([0, 12]),
+                ))
+                .max_height(300)
+                .into()
+            }
+        };
+        column![container(commits)
+            .width(Fill)
+            .padding(10)
+            .style(container::rounded_box)]
+        .push_maybe(
+            self.version
+                .as_ref()
+                .map(|version| self.version_view(version)),
+        )
+        .spacing(10)
+        .into()
+    }
+
+    /// Contents of an old version or its changes up to the current version
+    fn version_view<'a>(&'a self, version: &'a Version) -> Element<'a, Message> {
+        let toggle = if version.show_diff {
+            "Show contents"
+        } else {
+            "Compare with current"
+        };
+        let header = row![
+            text(format!("Version of {}", version.commit.short_hash())).width(Fill),
+            button(toggle).on_press_maybe(version.contents.as_ref().map(|_| Message::ToggleDiff)),
+            button("Restore this version").on_press_maybe(
+                (version.contents.is_some() && !self.restoring)
+                    .then(|| Message::RestoreVersion(version.commit.clone()))
+            ),
+            button("Close")
+                .style(button::secondary)
+                .on_press(Message::CloseVersion),
+        ]
+        .spacing(10)
+        .align_y(Center);
+        let body: Element<Message> = match (&version.contents, &self.file_contents) {
+            (None, _) => text("Decrypting...").into(),
+            (Some(old), Some(current)) if version.show_diff => {
+                Column::with_children(history::diff(old, current).into_iter().map(diff_line)).into()
             }
-            self.line_count = Some(count.to_string());
+            (Some(old), _) => text(old).font(Font::MONOSPACE).into(),
+        };
+        container(column![header, body].spacing(10))
+            .width(Fill)
+            .padding(10)
+            .style(container::rounded_box)
+            .into()
+    }
 
-            if has_otp {
-                self.otp = Some("*".repeat(6));
+    /// Whether the secret is revealed or peeked at
+    fn is_visible(&self, secret: Secret) -> bool {
+        self.revealed.contains(&secret) || self.peeking == Some(secret)
+    }
+
+    fn hide_secrets(&mut self) {
+        self.revealed.clear();
+        self.peeking = None;
+        self.hide_deadline = None;
+    }
 
-                let pass_id = entry.pass_id.clone();
-                return run_generate_otp(pass_id);
+    /// Generates a new one-time password when it is about to be shown
+    fn refresh_otp(&mut self, secret: Secret, service: &mut StoreService) -> Action {
+        match &self.entry {
+            Some(entry) if secret == Secret::Otp && self.otp.is_some() => {
+                let file = entry.file();
+                self.run_generate_otp(file, service)
             }
+            _ => Action::None,
         }
-        Action::None
     }
-}
 
-fn run_generate_otp(pass_id: String) -> Action {
-    // TODO:The password store directory should not be read each time
-    let store_dir = PasswordStore::get_store_dir();
-    let file_path = PathBuf::from(store_dir).join(format!("{}.gpg", pass_id));
+    /// Value of a secret field, masked unless it is visible,
+    /// holding the mouse button down on it shows it until the button is released
+    fn secret_value<'a>(
+        &self,
+        secret: Secret,
+        value: &'a str,
+        mask: &'a str,
+    ) -> Element<'a, Message> {
+        let shown = if self.is_visible(secret) { value } else { mask };
+        mouse_area(text(shown))
+            .on_press(Message::Peek(secret))
+            .on_release(Message::EndPeek)
+            .on_exit(Message::EndPeek)
+            .interaction(mouse::Interaction::Pointer)
+            .into()
+    }
 
-    async fn helper_function(file_path: PathBuf, pass_id: String) -> (String, String) {
-        match passepartout::generate_otp(&file_path) {
-            Ok(otp) => (pass_id, otp),
-            Err(_) => (String::new(), String::new()),
+    fn reveal_button<'a>(&self, secret: Secret) -> Button<'a, Message> {
+        if self.revealed.contains(&secret) {
+            button(row!["Hide", icon::visible()].spacing(8).align_y(Center))
+                .on_press(Message::Hide(secret))
+        } else {
+            button(row!["Show", icon::hidden()].spacing(8).align_y(Center))
+                .on_press(Message::Reveal(secret))
         }
     }
-    Action::Run(Task::perform(
-        helper_function(file_path, pass_id),
-        |(id, result)| Message::OtpFetched(id, result),
-    ))
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.hide_deadline.is_some() {
+            time::every(Duration::from_secs(1)).map(Message::Tick)
+        } else {
+            Subscription::none()
+        }
+    }
+
+    fn update_fields(&mut self, file_contents: String, service: &mut StoreService) -> Action {
+        let Some(ref entry) = self.entry else {
+            return Action::None;
+        };
+        let file = entry.file();
+        self.file_contents = Some(file_contents.clone());
+
+        let mut lines = file_contents.lines();
+        let mut count = 0;
+        let mut tasks = Vec::new();
+        if let Some(password) = lines.next() {
+            self.password = Some(password.to_string());
+            self.entropy = (!password.is_empty()).then(|| strength::entropy(password));
+            count += 1;
+            if let Some(breach_list) = self.breach_list.clone().filter(|_| !password.is_empty()) {
+                let (id, task) = service.submit(
+                    Channel::Breach,
+                    Request::CheckBreach(breach::hash(password), breach_list),
+                );
+                self.breach_request = Some(id);
+                tasks.push(task.map(Message::BreachChecked));
+            }
+        }
+        if let Some(login) = lines.next() {
+            self.login = Some(login.to_string());
+            count += 1;
+        }
+
+        let mut next_line = lines.next();
+        let mut has_otp = false;
+        while let Some(line) = next_line {
+            // One-time password (OTP)
+            if otp::is_url(line) {
+                has_otp = true;
+            }
+            count += 1;
+            next_line = lines.next();
+        }
+        self.line_count = Some(count.to_string());
+
+        if has_otp {
+            self.otp = Some("*".repeat(6));
+            if let Action::Run(task) = self.run_generate_otp(file, service) {
+                tasks.push(task);
+            }
+        }
+        if tasks.is_empty() {
+            Action::None
+        } else {
+            Action::Run(Task::batch(tasks))
+        }
+    }
+
+    fn run_generate_otp(&mut self, file: PasswordFile, service: &mut StoreService) -> Action {
+        let (id, task) = service.submit(Channel::Otp, Request::GenerateOtp(file));
+        self.otp_request = Some(id);
+        Action::Run(task.map(Message::OtpFetched))
+    }
+}
+
+fn copied_notification(what: &str, result: Result<Response, service::Error>) -> Action {
+    match result {
+        Ok(_) => Action::Notify(Notification::success(format!("{what} copied to clipboard"))),
+        Err(service::Error::Cancelled) => Action::None,
+        Err(e) => Action::Notify(Notification::error(format!(
+            "{what} could not be copied: {e}"
+        ))),
+    }
 }
 
 fn password_field<'a>(
     label: &'a str,
-    value: &'a str,
+    value: impl Into<Element<'a, Message>>,
     buttons: Vec<Button<'a, Message>>,
 ) -> Element<'a, Message> {
     row![
-        column![text(label).color(color!(0x0055ff)), text(value)].align_x(Left),
+        column![text(label).color(color!(0x0055ff)), value.into()].align_x(Left),
         horizontal_space(),
         Column::with_children(buttons.into_iter().map(|button| { button.into() }))
             .spacing(10)
@@ -442,3 +1036,131 @@ fn password_field<'a>(
     .align_y(Top)
     .into()
 }
+
+fn info_row<'a>(label: &'a str, value: impl Into<Element<'a, Message>>) -> Element<'a, Message> {
+    row![
+        text(label).width(130).style(text::secondary),
+        container(value).width(Fill)
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn tab_button(label: &str, tab: Tab, selected: Tab) -> Button<Message> {
+    button(label)
+        .style(if tab == selected {
+            button::primary
+        } else {
+            button::secondary
+        })
+        .on_press(Message::SelectTab(tab))
+}
+
+fn commit_row(commit: &Commit, selected: bool) -> Element<Message> {
+    row![
+        column![
+            text(&commit.subject),
+            text(format!(
+                "{} by {} on {}",
+                commit.short_hash(),
+                commit.author,
+                keyring::format_date(commit.time)
+            ))
+            .size(12)
+            .style(text::secondary),
+        ]
+        .spacing(2)
+        .width(Fill),
+        button("View")
+            .style(if selected {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::ViewVersion(commit.clone())),
+    ]
+    .spacing(10)
+    .align_y(Center)
+    .into()
+}
+
+fn diff_line(line: DiffLine) -> Element<'static, Message> {
+    let (text, style): (_, fn(&Theme) -> text::Style) = match line {
+        DiffLine::Same(line) => (format!("  {line}"), text::default),
+        DiffLine::Added(line) => (format!("+ {line}"), text::success),
+        DiffLine::Removed(line) => (format!("- {line}"), text::danger),
+    };
+    iced::widget::text(text)
+        .font(Font::MONOSPACE)
+        .style(style)
+        .into()
+}
+
+/// Length and estimated strength of the password
+fn strength_meter<'a>(password: &str, bits: f64) -> Element<'a, Message> {
+    let strength = Strength::of(bits);
+    let style = match strength {
+        Strength::VeryWeak | Strength::Weak => progress_bar::danger,
+        Strength::Fair => progress_bar::primary,
+        Strength::Strong | Strength::VeryStrong => progress_bar::success,
+    };
+    column![
+        progress_bar(0.0..=FULL_METER_BITS, bits as f32)
+            .height(6)
+            .style(style),
+        text(format!(
+            "{} characters, about {bits:.0} bits: {strength}, cracking takes {}",
+            password.chars().count(),
+            strength.crack_time()
+        ))
+        .size(12)
+        .style(text::secondary),
+    ]
+    .spacing(5)
+    .into()
+}
+
+/// Password in groups of characters with their positions,
+/// digits and symbols are coloured to tell them apart from similar letters
+fn character_breakdown<'a>(password: &str) -> Element<'a, Message> {
+    let characters: Vec<(usize, char)> = password.chars().enumerate().collect();
+    let groups = characters.chunks(GROUP_SIZE).map(|group| {
+        Row::with_children(group.iter().map(|&(index, c)| {
+            let class = CharClass::of(c);
+            // Spaces would be invisible
+            let shown = if class == CharClass::Whitespace {
+                '\u{2423}'
+            } else {
+                c
+            };
+            column![
+                text(shown.to_string())
+                    .font(Font::MONOSPACE)
+                    .size(18)
+                    .style(move |theme: &Theme| character_style(theme, class)),
+                text((index + 1).to_string()).size(9).style(text::secondary),
+            ]
+            .width(16)
+            .align_x(iced::Center)
+            .into()
+        }))
+        .into()
+    });
+    container(Row::with_children(groups).spacing(12).wrap())
+        .width(Fill)
+        .padding(10)
+        .style(container::bordered_box)
+        .into()
+}
+
+fn character_style(theme: &Theme, class: CharClass) -> text::Style {
+    let palette = theme.extended_palette();
+    text::Style {
+        color: match class {
+            CharClass::Letter => None,
+            CharClass::Digit => Some(palette.primary.strong.color),
+            CharClass::Symbol => Some(palette.danger.base.color),
+            CharClass::Whitespace => Some(palette.secondary.strong.color),
+        },
+    }
+}
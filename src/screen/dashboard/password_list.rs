@@ -2,23 +2,30 @@ use iced::alignment::Vertical::Center;
 use iced::widget::{
     button, column, container, horizontal_space, hover, row, scrollable, text, text_input, Column,
 };
-use iced::{Element, Fill, Font, Left, Right, Task};
+use iced::{Color, Element, Fill, Font, Left, Right, Task, Theme};
+use std::collections::BTreeSet;
 
-use crate::icon;
-use passepartout::PasswordInfo;
+use crate::{config::StoreState, icon, store::Entry, theme};
+
+/// Indentation of entries per folder level
+const INDENT: u16 = 20;
 
 #[derive(Debug, Clone)]
 pub enum Message {
     SearchChanged(String),
-    SelectEntry(PasswordInfo),
+    SelectEntry(Entry),
+    ToggleFolder(String),
+    ExportEntry(Entry),
+    ExportFolder(String),
 }
 
 pub enum Action {
     None,
     Run(Task<Message>),
-    Back,
     UpdateSearch,
-    SelectEntry(PasswordInfo),
+    SelectEntry(Entry),
+    /// Shares the entries of the selected entry or folder with someone else
+    ExportFor(Vec<Entry>, String),
 }
 
 pub struct PasswordList {
@@ -27,43 +34,102 @@ pub struct PasswordList {
     scroller_width: u16,
     anchor: scrollable::Anchor,
     search: String,
-    passwords: Vec<PasswordInfo>,
+    expanded_folders: BTreeSet<String>,
+    passwords: Vec<Entry>,
+    show_badges: bool,
+    /// Store indices and pass IDs of entries whose password was found in the breach list
+    breached: BTreeSet<(usize, String)>,
 }
 
 impl PasswordList {
-    pub fn new(passwords: Vec<PasswordInfo>) -> (Self, Task<Message>) {
+    pub fn new(
+        passwords: Vec<Entry>,
+        state: StoreState,
+        show_badges: bool,
+    ) -> (Self, Task<Message>) {
         (
             Self {
                 scrollbar_width: 10,
                 scrollbar_margin: 0,
                 scroller_width: 10,
                 anchor: scrollable::Anchor::Start,
-                search: String::new(),
+                search: state.last_search,
+                expanded_folders: state.expanded_folders,
                 passwords,
+                show_badges,
+                breached: BTreeSet::new(),
             },
             Task::none(),
         )
     }
 
+    /// Replaces the entries while keeping the search
+    pub fn set_passwords(&mut self, passwords: Vec<Entry>) {
+        self.passwords = passwords;
+    }
+
+    /// Replaces the entries that are known to have breached passwords
+    pub fn set_all_breached(&mut self, breached: BTreeSet<(usize, String)>) {
+        self.breached = breached;
+    }
+
+    /// Marks whether the password of an entry was found in the breach list
+    pub fn set_breached(&mut self, entry: &Entry, breached: bool) {
+        let key = (entry.store.index, entry.pass_id.clone());
+        if breached {
+            self.breached.insert(key);
+        } else {
+            self.breached.remove(&key);
+        }
+    }
+
+    fn is_breached(&self, entry: &Entry) -> bool {
+        self.breached
+            .contains(&(entry.store.index, entry.pass_id.clone()))
+    }
+
+    /// Expands a folder and all folders above it and clears the search
+    pub fn reveal_folder(&mut self, folder: &str) {
+        self.search.clear();
+        let parts: Vec<&str> = folder.split('/').filter(|part| !part.is_empty()).collect();
+        for depth in 1..=parts.len() {
+            self.expanded_folders.insert(parts[..depth].join("/"));
+        }
+    }
+
+    /// Returns the state that is remembered for the store
+    pub fn state(&self) -> StoreState {
+        StoreState {
+            last_search: self.search.clone(),
+            expanded_folders: self.expanded_folders.clone(),
+        }
+    }
+
     pub fn view(&self) -> Element<Message> {
         let search = text_input("Search...", &self.search)
             .size(20)
             .padding(10)
             .on_input(Message::SearchChanged);
 
-        let mut rows = Column::new();
-        let filter = !self.search.is_empty();
-        for entry in &self.passwords {
-            if filter
-                && !entry
-                    .pass_id
-                    .to_lowercase()
-                    .contains(&self.search.to_lowercase())
-            {
-                continue;
-            }
-            rows = rows.push(password_card(entry));
-        }
+        let rows = if self.search.is_empty() {
+            self.folder_tree()
+        } else {
+            let search = self.search.to_lowercase();
+            Column::with_children(
+                self.passwords
+                    .iter()
+                    .filter(|entry| entry.pass_id.to_lowercase().contains(&search))
+                    .map(|entry| {
+                        password_card(
+                            entry,
+                            &entry.pass_id,
+                            0,
+                            self.show_badges,
+                            self.is_breached(entry),
+                        )
+                    }),
+            )
+        };
         let list = scrollable(row![
             rows.align_x(Left).spacing(10),
             horizontal_space().width(12)
@@ -81,6 +147,48 @@ impl PasswordList {
         column![search, list].into()
     }
 
+    /// Lists the entries grouped by folders, entries in collapsed folders are hidden
+    fn folder_tree(&self) -> Column<Message> {
+        let mut rows = Column::new();
+        // Folders of the previous entry
+        let mut current: Vec<&str> = Vec::new();
+
+        for entry in &self.passwords {
+            let parts: Vec<&str> = entry.pass_id.split('/').collect();
+            let (name, folders) = parts.split_last().expect("split yields at least one part");
+
+            let common = current
+                .iter()
+                .zip(folders)
+                .take_while(|(a, b)| a == b)
+                .count();
+            for depth in common..folders.len() {
+                let path = folders[..=depth].join("/");
+                if self.is_visible(&folders[..depth]) {
+                    let expanded = self.expanded_folders.contains(&path);
+                    rows = rows.push(folder_header(folders[depth], path, depth, expanded));
+                }
+            }
+            current = folders.to_vec();
+
+            if self.is_visible(folders) {
+                rows = rows.push(password_card(
+                    entry,
+                    name,
+                    folders.len(),
+                    self.show_badges,
+                    self.is_breached(entry),
+                ));
+            }
+        }
+        rows
+    }
+
+    /// Whether all of the given nested folders are expanded
+    fn is_visible(&self, folders: &[&str]) -> bool {
+        (1..=folders.len()).all(|depth| self.expanded_folders.contains(&folders[..depth].join("/")))
+    }
+
     pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::SearchChanged(search) => {
@@ -88,37 +196,136 @@ impl PasswordList {
                 Action::UpdateSearch
             }
             Message::SelectEntry(entry) => Action::SelectEntry(entry),
+            Message::ToggleFolder(path) => {
+                if !self.expanded_folders.remove(&path) {
+                    self.expanded_folders.insert(path);
+                }
+                Action::None
+            }
+            Message::ExportEntry(entry) => {
+                let pass_id = entry.pass_id.clone();
+                Action::ExportFor(vec![entry], pass_id)
+            }
+            Message::ExportFolder(path) => {
+                let prefix = format!("{path}/");
+                let entries = self
+                    .passwords
+                    .iter()
+                    .filter(|entry| entry.pass_id.starts_with(&prefix))
+                    .cloned()
+                    .collect();
+                Action::ExportFor(entries, path)
+            }
         }
     }
 }
 
-fn password_card(entry: &PasswordInfo) -> Element<Message> {
+fn folder_header<'a>(
+    name: &str,
+    path: String,
+    depth: usize,
+    expanded: bool,
+) -> Element<'a, Message> {
+    let marker = if expanded { "-" } else { "+" };
+    let header = container(
+        button(
+            row![text(marker), text(format!("{name}/")).font(Font::MONOSPACE)]
+                .spacing(8)
+                .align_y(Center),
+        )
+        .style(button::text)
+        .on_press(Message::ToggleFolder(path.clone())),
+    )
+    .width(Fill);
+    let export = container(
+        button(text("Export for...").size(12))
+            .style(button::secondary)
+            .padding([2, 6])
+            .on_press(Message::ExportFolder(path)),
+    )
+    .width(Fill)
+    .align_x(Right)
+    .center_y(Fill);
+    row![
+        horizontal_space().width(INDENT * depth as u16),
+        hover(header, export),
+    ]
+    .into()
+}
+
+fn password_card<'a>(
+    entry: &'a Entry,
+    name: &str,
+    depth: usize,
+    show_badge: bool,
+    breached: bool,
+) -> Element<'a, Message> {
     let title = {
         const LIMIT: usize = 40;
 
-        let name = entry.pass_id.clone();
-
-        if name.len() < LIMIT {
-            text(name)
+        if name.chars().count() < LIMIT {
+            text(name.to_owned())
         } else {
-            text!("{}...", &name[0..LIMIT])
+            text!("{}...", name.chars().take(LIMIT).collect::<String>())
         }
         .font(Font::MONOSPACE)
     };
 
     let details = container(
-        button(row!["View", icon::file()].spacing(8).align_y(Center))
-            .on_press(Message::SelectEntry(entry.clone())),
+        row![
+            button("Export for...")
+                .style(button::secondary)
+                .on_press(Message::ExportEntry(entry.clone())),
+            button(row!["View", icon::file()].spacing(8).align_y(Center))
+                .on_press(Message::SelectEntry(entry.clone())),
+        ]
+        .spacing(10),
     )
     .width(Fill)
     .padding(10)
     .align_x(Right)
     .center_y(Fill);
 
-    let card = container(title)
-        .width(Fill)
-        .padding(15)
-        .style(container::rounded_box);
+    let badge = show_badge.then(|| store_badge(entry));
+    let card = container(
+        row![]
+            .push_maybe(badge)
+            .push(title)
+            .push_maybe(breached.then(breach_badge))
+            .spacing(10)
+            .align_y(Center),
+    )
+    .width(Fill)
+    .padding(15)
+    .style(container::rounded_box);
+
+    row![
+        horizontal_space().width(INDENT * depth as u16),
+        hover(card, details)
+    ]
+    .into()
+}
+
+/// Marks an entry whose password was found in the breach list
+fn breach_badge<'a>() -> Element<'a, Message> {
+    container(text("breached").size(12).color(Color::WHITE))
+        .padding([2, 6])
+        .style(|theme: &Theme| container::Style {
+            background: Some(theme.extended_palette().danger.base.color.into()),
+            border: iced::border::rounded(4),
+            ..container::Style::default()
+        })
+        .into()
+}
 
-    hover(card, details)
+fn store_badge<'a>(entry: &Entry) -> Element<'a, Message> {
+    let color = theme::store_color(entry.store.index);
+    container(text(entry.store.name.clone()).size(12).color(Color::WHITE))
+        .padding([2, 6])
+        .style(move |_theme: &Theme| container::Style {
+            background: Some(color.into()),
+            border: iced::border::rounded(4),
+            ..container::Style::default()
+        })
+        .into()
 }
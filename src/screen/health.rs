@@ -0,0 +1,294 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable, text,
+    Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
+
+use crate::{
+    health::{self, Event, Finding, IssueKind, Report},
+    notification::Notification,
+    service::{Channel, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    /// Shows only entries with issues of the given kind, or all
+    Filter(Option<IssueKind>),
+    ShowEntry(String),
+    Rescan,
+    Cancel,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+    /// Opens an entry of the store with the given index in the details
+    ShowEntry(usize, String),
+    /// Replaces the entries of the store with the given index
+    /// whose password was found in the breach list
+    ReportBreaches(usize, BTreeSet<String>),
+}
+
+/// Report of weak, reused and old passwords of a store
+pub struct Health {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    filter: Option<IssueKind>,
+    /// Downloaded list of breached password hashes
+    breach_list: Option<PathBuf>,
+    report: Option<Report>,
+    request: Option<RequestId>,
+    progress: (usize, usize),
+}
+
+impl Health {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        breach_list: Option<PathBuf>,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut health = Self {
+            stores,
+            store,
+            filter: None,
+            breach_list,
+            report: None,
+            request: None,
+            progress: (0, 0),
+        };
+        let task = health.scan(service);
+        (health, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Password health".to_owned()
+    }
+
+    #[cfg(test)]
+    pub fn report(&self) -> Option<&Report> {
+        self.report.as_ref()
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                self.report = None;
+                Action::Run(self.scan(service))
+            }
+            Message::Filter(filter) => {
+                self.filter = filter;
+                Action::None
+            }
+            Message::ShowEntry(pass_id) => Action::ShowEntry(self.store.0.index, pass_id),
+            Message::Rescan => Action::Run(self.scan(service)),
+            Message::Cancel => {
+                service.cancel(Channel::Audit);
+                self.request = None;
+                Action::None
+            }
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                match event {
+                    Event::Progress { done, total } => {
+                        self.progress = (done, total);
+                        Action::None
+                    }
+                    Event::Finished(report) => {
+                        self.request = None;
+                        let failures = report.failures.len();
+                        let breached = report
+                            .breaches_checked
+                            .then(|| report.breached().map(str::to_owned).collect());
+                        self.report = Some(report);
+                        if let Some(breached) = breached {
+                            Action::ReportBreaches(self.store.0.index, breached)
+                        } else if failures > 0 {
+                            Action::Notify(Notification::warning(format!(
+                                "{failures} password files could not be decrypted and were not checked"
+                            )))
+                        } else {
+                            Action::None
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decrypts the entries of the selected store in the background
+    fn scan(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store = self.store.0.clone();
+        let now = service.now();
+        let breach_list = self.breach_list.clone();
+        let (id, task) = service.run(Channel::Audit, move |progress| {
+            health::scan(
+                store.backend.as_ref(),
+                &store.dir,
+                now,
+                breach_list.as_deref(),
+                progress,
+            )
+        });
+        self.request = Some(id);
+        self.progress = (0, 0);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let mut header = row![].spacing(10).align_y(Center);
+        if self.stores.len() > 1 {
+            header = header.push(text("Store:")).push(
+                pick_list(
+                    self.stores.as_slice(),
+                    Some(&self.store),
+                    Message::SelectStore,
+                )
+                .width(150),
+            );
+        }
+        if let Some(report) = &self.report {
+            header = header.push(filter_button(
+                format!("All ({})", report.findings.len()),
+                None,
+                self.filter,
+            ));
+            for kind in IssueKind::ALL {
+                header = header.push(filter_button(
+                    format!("{kind} ({})", report.count(kind)),
+                    Some(kind),
+                    self.filter,
+                ));
+            }
+        }
+        header = header.push(
+            button("Rescan").on_press_maybe(self.request.is_none().then_some(Message::Rescan)),
+        );
+
+        let content: Element<Message> = match (&self.report, self.request) {
+            (_, Some(_)) => {
+                let (done, total) = self.progress;
+                row![
+                    text("Checking passwords..."),
+                    progress_bar(0.0..=total.max(1) as f32, done as f32).height(20),
+                    text(format!("{done} / {total}")),
+                    button("Cancel").on_press(Message::Cancel),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+            }
+            (Some(report), None) => self.report_view(report),
+            (None, None) => text("The store has not been checked").into(),
+        };
+
+        column![
+            text("Password health"),
+            horizontal_rule(38),
+            header,
+            content
+        ]
+        .spacing(20)
+        .padding(20)
+        .into()
+    }
+
+    fn report_view<'a>(&'a self, report: &'a Report) -> Element<'a, Message> {
+        let mut summary = format!(
+            "{} of {} entries have issues.",
+            report.findings.len(),
+            report.audited
+        );
+        if !report.failures.is_empty() {
+            summary.push_str(&format!(
+                " {} password files could not be decrypted.",
+                report.failures.len()
+            ));
+        }
+        let breaches = match (&report.breach_error, report.breaches_checked) {
+            (Some(e), _) => {
+                text(format!("Passwords were not checked for breaches: {e}")).style(text::danger)
+            }
+            (None, false) => {
+                text("Set a breach list in the settings to check for passwords of data breaches")
+                    .style(text::secondary)
+            }
+            (None, true) => text("Passwords were checked against the breach list"),
+        };
+        let findings = report.findings.iter().filter(|finding| {
+            self.filter
+                .is_none_or(|kind| finding.issues.iter().any(|issue| issue.kind() == kind))
+        });
+        column![
+            column![text(summary), breaches].spacing(5),
+            scrollable(
+                Column::with_children(findings.map(finding_card))
+                    .spacing(10)
+                    .padding([0, 12])
+            )
+            .height(Fill),
+        ]
+        .spacing(20)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+fn filter_button(
+    label: String,
+    filter: Option<IssueKind>,
+    selected: Option<IssueKind>,
+) -> Element<'static, Message> {
+    button(text(label))
+        .style(if filter == selected {
+            button::primary
+        } else {
+            button::secondary
+        })
+        .on_press(Message::Filter(filter))
+        .into()
+}
+
+fn finding_card(finding: &Finding) -> Element<Message> {
+    let issues = finding.issues.iter().map(|issue| {
+        let style = match issue.kind() {
+            IssueKind::Weak | IssueKind::Breached | IssueKind::Reused => text::danger,
+            IssueKind::Old | IssueKind::MissingLogin => text::secondary,
+        };
+        text(issue.to_string()).style(style).into()
+    });
+    container(
+        column![
+            button(text(&finding.pass_id).font(Font::MONOSPACE))
+                .style(button::text)
+                .padding(0)
+                .on_press(Message::ShowEntry(finding.pass_id.clone())),
+            Column::with_children(issues).spacing(5),
+        ]
+        .spacing(5),
+    )
+    .width(Fill)
+    .padding(10)
+    .style(container::rounded_box)
+    .into()
+}
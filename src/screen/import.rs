@@ -0,0 +1,422 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, checkbox, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable,
+    text, text_input, Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    import::{self, Conflict, Credentials, Event, Plan, Source, Summary},
+    notification::Notification,
+    service::{Channel, Progress, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    SelectSource(Source),
+    PathChanged(String),
+    PasswordChanged(String),
+    KeyFileChanged(String),
+    FolderChanged(String),
+    Preview,
+    ToggleOverwrite(bool),
+    Edit,
+    Import,
+    Cancel,
+    Restart,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+enum Step {
+    Select,
+    Planning,
+    Preview(Plan),
+    Running { done: usize, total: usize },
+    Done(Summary),
+}
+
+/// Wizard that imports the entries of another password manager into a folder of a store
+pub struct Import {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    source: Source,
+    path: String,
+    /// Master password of an encrypted export
+    password: String,
+    /// Optional key file of an encrypted export
+    key_file: String,
+    folder: String,
+    overwrite: bool,
+    step: Step,
+    request: Option<RequestId>,
+}
+
+impl Import {
+    pub fn new(stores: Vec<Arc<StoreInfo>>, active_store: usize) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        (
+            Self {
+                stores,
+                store,
+                source: Source::Csv,
+                path: String::new(),
+                password: String::new(),
+                key_file: String::new(),
+                folder: "imported".to_owned(),
+                overwrite: false,
+                step: Step::Select,
+                request: None,
+            },
+            Task::none(),
+        )
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Import".to_owned()
+    }
+
+    /// Whether entries are being written
+    pub fn is_busy(&self) -> bool {
+        matches!(self.step, Step::Running { .. })
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                Action::None
+            }
+            Message::SelectSource(source) => {
+                self.source = source;
+                Action::None
+            }
+            Message::PathChanged(path) => {
+                self.path = path;
+                Action::None
+            }
+            Message::PasswordChanged(password) => {
+                self.password = password;
+                Action::None
+            }
+            Message::KeyFileChanged(key_file) => {
+                self.key_file = key_file;
+                Action::None
+            }
+            Message::FolderChanged(folder) => {
+                self.folder = folder;
+                Action::None
+            }
+            Message::Preview => {
+                let path = self.path.trim();
+                if path.is_empty() {
+                    return Action::None;
+                }
+                let file_path = PathBuf::from(path);
+                let store = self.store.0.clone();
+                let source = self.source;
+                let key_file = self.key_file.trim();
+                let credentials = Credentials {
+                    password: self.password.clone(),
+                    key_file: (!key_file.is_empty()).then(|| PathBuf::from(key_file)),
+                };
+                let folder = self.folder.clone();
+                self.step = Step::Planning;
+                Action::Run(self.run(service, Channel::Audit, move |progress| {
+                    progress.report(Event::Planned(import::plan(
+                        source,
+                        &file_path,
+                        &credentials,
+                        store.backend.as_ref(),
+                        &store.dir,
+                        &folder,
+                        progress,
+                    )))
+                }))
+            }
+            Message::ToggleOverwrite(overwrite) => {
+                self.overwrite = overwrite;
+                Action::None
+            }
+            Message::Edit => {
+                self.step = Step::Select;
+                Action::None
+            }
+            Message::Import => {
+                let Step::Preview(plan) = &self.step else {
+                    return Action::None;
+                };
+                let plan = plan.clone();
+                let store = self.store.0.clone();
+                let overwrite = self.overwrite;
+                self.step = Step::Running {
+                    done: 0,
+                    total: plan.entries.len(),
+                };
+                Action::Run(self.run(service, Channel::Batch, move |progress| {
+                    import::write(
+                        store.backend.as_ref(),
+                        &store.dir,
+                        plan,
+                        overwrite,
+                        progress,
+                    )
+                }))
+            }
+            Message::Cancel => {
+                service.cancel(Channel::Batch);
+                Action::None
+            }
+            Message::Restart => {
+                self.path.clear();
+                self.password.clear();
+                self.key_file.clear();
+                self.step = Step::Select;
+                Action::None
+            }
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.handle_event(event)
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Planned(Ok(plan)) => {
+                self.step = Step::Preview(plan);
+                Action::None
+            }
+            Event::Planned(Err(e)) => {
+                self.step = Step::Select;
+                Action::Notify(Notification::error(format!(
+                    "Export could not be read: {e}"
+                )))
+            }
+            Event::Progress { done, total } => {
+                if let Step::Running { .. } = self.step {
+                    self.step = Step::Running { done, total };
+                }
+                Action::None
+            }
+            Event::Finished(summary) => {
+                let notification = if summary.failures.is_empty() {
+                    Notification::success(format!("Imported {} entries", summary.written))
+                } else {
+                    Notification::warning(format!(
+                        "Imported {} entries, {} could not be written",
+                        summary.written,
+                        summary.failures.len()
+                    ))
+                };
+                self.step = Step::Done(summary);
+                Action::Notify(notification)
+            }
+        }
+    }
+
+    fn run(
+        &mut self,
+        service: &mut StoreService,
+        channel: Channel,
+        operation: impl FnOnce(&Progress<Event>) + Send + 'static,
+    ) -> Task<Message> {
+        let (id, task) = service.run(channel, operation);
+        self.request = Some(id);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let content: Element<Message> = match &self.step {
+            Step::Select => self.select_view(),
+            Step::Planning => text("Reading the export...").into(),
+            Step::Preview(plan) => self.preview_view(plan),
+            Step::Running { done, total } => row![
+                progress_bar(0.0..=(*total).max(1) as f32, *done as f32).height(20),
+                text(format!("{done} / {total}")),
+                button("Cancel").on_press(Message::Cancel),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into(),
+            Step::Done(summary) => {
+                let mut result = format!(
+                    "Wrote {} password files, skipped {}.",
+                    summary.written, summary.skipped
+                );
+                if summary.cancelled {
+                    result.push_str(
+                        " The import was cancelled, the remaining entries were not written.",
+                    );
+                }
+                let failures =
+                    Column::with_children(summary.failures.iter().map(|(pass_id, e)| {
+                        text(format!("{pass_id}: {e}"))
+                            .style(text::danger)
+                            .size(12)
+                            .into()
+                    }));
+                column![
+                    text(result),
+                    failures,
+                    button("Import more").on_press(Message::Restart)
+                ]
+                .spacing(20)
+                .into()
+            }
+        };
+
+        column![text("Import"), horizontal_rule(38), content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    fn select_view(&self) -> Element<Message> {
+        let mut content = Column::new().spacing(20);
+        if self.stores.len() > 1 {
+            content = content.push(
+                column![
+                    text("Store:"),
+                    pick_list(
+                        self.stores.as_slice(),
+                        Some(&self.store),
+                        Message::SelectStore
+                    )
+                    .width(Fill),
+                ]
+                .spacing(10),
+            );
+        }
+        content = content
+            .push(
+                column![
+                    text("Format:"),
+                    pick_list(Source::ALL, Some(self.source), Message::SelectSource).width(Fill),
+                ]
+                .spacing(10),
+            )
+            .push(
+                column![
+                    text("Exported file:"),
+                    text_input("Path to the exported file", &self.path)
+                        .on_input(Message::PathChanged)
+                        .on_submit(Message::Preview),
+                ]
+                .spacing(10),
+            );
+        if self.source.is_encrypted() {
+            content = content.push(
+                column![
+                    text("Master password:"),
+                    text_input("Master password", &self.password)
+                        .secure(true)
+                        .font(Font::MONOSPACE)
+                        .on_input(Message::PasswordChanged)
+                        .on_submit(Message::Preview),
+                    text_input("Path to the key file, if any", &self.key_file)
+                        .on_input(Message::KeyFileChanged)
+                        .on_submit(Message::Preview),
+                ]
+                .spacing(10),
+            );
+        }
+        content
+            .push(
+                column![
+                    text("Target folder:"),
+                    text_input("Folder of the store, empty for the top level", &self.folder)
+                        .on_input(Message::FolderChanged)
+                        .on_submit(Message::Preview),
+                ]
+                .spacing(10),
+            )
+            .push(
+                button("Preview")
+                    .on_press_maybe((!self.path.trim().is_empty()).then_some(Message::Preview)),
+            )
+            .into()
+    }
+
+    fn preview_view<'a>(&'a self, plan: &'a Plan) -> Element<'a, Message> {
+        if plan.entries.is_empty() {
+            return column![
+                text("The export contains no entries"),
+                button("Back").on_press(Message::Edit),
+            ]
+            .spacing(20)
+            .into();
+        }
+
+        let entries = Column::with_children(plan.entries.iter().map(|planned| {
+            let mut entry = row![text(&planned.pass_id).font(Font::MONOSPACE).size(12)]
+                .spacing(10)
+                .align_y(Center);
+            if let Some(conflict) = planned.conflict {
+                let style = match conflict {
+                    Conflict::Exists if self.overwrite => text::danger,
+                    _ => text::secondary,
+                };
+                let note = match conflict {
+                    Conflict::Exists if self.overwrite => format!("{conflict}, is replaced"),
+                    _ => format!("{conflict}, is skipped"),
+                };
+                entry = entry.push(text(note).style(style).size(12));
+            } else if planned.append {
+                entry = entry.push(text("OTP is added to the existing entry").size(12));
+            }
+            entry.into()
+        }))
+        .spacing(5)
+        .padding([0, 12]);
+
+        let mut summary = format!(
+            "{} entries of the {} export will be encrypted into {}.",
+            plan.entries.len(),
+            plan.format,
+            self.store.0.name
+        );
+        let conflicts = plan.conflicts();
+        if conflicts > 0 {
+            summary.push_str(&format!(" {conflicts} entries conflict with others."));
+        }
+
+        column![
+            text(summary),
+            checkbox("Replace existing password files", self.overwrite)
+                .on_toggle(Message::ToggleOverwrite),
+            container(scrollable(entries).height(Fill))
+                .width(Fill)
+                .padding(10)
+                .style(container::rounded_box),
+            row![
+                button("Back").on_press(Message::Edit),
+                button("Import").on_press(Message::Import),
+            ]
+            .spacing(10),
+        ]
+        .spacing(20)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
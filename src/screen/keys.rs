@@ -0,0 +1,367 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, row, scrollable, text, text_input,
+    Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    backend::BackendKind,
+    keyring::{self, Event, Key, Keyring},
+    notification::Notification,
+    service::{Channel, Progress, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    ImportPathChanged(String),
+    Import,
+    /// Starts the export of the key with the given fingerprint
+    Export(String),
+    ExportPathChanged(String),
+    SaveExport,
+    CancelExport,
+    Reload,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+/// Public key that is about to be exported
+struct Export {
+    fingerprint: String,
+    path: String,
+}
+
+/// Keys of the keyring with their role in the selected store
+pub struct Keys {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    keyring: Option<Keyring>,
+    import_path: String,
+    export: Option<Export>,
+    request: Option<RequestId>,
+}
+
+impl Keys {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut keys = Self {
+            stores,
+            store,
+            keyring: None,
+            import_path: String::new(),
+            export: None,
+            request: None,
+        };
+        let task = keys.load(service);
+        (keys, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Keys".to_owned()
+    }
+
+    #[cfg(test)]
+    pub fn keyring(&self) -> Option<&Keyring> {
+        self.keyring.as_ref()
+    }
+
+    #[cfg(test)]
+    pub fn import_path(&self) -> &str {
+        &self.import_path
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                self.keyring = None;
+                Action::Run(self.load(service))
+            }
+            Message::ImportPathChanged(path) => {
+                self.import_path = path;
+                Action::None
+            }
+            Message::Import => {
+                let path = self.import_path.trim();
+                if path.is_empty() || self.request.is_some() {
+                    return Action::None;
+                }
+                let file_path = PathBuf::from(path);
+                let store_dir = self.store.0.dir.clone();
+                let keys = service.keys();
+                Action::Run(self.run(service, move |progress| {
+                    keyring::import(keys.as_ref(), &store_dir, &file_path, progress)
+                }))
+            }
+            Message::Export(fingerprint) => {
+                let path = dirs::home_dir()
+                    .unwrap_or_default()
+                    .join(format!("{fingerprint}.asc"));
+                self.export = Some(Export {
+                    fingerprint,
+                    path: path.to_string_lossy().into_owned(),
+                });
+                Action::None
+            }
+            Message::ExportPathChanged(path) => {
+                if let Some(export) = &mut self.export {
+                    export.path = path;
+                }
+                Action::None
+            }
+            Message::SaveExport => {
+                let Some(export) = self.export.take() else {
+                    return Action::None;
+                };
+                let file_path = PathBuf::from(export.path.trim());
+                let keys = service.keys();
+                Action::Run(self.run(service, move |progress| {
+                    keyring::export(keys.as_ref(), &export.fingerprint, file_path, progress)
+                }))
+            }
+            Message::CancelExport => {
+                self.export = None;
+                Action::None
+            }
+            Message::Reload => Action::Run(self.load(service)),
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.handle_event(event)
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Loaded(result) => {
+                self.request = None;
+                match result {
+                    Ok(keyring) => {
+                        self.keyring = Some(keyring);
+                        Action::None
+                    }
+                    Err(e) => Action::Notify(Notification::error(format!(
+                        "Keys could not be listed: {e}"
+                    ))),
+                }
+            }
+            Event::Imported(Ok(imported)) => {
+                self.import_path.clear();
+                Action::Notify(Notification::success(format!(
+                    "Imported {} of {} keys, {} unchanged, {} secret keys",
+                    imported.imported,
+                    imported.considered,
+                    imported.unchanged,
+                    imported.secret_imported
+                )))
+            }
+            Event::Imported(Err(e)) => Action::Notify(Notification::error(format!(
+                "Keys could not be imported: {e}"
+            ))),
+            Event::Exported(result) => {
+                self.request = None;
+                Action::Notify(match result {
+                    Ok(path) => Notification::success(format!(
+                        "Public key was exported to {}",
+                        path.display()
+                    )),
+                    Err(e) => Notification::error(format!("Key could not be exported: {e}")),
+                })
+            }
+        }
+    }
+
+    /// Lists the keys of the keyring, only GPG stores have `.gpg-id` files
+    fn load(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store_dir = self.store.0.dir.clone();
+        let kind = self.store.0.backend.kind();
+        let keys = service.keys();
+        self.run(service, move |progress| {
+            let keyring = if kind == BackendKind::Gpg {
+                keyring::load(keys.as_ref(), &store_dir)
+            } else {
+                Err("key management is only available for GPG stores".to_owned())
+            };
+            progress.report(Event::Loaded(keyring))
+        })
+    }
+
+    fn run(
+        &mut self,
+        service: &mut StoreService,
+        operation: impl FnOnce(&Progress<Event>) + Send + 'static,
+    ) -> Task<Message> {
+        let (id, task) = service.run(Channel::Audit, operation);
+        self.request = Some(id);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let busy = self.request.is_some();
+
+        let mut header = row![].spacing(10).align_y(Center);
+        if self.stores.len() > 1 {
+            header = header.push(text("Store:")).push(
+                pick_list(
+                    self.stores.as_slice(),
+                    Some(&self.store),
+                    Message::SelectStore,
+                )
+                .width(150),
+            );
+        }
+        header = header
+            .push(
+                text_input("Path to a key file to import", &self.import_path)
+                    .on_input(Message::ImportPathChanged)
+                    .on_submit(Message::Import),
+            )
+            .push(button("Import").on_press_maybe(
+                (!busy && !self.import_path.trim().is_empty()).then_some(Message::Import),
+            ))
+            .push(button("Reload").on_press_maybe((!busy).then_some(Message::Reload)));
+
+        let content: Element<Message> = match &self.keyring {
+            Some(keyring) => scrollable(
+                Column::with_children(keyring.keys.iter().map(|key| self.key_card(key, keyring)))
+                    .spacing(10)
+                    .padding([0, 12]),
+            )
+            .height(Fill)
+            .into(),
+            None if busy => text("Looking up keys...").into(),
+            None => text("No keys found").into(),
+        };
+
+        column![text("Keys"), horizontal_rule(38), header, content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    fn key_card<'a>(&'a self, key: &'a Key, keyring: &Keyring) -> Element<'a, Message> {
+        let info = &key.info;
+        let expires = match info.expires {
+            Some(time) => format!("Expires {}", keyring::format_date(time)),
+            None => "Does not expire".to_owned(),
+        };
+        let kind = if info.secret {
+            "Public and secret key"
+        } else {
+            "Public key"
+        };
+
+        let mut card = column![
+            text(key.name()),
+            text(&info.fingerprint).font(Font::MONOSPACE).size(12),
+        ]
+        .spacing(5);
+        for user_id in info.user_ids.iter().skip(1) {
+            card = card.push(text(user_id).size(12));
+        }
+        card = card
+            .push(text(format!("{kind} · {expires} · {}", capabilities(key))))
+            .push(text(store_role(key, keyring)));
+        if let Some(problem) = info.problem {
+            card = card.push(text(format!("Key is {problem}")).style(text::danger));
+        }
+
+        card = match &self.export {
+            Some(export) if export.fingerprint == info.fingerprint => card.push(
+                row![
+                    text_input("Export to", &export.path)
+                        .on_input(Message::ExportPathChanged)
+                        .on_submit(Message::SaveExport),
+                    button("Save").on_press_maybe(
+                        (!export.path.trim().is_empty()).then_some(Message::SaveExport),
+                    ),
+                    button("Cancel")
+                        .style(button::secondary)
+                        .on_press(Message::CancelExport),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            ),
+            _ => card.push(
+                button("Export public key")
+                    .style(button::secondary)
+                    .on_press(Message::Export(info.fingerprint.clone())),
+            ),
+        };
+
+        container(card)
+            .width(Fill)
+            .padding(10)
+            .style(container::rounded_box)
+            .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+fn capabilities(key: &Key) -> String {
+    let info = &key.info;
+    let capabilities: Vec<&str> = [
+        (info.can_encrypt, "encrypt"),
+        (info.can_sign, "sign"),
+        (info.can_certify, "certify"),
+        (info.can_authenticate, "authenticate"),
+    ]
+    .into_iter()
+    .filter_map(|(can, name)| can.then_some(name))
+    .collect();
+    if capabilities.is_empty() {
+        "no capabilities".to_owned()
+    } else {
+        capabilities.join(", ")
+    }
+}
+
+/// Describes in which `.gpg-id` files the key appears and whether it can decrypt the store
+fn store_role(key: &Key, keyring: &Keyring) -> String {
+    let folders: Vec<String> = key
+        .folders
+        .iter()
+        .map(|folder| format!("{folder}/"))
+        .collect();
+    if folders.is_empty() {
+        return "Not a recipient in this store".to_owned();
+    }
+    let recipient = format!("Recipient of {}", folders.join(", "));
+    if !key.can_decrypt() {
+        return recipient;
+    }
+    if key.folders.len() == keyring.folder_count {
+        format!("{recipient}, can decrypt the whole store")
+    } else {
+        format!(
+            "{recipient}, can decrypt {} of {} folders",
+            key.folders.len(),
+            keyring.folder_count
+        )
+    }
+}
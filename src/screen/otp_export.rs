@@ -0,0 +1,315 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, checkbox, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable,
+    text, text_input, Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    authenticator::{self, Event, Format, Found},
+    notification::Notification,
+    service::{Channel, Progress, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    Toggle(usize, bool),
+    SelectAll(bool),
+    SelectFormat(Format),
+    PathChanged(String),
+    Export,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+enum Step {
+    Scanning { done: usize, total: usize },
+    Select,
+}
+
+/// Exports the OTP secrets of a store to the backup format of an authenticator app
+pub struct OtpExport {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    found: Vec<Found>,
+    selected: Vec<bool>,
+    /// Number of password files that could not be decrypted
+    failures: usize,
+    format: Format,
+    path: String,
+    step: Step,
+    request: Option<RequestId>,
+}
+
+impl OtpExport {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut export = Self {
+            stores,
+            store,
+            found: Vec::new(),
+            selected: Vec::new(),
+            failures: 0,
+            format: Format::Aegis,
+            path: String::new(),
+            step: Step::Select,
+            request: None,
+        };
+        export.set_default_path();
+        let task = export.scan(service);
+        (export, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Export OTP".to_owned()
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                Action::Run(self.scan(service))
+            }
+            Message::Toggle(index, selected) => {
+                if let Some(entry) = self.selected.get_mut(index) {
+                    *entry = selected;
+                }
+                Action::None
+            }
+            Message::SelectAll(selected) => {
+                self.selected.fill(selected);
+                Action::None
+            }
+            Message::SelectFormat(format) => {
+                self.format = format;
+                self.set_default_path();
+                Action::None
+            }
+            Message::PathChanged(path) => {
+                self.path = path;
+                Action::None
+            }
+            Message::Export => {
+                let tokens: Vec<_> = self
+                    .found
+                    .iter()
+                    .zip(&self.selected)
+                    .filter(|(_, selected)| **selected)
+                    .map(|(found, _)| found.token.clone())
+                    .collect();
+                if tokens.is_empty() || self.path.trim().is_empty() {
+                    return Action::None;
+                }
+                let format = self.format;
+                let file_path = PathBuf::from(self.path.trim());
+                Action::Run(self.run(service, move |progress| {
+                    authenticator::export(format, tokens, file_path, progress)
+                }))
+            }
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.handle_event(event)
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Progress { done, total } => {
+                self.step = Step::Scanning { done, total };
+                Action::None
+            }
+            Event::Scanned { found, failures } => {
+                self.selected = vec![true; found.len()];
+                self.found = found;
+                self.failures = failures;
+                self.step = Step::Select;
+                self.request = None;
+                Action::None
+            }
+            Event::Exported(result) => {
+                self.request = None;
+                Action::Notify(match result {
+                    Ok(path) => Notification::warning(format!(
+                        "OTP secrets were exported to {}, delete the file once it is imported",
+                        path.display()
+                    )),
+                    Err(e) => {
+                        Notification::error(format!("OTP secrets could not be exported: {e}"))
+                    }
+                })
+            }
+        }
+    }
+
+    fn set_default_path(&mut self) {
+        let file_name = match self.format {
+            Format::Aegis => "aegis-export.json",
+            Format::AndOtp => "otp_accounts.json",
+        };
+        self.path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(file_name)
+            .to_string_lossy()
+            .into_owned();
+    }
+
+    fn scan(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store = self.store.0.clone();
+        self.found.clear();
+        self.selected.clear();
+        self.step = Step::Scanning { done: 0, total: 0 };
+        self.run(service, move |progress| {
+            authenticator::scan(store.backend.as_ref(), &store.dir, progress)
+        })
+    }
+
+    fn run(
+        &mut self,
+        service: &mut StoreService,
+        operation: impl FnOnce(&Progress<Event>) + Send + 'static,
+    ) -> Task<Message> {
+        let (id, task) = service.run(Channel::Audit, operation);
+        self.request = Some(id);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let content: Element<Message> = match self.step {
+            Step::Scanning { done, total } => row![
+                text("Looking for OTP secrets..."),
+                progress_bar(0.0..=total.max(1) as f32, done as f32).height(20),
+                text(format!("{done} / {total}")),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into(),
+            Step::Select => self.select_view(),
+        };
+
+        column![text("Export OTP"), horizontal_rule(38), content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    fn select_view(&self) -> Element<Message> {
+        let busy = self.request.is_some();
+        let mut content = Column::new().spacing(20);
+        if self.stores.len() > 1 {
+            content = content.push(
+                row![
+                    text("Store:"),
+                    pick_list(
+                        self.stores.as_slice(),
+                        Some(&self.store),
+                        Message::SelectStore
+                    )
+                    .width(150),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            );
+        }
+
+        let warning = container(
+            column![
+                text("The exported file contains the OTP secrets in plain text").size(20),
+                text(
+                    "Anyone who gets hold of it can generate your one-time passwords. \
+                     Import it into your authenticator app right away and delete it afterwards."
+                ),
+            ]
+            .spacing(10),
+        )
+        .width(Fill)
+        .padding(10)
+        .style(container::bordered_box);
+        content = content.push(warning);
+
+        if self.failures > 0 {
+            content = content.push(
+                text(format!(
+                    "{} password files could not be decrypted and are not listed",
+                    self.failures
+                ))
+                .style(text::danger),
+            );
+        }
+        if self.found.is_empty() {
+            return content
+                .push(text("No entries with OTP secrets found"))
+                .into();
+        }
+
+        let selected = self.selected.iter().filter(|selected| **selected).count();
+        let tokens = Column::with_children(self.found.iter().zip(&self.selected).enumerate().map(
+            |(index, (found, selected))| {
+                row![
+                    checkbox(found.token.label(), *selected)
+                        .on_toggle(move |selected| Message::Toggle(index, selected)),
+                    text(&found.pass_id).font(Font::MONOSPACE).size(12),
+                ]
+                .spacing(20)
+                .align_y(Center)
+                .into()
+            },
+        ))
+        .spacing(5)
+        .padding([0, 12]);
+
+        content
+            .push(
+                row![
+                    text(format!("{selected} of {} selected", self.found.len())),
+                    button("Select all")
+                        .style(button::secondary)
+                        .on_press(Message::SelectAll(true)),
+                    button("Select none")
+                        .style(button::secondary)
+                        .on_press(Message::SelectAll(false)),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            )
+            .push(scrollable(tokens).height(Fill))
+            .push(
+                row![
+                    pick_list(Format::ALL, Some(self.format), Message::SelectFormat).width(120),
+                    text_input("Export to", &self.path).on_input(Message::PathChanged),
+                    button("Export").style(button::danger).on_press_maybe(
+                        (!busy && selected > 0 && !self.path.trim().is_empty())
+                            .then_some(Message::Export),
+                    ),
+                ]
+                .spacing(10)
+                .align_y(Center),
+            )
+            .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
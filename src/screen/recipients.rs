@@ -0,0 +1,459 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable, text,
+    text_input, Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use crate::{
+    backend::BackendKind,
+    gpg::KeyInfo,
+    notification::Notification,
+    recipients::{self, Reencryption, Summary},
+    service::{self, Channel, Outcome, Request, RequestId, Response, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+/// Folder of a store relative to the store directory, the store root is empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct Folder(String);
+
+impl fmt::Display for Folder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    SelectFolder(Folder),
+    NewRecipientChanged(String),
+    AddRecipient,
+    RemoveRecipient(usize),
+    ResetRecipients,
+    Apply,
+    Cancel,
+    KeysResolved(Outcome),
+    Reencrypted((RequestId, Reencryption)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+/// Re-encryption that is currently running
+struct Running {
+    request: RequestId,
+    done: usize,
+    total: usize,
+}
+
+/// Shows and edits the recipients of a folder of a store
+pub struct Recipients {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    folders: Vec<Folder>,
+    folder: Folder,
+    /// The recipients file that governs the selected folder
+    recipients_file: Option<PathBuf>,
+    /// Password files that are re-encrypted when the recipients are changed
+    files: Vec<PathBuf>,
+    saved: Vec<String>,
+    recipients: Vec<String>,
+    keys: Vec<KeyInfo>,
+    keys_request: Option<RequestId>,
+    new_recipient: String,
+    running: Option<Running>,
+    summary: Option<Summary>,
+}
+
+impl Recipients {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        folder: String,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut recipients = Self {
+            stores,
+            store,
+            folders: Vec::new(),
+            folder: Folder(folder),
+            recipients_file: None,
+            files: Vec::new(),
+            saved: Vec::new(),
+            recipients: Vec::new(),
+            keys: Vec::new(),
+            keys_request: None,
+            new_recipient: String::new(),
+            running: None,
+            summary: None,
+        };
+        recipients.folders = recipients::folders(&recipients.store.0.dir)
+            .into_iter()
+            .map(Folder)
+            .collect();
+        let task = recipients.load_folder(service);
+        (recipients, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Recipients".to_owned()
+    }
+
+    /// Whether a re-encryption is running
+    pub fn is_busy(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                if self.is_busy() {
+                    return Action::None;
+                }
+                self.folders = recipients::folders(&store.0.dir)
+                    .into_iter()
+                    .map(Folder)
+                    .collect();
+                self.store = store;
+                self.folder = Folder(String::new());
+                self.summary = None;
+                Action::Run(self.load_folder(service))
+            }
+            Message::SelectFolder(folder) => {
+                if self.is_busy() {
+                    return Action::None;
+                }
+                self.folder = folder;
+                self.summary = None;
+                Action::Run(self.load_folder(service))
+            }
+            Message::NewRecipientChanged(recipient) => {
+                self.new_recipient = recipient;
+                Action::None
+            }
+            Message::AddRecipient => {
+                let recipient = self.new_recipient.trim().to_owned();
+                if recipient.is_empty() || self.recipients.contains(&recipient) {
+                    return Action::None;
+                }
+                self.recipients.push(recipient);
+                self.new_recipient.clear();
+                Action::Run(self.resolve_keys(service))
+            }
+            Message::RemoveRecipient(index) => {
+                if index < self.recipients.len() {
+                    self.recipients.remove(index);
+                }
+                Action::None
+            }
+            Message::ResetRecipients => {
+                self.recipients = self.saved.clone();
+                Action::Run(self.resolve_keys(service))
+            }
+            Message::KeysResolved((id, result)) => {
+                if self.keys_request != Some(id) {
+                    return Action::None;
+                }
+                self.keys_request = None;
+                match result {
+                    Ok(Response::Keys(keys)) => {
+                        self.keys = keys;
+                        Action::None
+                    }
+                    Ok(_) | Err(service::Error::Cancelled) => Action::None,
+                    Err(e) => Action::Notify(Notification::error(format!(
+                        "Keys could not be looked up: {e}"
+                    ))),
+                }
+            }
+            Message::Apply => self.apply(service),
+            Message::Cancel => {
+                service.cancel(Channel::Batch);
+                Action::None
+            }
+            Message::Reencrypted((id, reencryption)) => {
+                let Some(running) = &mut self.running else {
+                    return Action::None;
+                };
+                if running.request != id {
+                    return Action::None;
+                }
+                match reencryption {
+                    Reencryption::Progress { done, total } => {
+                        running.done = done;
+                        running.total = total;
+                        Action::None
+                    }
+                    Reencryption::Finished(summary) => {
+                        self.running = None;
+                        let notification = summary_notification(&summary);
+                        self.summary = Some(summary);
+                        Action::Notify(notification)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the recipients to the recipients file of the selected folder
+    /// and re-encrypts all password files governed by it
+    fn apply(&mut self, service: &mut StoreService) -> Action {
+        if self.is_busy() || self.recipients.is_empty() {
+            return Action::None;
+        }
+        let file = self.own_recipients_file();
+        if let Err(e) = recipients::write(&file, &self.recipients) {
+            return Action::Notify(Notification::error(format!(
+                "{} could not be written: {e}",
+                file.display()
+            )));
+        }
+        self.recipients_file = Some(file);
+        self.saved = self.recipients.clone();
+        self.summary = None;
+
+        let files = self.files.clone();
+        let recipients = self.recipients.clone();
+        let total = files.len();
+        let backend = Arc::clone(&self.store.0.backend);
+        let (request, task) = service.run(Channel::Batch, move |progress| {
+            recipients::reencrypt(backend.as_ref(), files, recipients, progress)
+        });
+        self.running = Some(Running {
+            request,
+            done: 0,
+            total,
+        });
+        Action::Run(task.map(Message::Reencrypted))
+    }
+
+    /// Reads the recipients of the selected folder
+    fn load_folder(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store = &self.store.0;
+        self.recipients_file =
+            recipients::governing_file(store.backend.as_ref(), &store.dir, &self.folder.0);
+        self.files =
+            recipients::governed_files(store.backend.as_ref(), &store.dir.join(&self.folder.0));
+        self.saved = self
+            .recipients_file
+            .as_ref()
+            .and_then(|file| recipients::read(file).ok())
+            .unwrap_or_default();
+        self.recipients = self.saved.clone();
+        self.resolve_keys(service)
+    }
+
+    /// The recipients file located directly in the selected folder
+    fn own_recipients_file(&self) -> PathBuf {
+        let store = &self.store.0;
+        store
+            .dir
+            .join(&self.folder.0)
+            .join(store.backend.recipients_file())
+    }
+
+    fn resolve_keys(&mut self, service: &mut StoreService) -> Task<Message> {
+        // Recipients of age stores are public keys themselves
+        if self.store.0.backend.kind() != BackendKind::Gpg {
+            self.keys_request = None;
+            return Task::none();
+        }
+        let (id, task) =
+            service.submit(Channel::Keys, Request::ResolveKeys(self.recipients.clone()));
+        self.keys_request = Some(id);
+        task.map(Message::KeysResolved)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let busy = self.is_busy();
+
+        let mut location = row![].spacing(10).align_y(Center);
+        if self.stores.len() > 1 {
+            location = location.push(text("Store:")).push(
+                pick_list(
+                    self.stores.as_slice(),
+                    Some(&self.store),
+                    Message::SelectStore,
+                )
+                .width(150),
+            );
+        }
+        location = location.push(text("Folder:")).push(
+            pick_list(
+                self.folders.as_slice(),
+                Some(&self.folder),
+                Message::SelectFolder,
+            )
+            .width(Fill),
+        );
+
+        let file_name = self.store.0.backend.recipients_file();
+        let origin = match &self.recipients_file {
+            Some(file) if *file == self.own_recipients_file() => {
+                text(format!("Recipients are set in {}", file.display()))
+            }
+            Some(file) => text(format!(
+                "Recipients are inherited from {}, saving creates a {file_name} file in this folder",
+                file.display()
+            )),
+            None => text(format!(
+                "No {file_name} file found, password files cannot be encrypted"
+            ))
+            .style(text::danger),
+        };
+
+        let recipient_list = Column::with_children(self.recipients.iter().enumerate().map(
+            |(index, recipient)| {
+                row![
+                    text(recipient).font(Font::MONOSPACE).width(250),
+                    self.key_details(recipient),
+                    button("Remove")
+                        .on_press_maybe((!busy).then_some(Message::RemoveRecipient(index))),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+            },
+        ))
+        .spacing(10);
+
+        let add_recipient = row![
+            text_input("Key ID, fingerprint or email", &self.new_recipient)
+                .on_input(Message::NewRecipientChanged)
+                .on_submit(Message::AddRecipient),
+            button("Add").on_press_maybe(
+                (!busy && !self.new_recipient.trim().is_empty()).then_some(Message::AddRecipient)
+            ),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
+        let changed = self.recipients != self.saved;
+        let controls = row![
+            button(text(format!(
+                "Save and re-encrypt {} password files",
+                self.files.len()
+            )))
+            .on_press_maybe((!busy && !self.recipients.is_empty()).then_some(Message::Apply)),
+            button("Reset").on_press_maybe((!busy && changed).then_some(Message::ResetRecipients)),
+        ]
+        .spacing(10);
+
+        let status: Option<Element<Message>> = if let Some(running) = &self.running {
+            Some(
+                row![
+                    progress_bar(0.0..=running.total.max(1) as f32, running.done as f32).height(20),
+                    text(format!("{} / {}", running.done, running.total)),
+                    button("Cancel").on_press(Message::Cancel),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into(),
+            )
+        } else {
+            self.summary
+                .as_ref()
+                .map(|summary| self.summary_view(summary))
+        };
+
+        column![
+            text("Recipients"),
+            horizontal_rule(38),
+            location,
+            origin,
+            recipient_list,
+            add_recipient,
+            controls,
+        ]
+        .push_maybe(status)
+        .spacing(20)
+        .padding(20)
+        .into()
+    }
+
+    fn key_details(&self, recipient: &str) -> Element<Message> {
+        if self.store.0.backend.kind() != BackendKind::Gpg {
+            return text("age recipient").width(Fill).into();
+        }
+        let key = self.keys.iter().find(|key| key.recipient == recipient);
+        match key {
+            Some(key) => {
+                let mut details = column![
+                    text(key.user_ids.join(", ")),
+                    text(&key.fingerprint).font(Font::MONOSPACE).size(12),
+                ];
+                if let Some(problem) = key.problem {
+                    details = details.push(text(format!("Key is {problem}")).style(text::danger));
+                }
+                details.width(Fill).into()
+            }
+            None if self.keys_request.is_some() => text("Looking up key...").width(Fill).into(),
+            None => text("No key found").style(text::danger).width(Fill).into(),
+        }
+    }
+
+    fn summary_view(&self, summary: &Summary) -> Element<Message> {
+        let failures = Column::with_children(summary.failures.iter().map(|(file, e)| {
+            let file = file.strip_prefix(&self.store.0.dir).unwrap_or(file);
+            text(format!("{}: {e}", file.display()))
+                .style(text::danger)
+                .into()
+        }))
+        .spacing(5);
+
+        container(
+            column![
+                text(summary_text(summary)),
+                scrollable(failures).height(150)
+            ]
+            .spacing(10),
+        )
+        .padding(10)
+        .width(Fill)
+        .style(container::rounded_box)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+fn summary_text(summary: &Summary) -> String {
+    let mut text = format!(
+        "Re-encrypted {} of {} password files",
+        summary.reencrypted, summary.total
+    );
+    if !summary.failures.is_empty() {
+        text.push_str(&format!(", {} failed", summary.failures.len()));
+    }
+    if summary.cancelled {
+        text.push_str(", cancelled");
+    }
+    text
+}
+
+fn summary_notification(summary: &Summary) -> Notification {
+    let text = summary_text(summary);
+    if !summary.failures.is_empty() {
+        Notification::error(text)
+    } else if summary.cancelled {
+        Notification::warning(text)
+    } else {
+        Notification::success(text)
+    }
+}
@@ -0,0 +1,377 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable, text,
+    Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::sync::Arc;
+
+use crate::{
+    backend::BackendKind,
+    notification::Notification,
+    rotation::{self, Candidates, Event, KeyOption, Plan, Report},
+    service::{Channel, Progress, RequestId, StoreService},
+    store::StoreInfo,
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    SelectOld(KeyOption),
+    SelectNew(KeyOption),
+    Preview,
+    Edit,
+    Rotate,
+    Cancel,
+    Restart,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+enum Step {
+    Loading,
+    Select,
+    Planning,
+    Preview(Plan),
+    Running { done: usize, total: usize },
+    Done(Result<Report, String>),
+}
+
+/// Wizard that replaces a recipient key in all `.gpg-id` files of a store
+pub struct Rotation {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    candidates: Option<Candidates>,
+    old: Option<KeyOption>,
+    new: Option<KeyOption>,
+    step: Step,
+    request: Option<RequestId>,
+}
+
+impl Rotation {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut rotation = Self {
+            stores,
+            store,
+            candidates: None,
+            old: None,
+            new: None,
+            step: Step::Loading,
+            request: None,
+        };
+        let task = rotation.load_candidates(service);
+        (rotation, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Key rotation".to_owned()
+    }
+
+    /// Whether the rotation is being performed
+    pub fn is_busy(&self) -> bool {
+        matches!(self.step, Step::Running { .. })
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                if self.is_busy() {
+                    return Action::None;
+                }
+                self.store = store;
+                Action::Run(self.load_candidates(service))
+            }
+            Message::SelectOld(key) => {
+                self.old = Some(key);
+                Action::None
+            }
+            Message::SelectNew(key) => {
+                self.new = Some(key);
+                Action::None
+            }
+            Message::Preview => {
+                let (Some(old), Some(new)) = (self.old.clone(), self.new.clone()) else {
+                    return Action::None;
+                };
+                let store_dir = self.store.0.dir.clone();
+                self.step = Step::Planning;
+                Action::Run(self.run(service, Channel::Audit, move |progress| {
+                    progress.report(Event::Planned(rotation::plan(&store_dir, old, new)))
+                }))
+            }
+            Message::Edit => {
+                self.step = Step::Select;
+                Action::None
+            }
+            Message::Rotate => {
+                let Step::Preview(plan) = &self.step else {
+                    return Action::None;
+                };
+                let plan = plan.clone();
+                let store_dir = self.store.0.dir.clone();
+                let backend = Arc::clone(&self.store.0.backend);
+                self.step = Step::Running {
+                    done: 0,
+                    total: plan.file_count(),
+                };
+                Action::Run(self.run(service, Channel::Batch, move |progress| {
+                    rotation::rotate(backend.as_ref(), &store_dir, plan, progress)
+                }))
+            }
+            Message::Cancel => {
+                service.cancel(Channel::Batch);
+                Action::None
+            }
+            Message::Restart => Action::Run(self.load_candidates(service)),
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.handle_event(event)
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Candidates(Ok(candidates)) => {
+                self.old = self.old.take().filter(|old| candidates.old.contains(old));
+                self.new = self.new.take().filter(|new| candidates.new.contains(new));
+                self.candidates = Some(candidates);
+                self.step = Step::Select;
+                Action::None
+            }
+            Event::Planned(Ok(plan)) => {
+                self.step = Step::Preview(plan);
+                Action::None
+            }
+            Event::Candidates(Err(e)) | Event::Planned(Err(e)) => {
+                self.step = Step::Select;
+                Action::Notify(Notification::error(format!(
+                    "Keys could not be looked up: {e}"
+                )))
+            }
+            Event::Progress { done, total } => {
+                if let Step::Running { .. } = self.step {
+                    self.step = Step::Running { done, total };
+                }
+                Action::None
+            }
+            Event::Finished(result) => {
+                let notification = finished(&result);
+                self.step = Step::Done(result);
+                Action::Notify(notification)
+            }
+        }
+    }
+
+    fn load_candidates(&mut self, service: &mut StoreService) -> Task<Message> {
+        let store_dir = self.store.0.dir.clone();
+        let kind = self.store.0.backend.kind();
+        self.step = Step::Loading;
+        self.run(service, Channel::Audit, move |progress| {
+            let candidates = if kind == BackendKind::Gpg {
+                rotation::candidates(&store_dir)
+            } else {
+                Err("key rotation is only available for GPG stores".to_owned())
+            };
+            progress.report(Event::Candidates(candidates))
+        })
+    }
+
+    fn run(
+        &mut self,
+        service: &mut StoreService,
+        channel: Channel,
+        operation: impl FnOnce(&Progress<Event>) + Send + 'static,
+    ) -> Task<Message> {
+        let (id, task) = service.run(channel, operation);
+        self.request = Some(id);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let content: Element<Message> = match &self.step {
+            Step::Loading => text("Looking up keys...").into(),
+            Step::Select => self.select_view(),
+            Step::Planning => text("Looking for affected folders...").into(),
+            Step::Preview(plan) => self.preview_view(plan),
+            Step::Running { done, total } => row![
+                progress_bar(0.0..=(*total).max(1) as f32, *done as f32).height(20),
+                text(format!("{done} / {total}")),
+                button("Cancel").on_press(Message::Cancel),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into(),
+            Step::Done(result) => {
+                let summary = match result {
+                    Ok(report) => {
+                        let commit = match &report.commit {
+                            Some(Ok(())) => "The changes were committed.".to_owned(),
+                            Some(Err(e)) => format!("The changes could not be committed: {e}"),
+                            None => "The store is not a git repository.".to_owned(),
+                        };
+                        text(format!(
+                            "Replaced the key in {} folders and re-encrypted {} password files. {commit}",
+                            report.folders, report.files
+                        ))
+                    }
+                    Err(e) => text(format!("Key rotation failed: {e}")).style(text::danger),
+                };
+                column![summary, button("Start over").on_press(Message::Restart)]
+                    .spacing(20)
+                    .into()
+            }
+        };
+
+        column![text("Key rotation"), horizontal_rule(38), content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    fn select_view(&self) -> Element<Message> {
+        let Some(candidates) = &self.candidates else {
+            return text("No keys found").into();
+        };
+        let mut content = Column::new().spacing(20);
+        if self.stores.len() > 1 {
+            content = content.push(
+                column![
+                    text("Store:"),
+                    pick_list(
+                        self.stores.as_slice(),
+                        Some(&self.store),
+                        Message::SelectStore
+                    )
+                    .width(Fill),
+                ]
+                .spacing(10),
+            );
+        }
+        content
+            .push(
+                column![
+                    text("Key to replace:"),
+                    pick_list(
+                        candidates.old.as_slice(),
+                        self.old.as_ref(),
+                        Message::SelectOld
+                    )
+                    .width(Fill),
+                ]
+                .spacing(10),
+            )
+            .push(
+                column![
+                    text("Replacement key:"),
+                    pick_list(
+                        candidates.new.as_slice(),
+                        self.new.as_ref(),
+                        Message::SelectNew
+                    )
+                    .width(Fill),
+                ]
+                .spacing(10),
+            )
+            .push(
+                button("Preview").on_press_maybe(
+                    (self.old.is_some() && self.new.is_some() && self.old != self.new)
+                        .then_some(Message::Preview),
+                ),
+            )
+            .into()
+    }
+
+    fn preview_view<'a>(&'a self, plan: &'a Plan) -> Element<'a, Message> {
+        if plan.changes.is_empty() {
+            return column![
+                text(format!("No .gpg-id file lists {}", plan.old.label)),
+                button("Back").on_press(Message::Edit),
+            ]
+            .spacing(20)
+            .into();
+        }
+
+        let changes = Column::with_children(plan.changes.iter().map(|change| {
+            let entries = Column::with_children(change.files.iter().map(|file| {
+                let file = file.strip_prefix(&self.store.0.dir).unwrap_or(file);
+                text(file.with_extension("").to_string_lossy().into_owned())
+                    .font(Font::MONOSPACE)
+                    .size(12)
+                    .into()
+            }));
+            container(
+                column![
+                    text(format!("{}/", change.folder)).font(Font::MONOSPACE),
+                    text(format!("New recipients: {}", change.recipients.join(", "))),
+                    entries,
+                ]
+                .spacing(5),
+            )
+            .width(Fill)
+            .padding(10)
+            .style(container::rounded_box)
+            .into()
+        }))
+        .spacing(10)
+        .padding([0, 12]);
+
+        column![
+            text(format!(
+                "Replacing {} with {} affects {} folders and {} password files.",
+                plan.old.label,
+                plan.new.label,
+                plan.changes.len(),
+                plan.file_count()
+            )),
+            scrollable(changes).height(Fill),
+            row![
+                button("Back").on_press(Message::Edit),
+                button("Replace key").on_press(Message::Rotate),
+            ]
+            .spacing(10),
+        ]
+        .spacing(20)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+/// Notification about the outcome of a rotation,
+/// also shown when the rotation ends after its screen was left
+pub fn finished(result: &Result<Report, String>) -> Notification {
+    match result {
+        Ok(report) => match &report.commit {
+            Some(Err(e)) => Notification::warning(format!(
+                "Key was replaced, but the changes could not be committed: {e}"
+            )),
+            _ => Notification::success(format!(
+                "Key was replaced in {} folders and {} password files",
+                report.folders, report.files
+            )),
+        },
+        Err(e) => Notification::error(format!("Key rotation failed: {e}")),
+    }
+}
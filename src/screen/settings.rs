@@ -1,28 +1,74 @@
-use iced::widget::{column, horizontal_rule, pick_list, text};
-use iced::{Element, Fill, Subscription, Task, Theme};
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, checkbox, column, horizontal_rule, pick_list, row, text, text_input, Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task, Theme};
+use std::path::PathBuf;
+
+use crate::{
+    backend::BackendKind,
+    config::{Passphrases, StoreConfig},
+};
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ThemeChanged(Theme),
+    StoreNameChanged(String),
+    StorePathChanged(String),
+    StoreBackendChanged(BackendKind),
+    StoreIdentitiesChanged(String),
+    AddStore,
+    RemoveStore(usize),
+    LoopbackPinentryToggled(bool),
+    ForgetOnQuitToggled(bool),
+    ForgetOnLockToggled(bool),
+    BreachListChanged(String),
+    SaveBreachList,
 }
 
 pub enum Action {
     None,
     ChangeTheme(Theme),
+    ChangeStores(Vec<StoreConfig>),
+    ChangePassphrases(Passphrases),
+    /// Sets the downloaded list of breached password hashes, `None` disables the check
+    ChangeBreachList(Option<PathBuf>),
     Run(Task<Message>),
     Back,
 }
 
 pub struct Settings {
     theme: Theme,
+    stores: Vec<StoreConfig>,
+    store_name: String,
+    store_path: String,
+    store_backend: BackendKind,
+    /// Identity file of a new age store, the passage default is used when empty
+    store_identities: String,
+    passphrases: Passphrases,
+    breach_list: String,
 }
 
 impl Settings {
-    pub fn new(theme: &mut Theme) -> (Self, Task<Message>) {
+    pub fn new(
+        theme: &mut Theme,
+        stores: Vec<StoreConfig>,
+        passphrases: Passphrases,
+        breach_list: Option<PathBuf>,
+    ) -> (Self, Task<Message>) {
         // TODO: theme should not be cloned
         (
             Self {
                 theme: theme.clone(),
+                stores,
+                store_name: String::new(),
+                store_path: String::new(),
+                store_backend: BackendKind::default(),
+                store_identities: String::new(),
+                passphrases,
+                breach_list: breach_list
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
             },
             Task::none(),
         )
@@ -38,9 +84,80 @@ impl Settings {
                 self.theme = theme.clone();
                 Action::ChangeTheme(theme)
             }
+            Message::StoreNameChanged(name) => {
+                self.store_name = name;
+                Action::None
+            }
+            Message::StorePathChanged(path) => {
+                self.store_path = path;
+                Action::None
+            }
+            Message::StoreBackendChanged(backend) => {
+                self.store_backend = backend;
+                Action::None
+            }
+            Message::StoreIdentitiesChanged(path) => {
+                self.store_identities = path;
+                Action::None
+            }
+            Message::AddStore => {
+                if !self.can_add_store() {
+                    return Action::None;
+                }
+                let mut store = StoreConfig::new(
+                    self.store_name.trim(),
+                    PathBuf::from(self.store_path.trim()),
+                );
+                store.backend = self.store_backend;
+                let identities = self.store_identities.trim();
+                if store.backend == BackendKind::Age && !identities.is_empty() {
+                    store.identities = Some(PathBuf::from(identities));
+                }
+                self.stores.push(store);
+                self.store_name.clear();
+                self.store_path.clear();
+                self.store_identities.clear();
+                Action::ChangeStores(self.stores.clone())
+            }
+            Message::RemoveStore(index) => {
+                // At least one store has to remain
+                if self.stores.len() > 1 && index < self.stores.len() {
+                    self.stores.remove(index);
+                    Action::ChangeStores(self.stores.clone())
+                } else {
+                    Action::None
+                }
+            }
+            Message::LoopbackPinentryToggled(enabled) => {
+                self.passphrases.loopback_pinentry = enabled;
+                Action::ChangePassphrases(self.passphrases.clone())
+            }
+            Message::ForgetOnQuitToggled(enabled) => {
+                self.passphrases.forget_on_quit = enabled;
+                Action::ChangePassphrases(self.passphrases.clone())
+            }
+            Message::ForgetOnLockToggled(enabled) => {
+                self.passphrases.forget_on_lock = enabled;
+                Action::ChangePassphrases(self.passphrases.clone())
+            }
+            Message::BreachListChanged(path) => {
+                self.breach_list = path;
+                Action::None
+            }
+            Message::SaveBreachList => {
+                let path = self.breach_list.trim();
+                Action::ChangeBreachList((!path.is_empty()).then(|| PathBuf::from(path)))
+            }
         }
     }
 
+    fn can_add_store(&self) -> bool {
+        let name = self.store_name.trim();
+        !name.is_empty()
+            && !self.store_path.trim().is_empty()
+            && !self.stores.iter().any(|store| store.name == name)
+    }
+
     pub fn view(&self) -> Element<Message> {
         let choose_theme = column![
             text("Theme:"),
@@ -48,12 +165,104 @@ impl Settings {
         ]
         .spacing(10);
 
+        let store_list =
+            Column::with_children(self.stores.iter().enumerate().map(|(index, store)| {
+                row![
+                    text(&store.name).width(150),
+                    text(store.path.to_string_lossy())
+                        .font(Font::MONOSPACE)
+                        .width(Fill),
+                    text(store.backend.to_string()).width(120),
+                    button("Remove").on_press_maybe(
+                        (self.stores.len() > 1).then_some(Message::RemoveStore(index))
+                    ),
+                ]
+                .spacing(10)
+                .align_y(Center)
+                .into()
+            }))
+            .spacing(10);
+
+        let add_store = row![
+            text_input("Name", &self.store_name)
+                .on_input(Message::StoreNameChanged)
+                .width(150),
+            text_input("Path to password store", &self.store_path)
+                .on_input(Message::StorePathChanged)
+                .on_submit(Message::AddStore),
+            pick_list(
+                BackendKind::ALL,
+                Some(self.store_backend),
+                Message::StoreBackendChanged
+            )
+            .width(120),
+            button("Add").on_press_maybe(self.can_add_store().then_some(Message::AddStore)),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
+        let identities = (self.store_backend == BackendKind::Age).then(|| {
+            text_input(
+                "Identity file (defaults to ~/.passage/identities)",
+                &self.store_identities,
+            )
+            .on_input(Message::StoreIdentitiesChanged)
+            .on_submit(Message::AddStore)
+        });
+
+        let manage_stores = column![text("Password stores:"), store_list, add_store]
+            .push_maybe(identities)
+            .spacing(10);
+
+        let passphrases = column![
+            text("Passphrases:"),
+            checkbox(
+                "Ask for passphrases in partout instead of the pinentry of gpg-agent",
+                self.passphrases.loopback_pinentry,
+            )
+            .on_toggle(Message::LoopbackPinentryToggled),
+            checkbox(
+                "Forget cached passphrases when partout quits",
+                self.passphrases.forget_on_quit,
+            )
+            .on_toggle(Message::ForgetOnQuitToggled),
+            checkbox(
+                "Forget cached passphrases when partout is locked",
+                self.passphrases.forget_on_lock,
+            )
+            .on_toggle(Message::ForgetOnLockToggled),
+        ]
+        .spacing(10);
+
+        let breach_list = column![
+            text("Breached passwords:"),
+            row![
+                text_input(
+                    "Path to the SHA-1 list of Have I Been Pwned, ordered by hash",
+                    &self.breach_list
+                )
+                .on_input(Message::BreachListChanged)
+                .on_submit(Message::SaveBreachList),
+                button("Save").on_press(Message::SaveBreachList),
+            ]
+            .spacing(10)
+            .align_y(Center),
+            text("Passwords are looked up in the file without any network access.").size(12),
+        ]
+        .spacing(10);
+
         let content = column![
             text("Settings"),
             horizontal_rule(38),
             choose_theme,
             horizontal_rule(38),
-            text("Note: Settings cannot be saved at the moment."),
+            manage_stores,
+            horizontal_rule(38),
+            passphrases,
+            horizontal_rule(38),
+            breach_list,
+            horizontal_rule(38),
+            text("Note: The theme cannot be saved at the moment."),
         ]
         .spacing(20)
         .padding(20);
@@ -0,0 +1,278 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, progress_bar, row, scrollable, text,
+    text_input, Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::{fmt, path::PathBuf};
+
+use crate::{
+    backend::BackendKind,
+    gpg::KeyringKey,
+    notification::Notification,
+    service::{Channel, Progress, RequestId, StoreService},
+    share::{self, Event},
+    store::Entry,
+};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectKey(KeyOption),
+    RecipientChanged(String),
+    PathChanged(String),
+    Export,
+    Cancel,
+    Event((RequestId, Event)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+}
+
+/// Key of the keyring in the recipient picker
+#[derive(Debug, Clone)]
+pub struct KeyOption(KeyringKey);
+
+impl PartialEq for KeyOption {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.fingerprint == other.0.fingerprint
+    }
+}
+
+impl fmt::Display for KeyOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.user_ids.first() {
+            Some(user_id) => write!(f, "{user_id}"),
+            None => write!(f, "{}", self.0.fingerprint),
+        }
+    }
+}
+
+/// Encrypts a selection of entries for someone else into a single file
+pub struct Share {
+    entries: Vec<Entry>,
+    /// Pass ID of the selected entry or path of the selected folder
+    selection: String,
+    kind: BackendKind,
+    keys: Vec<KeyOption>,
+    recipient: String,
+    path: String,
+    progress: Option<(usize, usize)>,
+    request: Option<RequestId>,
+}
+
+impl Share {
+    pub fn new(
+        entries: Vec<Entry>,
+        selection: String,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let kind = entries
+            .first()
+            .map_or(BackendKind::Gpg, |entry| entry.store.backend.kind());
+        let name = selection.rsplit('/').next().unwrap_or_default();
+        let path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(format!("{name}.{}", share::extension(kind)));
+        let mut share = Self {
+            entries,
+            kind,
+            keys: Vec::new(),
+            recipient: String::new(),
+            path: path.to_string_lossy().into_owned(),
+            progress: None,
+            request: None,
+            selection,
+        };
+        // Recipients of age are public keys that are entered directly
+        let task = if kind == BackendKind::Gpg {
+            share.run(service, share::load_keys)
+        } else {
+            Task::none()
+        };
+        (share, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Export for...".to_owned()
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectKey(key) => {
+                self.recipient = key.0.fingerprint;
+                Action::None
+            }
+            Message::RecipientChanged(recipient) => {
+                self.recipient = recipient;
+                Action::None
+            }
+            Message::PathChanged(path) => {
+                self.path = path;
+                Action::None
+            }
+            Message::Export => {
+                let recipient = self.recipient.trim().to_owned();
+                let path = self.path.trim();
+                if recipient.is_empty() || path.is_empty() || self.progress.is_some() {
+                    return Action::None;
+                }
+                let entries = self.entries.clone();
+                let selection = self.selection.clone();
+                let file_path = PathBuf::from(path);
+                self.progress = Some((0, entries.len()));
+                Action::Run(self.run(service, move |progress| {
+                    share::export(entries, &selection, &recipient, file_path, progress)
+                }))
+            }
+            Message::Cancel => {
+                service.cancel(Channel::Audit);
+                Action::None
+            }
+            Message::Event((id, event)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.handle_event(event)
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) -> Action {
+        match event {
+            Event::Keys(result) => {
+                self.request = None;
+                match result {
+                    Ok(keys) => {
+                        self.keys = keys.into_iter().map(KeyOption).collect();
+                        Action::None
+                    }
+                    Err(e) => Action::Notify(Notification::warning(format!(
+                        "Keys could not be listed: {e}"
+                    ))),
+                }
+            }
+            Event::Progress { done, total } => {
+                self.progress = Some((done, total));
+                Action::None
+            }
+            Event::Exported(result) => {
+                self.request = None;
+                self.progress = None;
+                Action::Notify(match result {
+                    Ok((path, count)) => Notification::success(format!(
+                        "{count} entries were encrypted into {}",
+                        path.display()
+                    )),
+                    Err(e) => Notification::error(format!("Entries could not be exported: {e}")),
+                })
+            }
+        }
+    }
+
+    fn run(
+        &mut self,
+        service: &mut StoreService,
+        operation: impl FnOnce(&Progress<Event>) + Send + 'static,
+    ) -> Task<Message> {
+        let (id, task) = service.run(Channel::Audit, operation);
+        self.request = Some(id);
+        task.map(Message::Event)
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let entries = Column::with_children(
+            self.entries
+                .iter()
+                .map(|entry| text(&entry.pass_id).font(Font::MONOSPACE).size(12).into()),
+        )
+        .spacing(5)
+        .padding([0, 12]);
+
+        let warning = container(
+            column![
+                text(format!(
+                    "{} entries of {} are decrypted and encrypted for the recipient",
+                    self.entries.len(),
+                    self.selection
+                ))
+                .size(20),
+                text(
+                    "The recipient can read them without access to this store. \
+                     They can add them to their own store with Import."
+                ),
+            ]
+            .spacing(10),
+        )
+        .width(Fill)
+        .padding(10)
+        .style(container::bordered_box);
+
+        let recipient: Element<Message> = match self.kind {
+            BackendKind::Gpg => {
+                let selected = self
+                    .keys
+                    .iter()
+                    .find(|key| key.0.fingerprint == self.recipient.trim());
+                row![
+                    pick_list(self.keys.as_slice(), selected, Message::SelectKey)
+                        .placeholder("Key of the keyring")
+                        .width(Fill),
+                    text_input("Key ID, fingerprint or email", &self.recipient)
+                        .font(Font::MONOSPACE)
+                        .on_input(Message::RecipientChanged),
+                ]
+                .spacing(10)
+                .into()
+            }
+            BackendKind::Age => text_input("age1...", &self.recipient)
+                .font(Font::MONOSPACE)
+                .on_input(Message::RecipientChanged)
+                .into(),
+        };
+
+        let export: Element<Message> = match self.progress {
+            Some((done, total)) => row![
+                progress_bar(0.0..=total.max(1) as f32, done as f32).height(20),
+                text(format!("{done} / {total}")),
+                button("Cancel").on_press(Message::Cancel),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into(),
+            None => row![
+                text_input("Export to", &self.path)
+                    .on_input(Message::PathChanged)
+                    .on_submit(Message::Export),
+                button("Export").style(button::danger).on_press_maybe(
+                    (!self.recipient.trim().is_empty() && !self.path.trim().is_empty())
+                        .then_some(Message::Export),
+                ),
+            ]
+            .spacing(10)
+            .align_y(Center)
+            .into(),
+        };
+
+        column![
+            text("Export for..."),
+            horizontal_rule(38),
+            warning,
+            column![text("Recipient:"), recipient].spacing(10),
+            container(scrollable(entries).height(Fill))
+                .width(Fill)
+                .padding(10)
+                .style(container::rounded_box),
+            export,
+        ]
+        .spacing(20)
+        .padding(20)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
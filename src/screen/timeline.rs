@@ -0,0 +1,351 @@
+use iced::alignment::Vertical::Center;
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, row, scrollable, text, text_input,
+    Column,
+};
+use iced::{Element, Fill, Font, Subscription, Task};
+use std::sync::Arc;
+
+use crate::{
+    git::{self, Change, Signature, StoreCommit},
+    keyring,
+    notification::Notification,
+    service::{Channel, RequestId, StoreService},
+    store::StoreInfo,
+    timeline::{self, Filter},
+};
+
+use super::StoreOption;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectStore(StoreOption),
+    SelectAuthor(String),
+    FolderChanged(String),
+    SinceChanged(String),
+    UntilChanged(String),
+    ClearFilters,
+    ShowEntry(String),
+    Reload,
+    Loaded((RequestId, Result<Vec<StoreCommit>, String>)),
+}
+
+pub enum Action {
+    None,
+    Run(Task<Message>),
+    Notify(Notification),
+    /// Opens an entry of the store with the given index in the details
+    ShowEntry(usize, String),
+}
+
+/// Git log of a store that shows who changed which entries when
+pub struct Timeline {
+    stores: Vec<StoreOption>,
+    store: StoreOption,
+    /// Commits of the store, `None` while they are loaded or without a repository
+    commits: Option<Result<Vec<StoreCommit>, String>>,
+    authors: Vec<String>,
+    author: Option<String>,
+    folder: String,
+    /// Dates as they are typed, like `2024-12-31`
+    since: String,
+    until: String,
+    request: Option<RequestId>,
+}
+
+impl Timeline {
+    pub fn new(
+        stores: Vec<Arc<StoreInfo>>,
+        active_store: usize,
+        service: &mut StoreService,
+    ) -> (Self, Task<Message>) {
+        let stores: Vec<StoreOption> = stores.into_iter().map(StoreOption).collect();
+        let store = stores
+            .get(active_store)
+            .or(stores.first())
+            .cloned()
+            .expect("at least one store is configured");
+        let mut timeline = Self {
+            stores,
+            store,
+            commits: None,
+            authors: Vec::new(),
+            author: None,
+            folder: String::new(),
+            since: String::new(),
+            until: String::new(),
+            request: None,
+        };
+        let task = timeline.load(service);
+        (timeline, task)
+    }
+
+    pub fn title(&self) -> String {
+        "Partout - Timeline".to_owned()
+    }
+
+    #[cfg(test)]
+    pub fn shown(&self) -> Vec<&StoreCommit> {
+        let filter = self.filter();
+        match &self.commits {
+            Some(Ok(commits)) => commits
+                .iter()
+                .filter(|commit| filter.matches(commit))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message, service: &mut StoreService) -> Action {
+        match message {
+            Message::SelectStore(store) => {
+                self.store = store;
+                self.author = None;
+                Action::Run(self.load(service))
+            }
+            Message::SelectAuthor(author) => {
+                self.author = Some(author);
+                Action::None
+            }
+            Message::FolderChanged(folder) => {
+                self.folder = folder;
+                Action::None
+            }
+            Message::SinceChanged(since) => {
+                self.since = since;
+                Action::None
+            }
+            Message::UntilChanged(until) => {
+                self.until = until;
+                Action::None
+            }
+            Message::ClearFilters => {
+                self.author = None;
+                self.folder.clear();
+                self.since.clear();
+                self.until.clear();
+                Action::None
+            }
+            Message::ShowEntry(pass_id) => Action::ShowEntry(self.store.0.index, pass_id),
+            Message::Reload => Action::Run(self.load(service)),
+            Message::Loaded((id, result)) => {
+                if self.request != Some(id) {
+                    return Action::None;
+                }
+                self.request = None;
+                if let Ok(commits) = &result {
+                    self.authors = timeline::authors(commits);
+                }
+                let notification = result.as_ref().err().map(|e| {
+                    Action::Notify(Notification::error(format!(
+                        "Git history could not be read: {e}"
+                    )))
+                });
+                self.commits = Some(result);
+                notification.unwrap_or(Action::None)
+            }
+        }
+    }
+
+    /// Reads the git log of the selected store in the background
+    fn load(&mut self, service: &mut StoreService) -> Task<Message> {
+        self.commits = None;
+        self.authors.clear();
+        let dir = self.store.0.dir.clone();
+        if !git::is_repository(&dir) {
+            self.request = None;
+            return Task::none();
+        }
+        let (id, task) = service.run(Channel::Audit, move |progress| {
+            progress.report(git::log(&dir));
+        });
+        self.request = Some(id);
+        task.map(Message::Loaded)
+    }
+
+    fn filter(&self) -> Filter {
+        Filter {
+            author: self.author.clone(),
+            folder: self.folder.clone(),
+            since: keyring::parse_date(&self.since),
+            until: keyring::parse_date(&self.until),
+        }
+    }
+
+    pub fn view(&self) -> Element<Message> {
+        let mut header = row![].spacing(10).align_y(Center);
+        if self.stores.len() > 1 {
+            header = header.push(text("Store:")).push(
+                pick_list(
+                    self.stores.as_slice(),
+                    Some(&self.store),
+                    Message::SelectStore,
+                )
+                .width(150),
+            );
+        }
+        header = header
+            .push(
+                pick_list(
+                    self.authors.as_slice(),
+                    self.author.as_ref(),
+                    Message::SelectAuthor,
+                )
+                .placeholder("All authors")
+                .width(150),
+            )
+            .push(
+                text_input("Folder", &self.folder)
+                    .on_input(Message::FolderChanged)
+                    .width(150),
+            )
+            .push(date_input(
+                "From YYYY-MM-DD",
+                &self.since,
+                Message::SinceChanged,
+            ))
+            .push(date_input(
+                "To YYYY-MM-DD",
+                &self.until,
+                Message::UntilChanged,
+            ))
+            .push(
+                button("Clear filters")
+                    .style(button::secondary)
+                    .on_press(Message::ClearFilters),
+            )
+            .push(
+                button("Reload").on_press_maybe(self.request.is_none().then_some(Message::Reload)),
+            );
+
+        let content: Element<Message> = match &self.commits {
+            _ if self.request.is_some() => text("Reading git history...").into(),
+            None => text("The store is not a git repository").into(),
+            Some(Err(e)) => text(format!("Git history could not be read: {e}"))
+                .style(text::danger)
+                .into(),
+            Some(Ok(commits)) => self.commits_view(commits),
+        };
+
+        column![text("Timeline"), horizontal_rule(38), header, content]
+            .spacing(20)
+            .padding(20)
+            .into()
+    }
+
+    fn commits_view<'a>(&'a self, commits: &'a [StoreCommit]) -> Element<'a, Message> {
+        let filter = self.filter();
+        let extension = self.store.0.backend.extension();
+        let cards: Vec<Element<Message>> = commits
+            .iter()
+            .filter(|commit| filter.matches(commit))
+            .map(|commit| commit_card(commit, filter.changes(commit), extension))
+            .collect();
+        column![
+            text(format!("{} of {} commits", cards.len(), commits.len())),
+            scrollable(Column::with_children(cards).spacing(10).padding([0, 12])).height(Fill),
+        ]
+        .spacing(20)
+        .into()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::none()
+    }
+}
+
+/// Text input for a date that turns red when the date cannot be read
+fn date_input<'a>(
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: fn(String) -> Message,
+) -> Element<'a, Message> {
+    let valid = value.trim().is_empty() || keyring::parse_date(value).is_some();
+    text_input(placeholder, value)
+        .on_input(on_input)
+        .style(move |theme, status| {
+            let mut style = text_input::default(theme, status);
+            if !valid {
+                style.border.color = theme.extended_palette().danger.base.color;
+            }
+            style
+        })
+        .width(140)
+        .into()
+}
+
+fn commit_card<'a>(
+    commit: &'a StoreCommit,
+    changes: impl Iterator<Item = &'a Change>,
+    extension: &str,
+) -> Element<'a, Message> {
+    let signature_style = match commit.signature {
+        Signature::Good => text::success,
+        Signature::Bad | Signature::Revoked => text::danger,
+        Signature::Unsigned
+        | Signature::Untrusted
+        | Signature::Expired
+        | Signature::Unverifiable => text::secondary,
+    };
+    let signature = if commit.signer.is_empty() {
+        commit.signature.to_string()
+    } else {
+        format!("{} by {}", commit.signature, commit.signer)
+    };
+    let changes = changes.map(|change| change_row(change, extension));
+    container(
+        column![
+            row![
+                text(&commit.subject).width(Fill),
+                text(signature).size(12).style(signature_style),
+            ]
+            .spacing(10),
+            text(format!(
+                "{} by {} <{}> on {}",
+                commit.short_hash(),
+                commit.author,
+                commit.email,
+                keyring::format_time(commit.time)
+            ))
+            .size(12)
+            .style(text::secondary),
+            Column::with_children(changes).spacing(2),
+        ]
+        .spacing(5),
+    )
+    .width(Fill)
+    .padding(10)
+    .style(container::rounded_box)
+    .into()
+}
+
+/// Changed file, password files that still exist link to their entry
+fn change_row<'a>(change: &Change, extension: &str) -> Element<'a, Message> {
+    let (symbol, style): (_, fn(&iced::Theme) -> text::Style) = match change {
+        Change::Added(_) => ("+", text::success),
+        Change::Modified(_) => ("~", text::default),
+        Change::Deleted(_) => ("-", text::danger),
+        Change::Renamed(..) => (">", text::default),
+    };
+    let name = |path: &std::path::Path| {
+        timeline::pass_id(path, extension).unwrap_or_else(|| path.display().to_string())
+    };
+    let label = match change {
+        Change::Renamed(from, to) => format!("{} -> {}", name(from), name(to)),
+        change => name(change.path()),
+    };
+    let link = match change {
+        Change::Deleted(_) => None,
+        change => timeline::pass_id(change.path(), extension),
+    };
+    row![
+        text(symbol).font(Font::MONOSPACE).style(style),
+        button(text(label).font(Font::MONOSPACE).size(14))
+            .style(button::text)
+            .padding(0)
+            .on_press_maybe(link.map(Message::ShowEntry)),
+    ]
+    .spacing(8)
+    .align_y(Center)
+    .into()
+}
@@ -0,0 +1,381 @@
+use iced::futures::channel::{mpsc as futures_mpsc, oneshot};
+use iced::Task;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::PathBuf,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::SystemTime,
+};
+
+use crate::{
+    backend, breach,
+    clipboard::Clipboard,
+    clock::Clock,
+    environment::Environment,
+    file_info::{self, FileInfo},
+    git::Commit,
+    gpg::{self, KeyStore},
+    history, otp,
+    store::{Entry, PasswordFile},
+};
+
+/// Monotonically increasing ID that tags every request sent to the [`StoreService`]
+pub type RequestId = u64;
+
+/// Requests on the same channel supersede each other,
+/// a pending request is cancelled when a newer one is submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    Entry,
+    Otp,
+    Clipboard,
+    Keys,
+    /// Scans of the whole store
+    Audit,
+    /// Checks of the recipient keys of all stores
+    KeyCheck,
+    /// Long running operations on many password files
+    Batch,
+    /// Commands sent to gpg-agent
+    Agent,
+    /// Lookups of passwords in the breach list
+    Breach,
+    /// Old versions of the selected entry in the git history
+    History,
+    /// Metadata of the password file of the selected entry
+    FileInfo,
+}
+
+/// Operations on password files
+#[derive(Debug, Clone)]
+pub enum Request {
+    Decrypt(PasswordFile),
+    GenerateOtp(PasswordFile),
+    CopyId(String),
+    CopyPassword(PasswordFile),
+    CopyLogin(PasswordFile),
+    CopyOtp(PasswordFile),
+    /// Looks up the keys of the given recipients
+    ResolveKeys(Vec<String>),
+    /// Clears the passphrase cache of gpg-agent
+    ForgetPassphrases,
+    /// Looks up the SHA-1 hash of a password in the breach list at the given path,
+    /// the password was already decrypted for the details
+    CheckBreach(String, PathBuf),
+    /// Lists the commits that changed the password file of the entry
+    History(Entry),
+    /// Decrypts the password file of the entry as it was after the commit
+    DecryptVersion(Entry, Commit),
+    /// Writes an old version of the password file back as a new commit
+    RestoreVersion(Entry, Commit),
+    /// Reads what is known about the password file without decrypting it
+    FileInfo(Entry),
+}
+
+#[derive(Debug, Clone)]
+pub enum Response {
+    Decrypted(String),
+    Otp(String),
+    Copied,
+    Keys(Vec<gpg::KeyInfo>),
+    PassphrasesForgotten,
+    /// How often the password appeared in breaches
+    Breached(Option<u64>),
+    History(Vec<Commit>),
+    Restored,
+    FileInfo(FileInfo),
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// The request was superseded by a newer request on the same channel
+    Cancelled,
+    Failed(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cancelled => write!(f, "request was cancelled"),
+            Error::Failed(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<String> for Error {
+    fn from(e: String) -> Self {
+        Error::Failed(e)
+    }
+}
+
+pub type Outcome = (RequestId, Result<Response, Error>);
+
+/// Work that is executed on one of the workers
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Handle passed to long running operations to report their progress
+pub struct Progress<T> {
+    id: RequestId,
+    channel: Channel,
+    latest: Arc<Mutex<HashMap<Channel, RequestId>>>,
+    sender: futures_mpsc::UnboundedSender<T>,
+}
+
+impl<T> Progress<T> {
+    pub fn report(&self, value: T) {
+        // The receiver is dropped when the app is no longer interested
+        let _ = self.sender.unbounded_send(value);
+    }
+
+    /// Whether the operation was cancelled or superseded in the meantime
+    pub fn is_cancelled(&self) -> bool {
+        is_superseded(&self.latest, self.channel, self.id)
+    }
+
+    /// Handle of an operation that is run without the service and never cancelled
+    #[cfg(test)]
+    pub fn detached(sender: futures_mpsc::UnboundedSender<T>) -> Self {
+        Self {
+            id: 0,
+            channel: Channel::Batch,
+            latest: Arc::default(),
+            sender,
+        }
+    }
+
+    /// Cancels the operation as if a newer one was started on its channel
+    #[cfg(test)]
+    pub fn cancel(&self) {
+        self.latest
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .insert(self.channel, self.id + 1);
+    }
+}
+
+/// Workers owned by the app that serialise the operations on the password store
+///
+/// Requests are executed one after another on a dedicated thread,
+/// long operations on a second one so that they do not hold up requests.
+/// Requests that are superseded before they are picked up by the worker
+/// are answered with [`Error::Cancelled`] without being executed.
+pub struct StoreService {
+    next_id: RequestId,
+    latest: Arc<Mutex<HashMap<Channel, RequestId>>>,
+    sender: mpsc::Sender<Job>,
+    /// Worker of the long operations
+    operations: mpsc::Sender<Job>,
+    /// Channels that long operations were started on
+    operation_channels: HashSet<Channel>,
+    clipboard: Arc<dyn Clipboard>,
+    clock: Arc<dyn Clock>,
+    keys: Arc<dyn KeyStore>,
+}
+
+impl StoreService {
+    pub fn new(environment: &Environment) -> Self {
+        let latest = Arc::new(Mutex::new(HashMap::new()));
+
+        Self {
+            next_id: 0,
+            sender: spawn_worker("store-service"),
+            operations: spawn_worker("store-operations"),
+            operation_channels: HashSet::new(),
+            latest,
+            clipboard: Arc::clone(&environment.clipboard),
+            clock: Arc::clone(&environment.clock),
+            keys: Arc::clone(&environment.keys),
+        }
+    }
+
+    /// Submits a request and returns its ID together with a task that resolves
+    /// once the worker has processed the request
+    pub fn submit(&mut self, channel: Channel, request: Request) -> (RequestId, Task<Outcome>) {
+        let id = self.start(channel);
+
+        let (reply, receiver) = oneshot::channel();
+        let clipboard = Arc::clone(&self.clipboard);
+        let clock = Arc::clone(&self.clock);
+        let keys = Arc::clone(&self.keys);
+        let latest = Arc::clone(&self.latest);
+        let job: Job = Box::new(move || {
+            let result = if is_superseded(&latest, channel, id) {
+                Err(Error::Cancelled)
+            } else {
+                execute(request, clipboard.as_ref(), clock.as_ref(), keys.as_ref())
+            };
+            // The receiver is dropped when the app is no longer interested
+            let _ = reply.send(result);
+        });
+        let task = match self.sender.send(job) {
+            Ok(()) => Task::perform(
+                async move { receiver.await.unwrap_or(Err(Error::Cancelled)) },
+                move |result| (id, result),
+            ),
+            Err(_) => Task::done((
+                id,
+                Err(Error::Failed("store service is not running".to_owned())),
+            )),
+        };
+        (id, task)
+    }
+
+    /// Runs a long operation on its own worker and returns a task that yields its progress
+    ///
+    /// The operation is always started, even when it was superseded in the meantime,
+    /// so that it can report how far it got. It has to check [`Progress::is_cancelled`] by itself.
+    pub fn run<T, F>(&mut self, channel: Channel, operation: F) -> (RequestId, Task<(RequestId, T)>)
+    where
+        T: Send + 'static,
+        F: FnOnce(&Progress<T>) + Send + 'static,
+    {
+        let id = self.start(channel);
+
+        let (sender, receiver) = futures_mpsc::unbounded();
+        let progress = Progress {
+            id,
+            channel,
+            latest: Arc::clone(&self.latest),
+            sender,
+        };
+        self.operation_channels.insert(channel);
+        let task = match self.operations.send(Box::new(move || operation(&progress))) {
+            Ok(()) => Task::run(receiver, move |value| (id, value)),
+            Err(_) => Task::none(),
+        };
+        (id, task)
+    }
+
+    /// Returns a task that completes once all long operations started so far have ended
+    ///
+    /// Cancelled operations end at their next check, after they cleaned up after themselves.
+    pub fn operations_finished(&self) -> Task<()> {
+        let (done, receiver) = oneshot::channel();
+        // The worker runs the operations in order, this job runs after the earlier ones
+        let job: Job = Box::new(move || {
+            let _ = done.send(());
+        });
+        match self.operations.send(job) {
+            Ok(()) => Task::future(async move {
+                let _ = receiver.await;
+            }),
+            Err(_) => Task::done(()),
+        }
+    }
+
+    /// Keyring that recipients are looked up in
+    pub fn keys(&self) -> Arc<dyn KeyStore> {
+        Arc::clone(&self.keys)
+    }
+
+    /// Current time of the clock that one-time passwords are generated with
+    pub fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    /// Cancels all pending requests on a channel
+    pub fn cancel(&mut self, channel: Channel) {
+        self.start(channel);
+    }
+
+    /// Cancels the long operations on all channels, they stop at their next check
+    pub fn cancel_operations(&mut self) {
+        for channel in self.operation_channels.clone() {
+            self.start(channel);
+        }
+    }
+
+    /// Reserves a new request ID that supersedes all earlier requests on the channel
+    fn start(&mut self, channel: Channel) -> RequestId {
+        self.next_id += 1;
+        self.latest
+            .lock()
+            .expect("another thread holding the lock panicked")
+            .insert(channel, self.next_id);
+        self.next_id
+    }
+}
+
+fn is_superseded(
+    latest: &Mutex<HashMap<Channel, RequestId>>,
+    channel: Channel,
+    id: RequestId,
+) -> bool {
+    latest
+        .lock()
+        .expect("another thread holding the lock panicked")
+        .get(&channel)
+        .is_some_and(|&latest_id| latest_id != id)
+}
+
+fn spawn_worker(name: &str) -> mpsc::Sender<Job> {
+    let (sender, receiver) = mpsc::channel();
+    thread::Builder::new()
+        .name(name.to_owned())
+        .spawn(move || run_worker(receiver))
+        .expect("failed to spawn store service thread");
+    sender
+}
+
+fn run_worker(receiver: mpsc::Receiver<Job>) {
+    while let Ok(job) = receiver.recv() {
+        job();
+    }
+}
+
+fn execute(
+    request: Request,
+    clipboard: &dyn Clipboard,
+    clock: &dyn Clock,
+    keys: &dyn KeyStore,
+) -> Result<Response, Error> {
+    match request {
+        Request::Decrypt(file) => decrypt(&file).map(Response::Decrypted),
+        Request::GenerateOtp(file) => {
+            otp::generate(&decrypt(&file)?, clock.now()).map(Response::Otp)
+        }
+        Request::CopyId(pass_id) => clipboard.copy(&pass_id, false).map(|_| Response::Copied),
+        Request::CopyPassword(file) => {
+            let contents = decrypt(&file)?;
+            let password = contents
+                .lines()
+                .next()
+                .ok_or_else(|| "no password found".to_owned())?;
+            clipboard.copy(password, true).map(|_| Response::Copied)
+        }
+        Request::CopyLogin(file) => {
+            let contents = decrypt(&file)?;
+            let login = contents
+                .lines()
+                .nth(1)
+                .ok_or_else(|| "no login found".to_owned())?;
+            clipboard.copy(login, true).map(|_| Response::Copied)
+        }
+        Request::CopyOtp(file) => {
+            let otp = otp::generate(&decrypt(&file)?, clock.now())?;
+            clipboard.copy(&otp, false).map(|_| Response::Copied)
+        }
+        Request::ResolveKeys(recipients) => keys.resolve_keys(&recipients).map(Response::Keys),
+        Request::ForgetPassphrases => {
+            gpg::forget_passphrases().map(|_| Response::PassphrasesForgotten)
+        }
+        Request::CheckBreach(hash, breach_list) => {
+            breach::check(&breach_list, &hash).map(Response::Breached)
+        }
+        Request::History(entry) => history::commits(&entry).map(Response::History),
+        Request::DecryptVersion(entry, commit) => {
+            history::decrypt(&entry, &commit).map(Response::Decrypted)
+        }
+        Request::RestoreVersion(entry, commit) => {
+            history::restore(&entry, &commit).map(|()| Response::Restored)
+        }
+        Request::FileInfo(entry) => file_info::read(&entry).map(Response::FileInfo),
+    }
+    .map_err(Error::from)
+}
+
+fn decrypt(file: &PasswordFile) -> Result<String, String> {
+    backend::decrypt_text(file.backend.as_ref(), &file.path)
+}
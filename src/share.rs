@@ -0,0 +1,157 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{Backend, BackendKind},
+    gpg::{self, KeyringKey},
+    service::Progress,
+    store::Entry,
+};
+
+/// Version of the bundle format that is written
+const VERSION: u32 = 1;
+
+/// Password files that are shared with someone else, encrypted as a whole to their key
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    partout_bundle: u32,
+    entries: Vec<BundleEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleEntry {
+    /// ID relative to the folder that contains the shared entries
+    pass_id: String,
+    /// Base64 encoded contents of the password file
+    contents: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Keys of the keyring that the bundle can be encrypted for
+    Keys(Result<Vec<KeyringKey>, String>),
+    Progress {
+        done: usize,
+        total: usize,
+    },
+    /// Path of the written bundle and the number of entries in it
+    Exported(Result<(PathBuf, usize), String>),
+}
+
+/// Extension of bundles that are encrypted with the given backend
+pub fn extension(kind: BackendKind) -> &'static str {
+    match kind {
+        BackendKind::Gpg => "asc",
+        BackendKind::Age => "age",
+    }
+}
+
+/// Lists the keys of the keyring that can be encrypted for
+pub fn load_keys(progress: &Progress<Event>) {
+    let keys = gpg::keyring()
+        .map(|keys| {
+            keys.into_iter()
+                .filter(|key| key.can_encrypt && key.problem.is_none())
+                .collect()
+        })
+        .map_err(|e| e.to_string());
+    progress.report(Event::Keys(keys));
+}
+
+/// Decrypts the entries and writes them into a bundle that only the recipient can read,
+/// the IDs in the bundle are relative to the folder that contains `selection`
+pub fn export(
+    entries: Vec<Entry>,
+    selection: &str,
+    recipient: &str,
+    file_path: PathBuf,
+    progress: &Progress<Event>,
+) {
+    let result = write_bundle(&entries, selection, recipient, &file_path, progress)
+        .map(|count| (file_path, count));
+    progress.report(Event::Exported(result));
+}
+
+fn write_bundle(
+    entries: &[Entry],
+    selection: &str,
+    recipient: &str,
+    file_path: &Path,
+    progress: &Progress<Event>,
+) -> Result<usize, String> {
+    let first = entries
+        .first()
+        .ok_or_else(|| "nothing selected".to_owned())?;
+    let mut encrypter = first
+        .store
+        .backend
+        .armored_encrypter(&[recipient.to_owned()])?;
+    let base = selection.rsplit_once('/').map(|(base, _)| base);
+
+    let total = entries.len();
+    let mut bundle = Bundle {
+        partout_bundle: VERSION,
+        entries: Vec::with_capacity(total),
+    };
+    for (done, entry) in entries.iter().enumerate() {
+        if progress.is_cancelled() {
+            return Err("export was cancelled".to_owned());
+        }
+        progress.report(Event::Progress { done, total });
+        let plaintext = entry
+            .store
+            .backend
+            .decrypt(&entry.file_path())
+            .map_err(|e| format!("{}: {e}", entry.pass_id))?;
+        let pass_id = base
+            .and_then(|base| entry.pass_id.strip_prefix(base))
+            .map_or(entry.pass_id.as_str(), |pass_id| {
+                pass_id.trim_start_matches('/')
+            });
+        bundle.entries.push(BundleEntry {
+            pass_id: pass_id.to_owned(),
+            contents: BASE64.encode(plaintext),
+        });
+    }
+
+    let json = serde_json::to_vec(&bundle).map_err(|e| e.to_string())?;
+    let ciphertext = encrypter.encrypt(&json)?;
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(file_path)
+        .and_then(|mut file| file.write_all(&ciphertext))
+        .map_err(|e| e.to_string())?;
+    Ok(total)
+}
+
+/// Decrypts a bundle with the keys of a store and returns the IDs and contents of its entries
+pub fn read(backend: &dyn Backend, file_path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let json = backend.decrypt(file_path)?;
+    let bundle: Bundle =
+        serde_json::from_slice(&json).map_err(|_| "file is not a shared bundle".to_owned())?;
+    if bundle.partout_bundle > VERSION {
+        return Err(format!(
+            "bundle version {} is not supported",
+            bundle.partout_bundle
+        ));
+    }
+    bundle
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let contents = BASE64
+                .decode(&entry.contents)
+                .map_err(|e| format!("{}: {e}", entry.pass_id))?;
+            Ok((entry.pass_id, contents))
+        })
+        .collect()
+}
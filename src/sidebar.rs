@@ -1,13 +1,44 @@
 use iced::alignment::Vertical::Center;
-use iced::widget::{button, column, container, horizontal_space, row, text, vertical_space};
-use iced::{Element, Subscription, Task};
+use iced::widget::{
+    button, column, container, horizontal_space, pick_list, row, text, vertical_space,
+};
+use iced::{Element, Fill, Subscription, Task};
+use std::fmt;
 
 use crate::icon;
 
+/// Option of the store switcher
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreChoice {
+    Store(usize, String),
+    All,
+}
+
+impl fmt::Display for StoreChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreChoice::Store(_, name) => write!(f, "{name}"),
+            StoreChoice::All => write!(f, "All stores"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     ShowSettings,
     ShowDashboard,
+    ShowRecipients,
+    ShowAccess,
+    ShowRotation,
+    ShowKeys,
+    ShowImport,
+    ShowOtpExport,
+    ShowHealth,
+    ShowTimeline,
+    ToggleNotifications,
+    SelectStore(StoreChoice),
+    ForgetPassphrases,
+    Lock,
     Quit,
 }
 
@@ -15,38 +46,171 @@ pub enum Action {
     None,
     ShowSettings,
     ShowDashboard,
+    ShowRecipients,
+    ShowAccess,
+    ShowRotation,
+    ShowKeys,
+    ShowImport,
+    ShowOtpExport,
+    ShowHealth,
+    ShowTimeline,
+    ToggleNotifications,
+    SwitchStore(usize),
+    ShowAllStores,
+    ForgetPassphrases,
+    Lock,
     Quit,
 }
 
-pub struct Sidebar {}
+pub struct Sidebar {
+    stores: Vec<StoreChoice>,
+    active_store: Option<StoreChoice>,
+}
 
 impl Sidebar {
-    pub fn new() -> (Self, Task<Message>) {
-        (Self {}, Task::none())
+    /// Creates the sidebar with the names of all stores,
+    /// no active store means that all stores are shown together
+    pub fn new(stores: Vec<String>, active_store: Option<usize>) -> (Self, Task<Message>) {
+        let mut sidebar = Self {
+            stores: Vec::new(),
+            active_store: None,
+        };
+        sidebar.set_stores(stores, active_store);
+        (sidebar, Task::none())
+    }
+
+    /// Updates the store names shown in the store switcher
+    pub fn set_stores(&mut self, stores: Vec<String>, active_store: Option<usize>) {
+        let show_all = stores.len() > 1;
+        self.stores = stores
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| StoreChoice::Store(index, name))
+            .collect();
+        if show_all {
+            self.stores.push(StoreChoice::All);
+        }
+        self.active_store = match active_store {
+            Some(index) => self.stores.get(index).cloned(),
+            None if show_all => Some(StoreChoice::All),
+            None => self.stores.first().cloned(),
+        };
     }
 
     pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::ShowDashboard => Action::ShowDashboard,
             Message::ShowSettings => Action::ShowSettings,
+            Message::ShowRecipients => Action::ShowRecipients,
+            Message::ShowAccess => Action::ShowAccess,
+            Message::ShowRotation => Action::ShowRotation,
+            Message::ShowKeys => Action::ShowKeys,
+            Message::ShowImport => Action::ShowImport,
+            Message::ShowOtpExport => Action::ShowOtpExport,
+            Message::ShowHealth => Action::ShowHealth,
+            Message::ShowTimeline => Action::ShowTimeline,
+            Message::ToggleNotifications => Action::ToggleNotifications,
+            Message::SelectStore(choice) => {
+                if self.active_store.as_ref() == Some(&choice) {
+                    return Action::None;
+                }
+                self.active_store = Some(choice.clone());
+                match choice {
+                    StoreChoice::Store(index, _) => Action::SwitchStore(index),
+                    StoreChoice::All => Action::ShowAllStores,
+                }
+            }
+            Message::ForgetPassphrases => Action::ForgetPassphrases,
+            Message::Lock => Action::Lock,
             Message::Quit => Action::Quit,
         }
     }
 
     pub fn view(&self) -> Element<Message> {
+        let store_switcher = column![
+            text("Store"),
+            pick_list(
+                self.stores.as_slice(),
+                self.active_store.as_ref(),
+                Message::SelectStore
+            )
+            .width(Fill),
+        ]
+        .spacing(5)
+        .width(105);
+
         column![
+            store_switcher,
             button(
                 row!["Dashboard", horizontal_space(), icon::book()]
                     .width(105)
                     .align_y(Center),
             )
             .on_press(Message::ShowDashboard),
+            button(
+                row!["Recipients", horizontal_space(), icon::key()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowRecipients),
+            button(
+                row!["Access", horizontal_space(), icon::login()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowAccess),
+            button(
+                row!["Health", horizontal_space(), icon::alert()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowHealth),
+            button(
+                row!["Timeline", horizontal_space(), icon::search()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowTimeline),
+            button(
+                row!["Rotate key", horizontal_space(), icon::refresh()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowRotation),
+            button(
+                row!["Keys", horizontal_space(), icon::file()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowKeys),
+            button(
+                row!["Import", horizontal_space(), icon::document()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowImport),
+            button(
+                row!["Export OTP", horizontal_space(), icon::chronometer()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ShowOtpExport),
             button(
                 row!["Settings", horizontal_space(), icon::settings()]
                     .width(105)
                     .align_y(Center),
             )
             .on_press(Message::ShowSettings),
+            button(
+                row!["History", horizontal_space(), icon::info()]
+                    .width(105)
+                    .align_y(Center),
+            )
+            .on_press(Message::ToggleNotifications),
+            button(text("Forget passphrases").width(105))
+                .style(button::secondary)
+                .on_press(Message::ForgetPassphrases),
+            button("Lock").on_press(Message::Lock),
             button("Quit").on_press(Message::Quit),
             vertical_space(),
             container(text("alpha"))
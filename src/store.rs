@@ -0,0 +1,147 @@
+use passepartout::{PasswordInfo, PasswordStore};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::backend::Backend;
+
+/// Reads all password entries of the store in the given directory
+pub fn load(store_dir: PathBuf, backend: &dyn Backend) -> PasswordStore {
+    let mut passwords = password_infos(&store_dir, backend.extension());
+    passwords.sort_by_key(|element| element.pass_id.clone());
+    PasswordStore {
+        store_dir,
+        passwords,
+    }
+}
+
+/// Reads the password entries below a directory, their IDs are relative to it
+pub fn password_infos(dir: &Path, extension: &str) -> Vec<PasswordInfo> {
+    password_files(dir, extension, None)
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let pass_id = path.strip_prefix(dir).ok()?.with_extension("");
+            Some(PasswordInfo::new(
+                pass_id.to_string_lossy().into(),
+                metadata,
+            ))
+        })
+        .collect()
+}
+
+/// Lists the password files below a directory, hidden files are ignored
+///
+/// Subfolders that contain a file named `stop_at` are skipped.
+pub fn password_files(dir: &Path, extension: &str, stop_at: Option<&str>) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(dir, extension, stop_at, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_files(dir: &Path, extension: &str, stop_at: Option<&str>, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            if !stop_at.is_some_and(|name| path.join(name).is_file()) {
+                collect_files(&path, extension, stop_at, files);
+            }
+        } else if has_extension(&path, extension) {
+            files.push(path);
+        }
+    }
+}
+
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Returns the path of the password file for a pass ID
+pub fn entry_path(store_dir: &Path, pass_id: &str, extension: &str) -> PathBuf {
+    store_dir.join(format!("{pass_id}.{extension}"))
+}
+
+/// Name and location of a loaded password store
+#[derive(Debug)]
+pub struct StoreInfo {
+    /// Position of the store in the configuration
+    pub index: usize,
+    pub name: String,
+    pub dir: PathBuf,
+    pub backend: Arc<dyn Backend>,
+}
+
+/// Password file together with the backend it is encrypted with
+#[derive(Debug, Clone)]
+pub struct PasswordFile {
+    pub path: PathBuf,
+    pub backend: Arc<dyn Backend>,
+}
+
+/// A password entry together with the store it belongs to
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub pass_id: String,
+    pub store: Arc<StoreInfo>,
+}
+
+impl Entry {
+    pub fn new(info: PasswordInfo, store: Arc<StoreInfo>) -> Self {
+        Self {
+            pass_id: info.pass_id,
+            store,
+        }
+    }
+
+    /// Returns the path of the password file
+    pub fn file_path(&self) -> PathBuf {
+        entry_path(
+            &self.store.dir,
+            &self.pass_id,
+            self.store.backend.extension(),
+        )
+    }
+
+    pub fn file(&self) -> PasswordFile {
+        PasswordFile {
+            path: self.file_path(),
+            backend: Arc::clone(&self.store.backend),
+        }
+    }
+}
+
+/// Collects the entries of several stores, sorted by their pass ID
+pub fn entries<'a>(
+    stores: impl IntoIterator<Item = (Arc<StoreInfo>, &'a PasswordStore)>,
+) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = stores
+        .into_iter()
+        .flat_map(|(info, store)| {
+            store
+                .passwords
+                .iter()
+                .map(move |password| Entry::new(password.clone(), Arc::clone(&info)))
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        a.pass_id
+            .cmp(&b.pass_id)
+            .then(a.store.index.cmp(&b.store.index))
+    });
+    entries
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+
+/// Passwords that are tried first by every attacker, compared case-insensitively
+const COMMON: &[&str] = &[
+    "123456",
+    "123456789",
+    "12345678",
+    "1234567890",
+    "qwerty",
+    "qwertz",
+    "azerty",
+    "password",
+    "passwort",
+    "password1",
+    "letmein",
+    "welcome",
+    "admin",
+    "iloveyou",
+    "monkey",
+    "dragon",
+    "football",
+    "abc123",
+    "111111",
+    "000000",
+    "changeme",
+    "secret",
+];
+
+/// Rough class of how long guessing a password takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Strength {
+    VeryWeak,
+    Weak,
+    Fair,
+    Strong,
+    VeryStrong,
+}
+
+impl Strength {
+    /// Classifies an entropy estimate in bits
+    pub fn of(bits: f64) -> Self {
+        match bits {
+            bits if bits < 28.0 => Strength::VeryWeak,
+            bits if bits < 50.0 => Strength::Weak,
+            bits if bits < 70.0 => Strength::Fair,
+            bits if bits < 100.0 => Strength::Strong,
+            _ => Strength::VeryStrong,
+        }
+    }
+
+    /// Whether the password should be replaced
+    pub fn is_weak(self) -> bool {
+        self <= Strength::Weak
+    }
+
+    /// How long an offline attack on the password would take
+    pub fn crack_time(self) -> &'static str {
+        match self {
+            Strength::VeryWeak => "seconds",
+            Strength::Weak => "hours to months",
+            Strength::Fair => "years",
+            Strength::Strong => "centuries",
+            Strength::VeryStrong => "longer than the age of the universe",
+        }
+    }
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strength::VeryWeak => write!(f, "very weak"),
+            Strength::Weak => write!(f, "weak"),
+            Strength::Fair => write!(f, "fair"),
+            Strength::Strong => write!(f, "strong"),
+            Strength::VeryStrong => write!(f, "very strong"),
+        }
+    }
+}
+
+/// Kind of a character in a password, to tell similar looking characters apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Letter,
+    Digit,
+    Symbol,
+    /// Spaces and other characters that are hard to tell apart
+    Whitespace,
+}
+
+impl CharClass {
+    pub fn of(c: char) -> Self {
+        match c {
+            c if c.is_ascii_digit() => CharClass::Digit,
+            c if c.is_whitespace() => CharClass::Whitespace,
+            c if c.is_alphabetic() => CharClass::Letter,
+            _ => CharClass::Symbol,
+        }
+    }
+}
+
+/// Estimates the entropy of a password in bits
+///
+/// Every character adds the bits of the character classes that the password uses,
+/// characters that repeat or continue a sequence like `abc` or `321` add only one bit.
+pub fn entropy(password: &str) -> f64 {
+    if COMMON
+        .iter()
+        .any(|common| common.eq_ignore_ascii_case(password))
+    {
+        return 0.0;
+    }
+    let pool = pool_size(password);
+    if pool == 0 {
+        return 0.0;
+    }
+    let bits_per_char = f64::from(pool).log2();
+    let mut bits = 0.0;
+    let mut previous: Option<(char, i64)> = None;
+    for c in password.chars() {
+        let step = previous.map(|(previous, _)| c as i64 - previous as i64);
+        let predictable = match (step, previous) {
+            (Some(0), _) => true,
+            // Only the third character of a sequence is predictable
+            (Some(step), Some((_, previous_step))) => step.abs() == 1 && step == previous_step,
+            _ => false,
+        };
+        bits += if predictable { 1.0 } else { bits_per_char };
+        previous = Some((c, step.unwrap_or_default()));
+    }
+    bits
+}
+
+/// Number of characters an attacker has to try per position
+fn pool_size(password: &str) -> u32 {
+    let has = |predicate: fn(&char) -> bool| password.chars().any(|c| predicate(&c));
+    let mut pool = 0;
+    if has(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if has(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if has(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_bits(password: &str, expected: f64) {
+        let bits = entropy(password);
+        assert!(
+            (bits - expected).abs() < 1e-9,
+            "{password}: {bits} bits instead of {expected}"
+        );
+    }
+
+    #[test]
+    fn common_passwords_have_no_entropy() {
+        assert_bits("password", 0.0);
+        assert_bits("PassWord", 0.0);
+        assert_bits("123456", 0.0);
+        assert!(entropy("password2") > 0.0);
+        assert_bits("", 0.0);
+    }
+
+    #[test]
+    fn repeated_characters_and_sequences_add_one_bit() {
+        let lowercase = 26_f64.log2();
+        assert_bits("aaa", lowercase + 2.0);
+        assert_bits("abc", 2.0 * lowercase + 1.0);
+        assert_bits("cba", 2.0 * lowercase + 1.0);
+        assert_bits("abcd", 2.0 * lowercase + 2.0);
+        // Steps in different directions or of different sizes are not a sequence
+        assert_bits("aba", 3.0 * lowercase);
+        assert_bits("ace", 3.0 * lowercase);
+
+        let digits = 10_f64.log2();
+        assert_bits("3210", 2.0 * digits + 2.0);
+        assert_bits("7771", 2.0 * digits + 2.0);
+    }
+
+    #[test]
+    fn the_pool_grows_with_every_character_class() {
+        assert_eq!(pool_size("abc"), 26);
+        assert_eq!(pool_size("aBc"), 52);
+        assert_eq!(pool_size("aB3"), 62);
+        assert_eq!(pool_size("aB3!"), 95);
+        assert_eq!(pool_size("a b"), 59);
+        // Characters outside of ASCII add a fixed guess of the size of their script
+        assert_eq!(pool_size("é"), 100);
+        assert_eq!(pool_size("aé"), 126);
+        assert_eq!(pool_size("пароль"), 100);
+        assert_bits("日本", 2.0 * 100_f64.log2());
+    }
+
+    #[test]
+    fn entropy_is_classified_at_the_class_boundaries() {
+        assert_eq!(Strength::of(0.0), Strength::VeryWeak);
+        assert_eq!(Strength::of(27.9), Strength::VeryWeak);
+        assert_eq!(Strength::of(28.0), Strength::Weak);
+        assert_eq!(Strength::of(49.9), Strength::Weak);
+        assert_eq!(Strength::of(50.0), Strength::Fair);
+        assert_eq!(Strength::of(70.0), Strength::Strong);
+        assert_eq!(Strength::of(99.9), Strength::Strong);
+        assert_eq!(Strength::of(100.0), Strength::VeryStrong);
+        assert!(Strength::Weak.is_weak());
+        assert!(!Strength::Fair.is_weak());
+    }
+
+    #[test]
+    fn characters_are_classified_beyond_ascii() {
+        assert_eq!(CharClass::of('l'), CharClass::Letter);
+        assert_eq!(CharClass::of('ß'), CharClass::Letter);
+        assert_eq!(CharClass::of('Ж'), CharClass::Letter);
+        assert_eq!(CharClass::of('1'), CharClass::Digit);
+        // Digits of other scripts do not look like ASCII digits
+        assert_eq!(CharClass::of('٣'), CharClass::Symbol);
+        assert_eq!(CharClass::of('|'), CharClass::Symbol);
+        assert_eq!(CharClass::of(' '), CharClass::Whitespace);
+        assert_eq!(CharClass::of('\u{a0}'), CharClass::Whitespace);
+        assert_eq!(CharClass::of('\t'), CharClass::Whitespace);
+    }
+}
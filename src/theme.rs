@@ -1,2 +1,19 @@
+use iced::{color, Color};
+
 pub const TEXT_SIZE: f32 = 12.0;
 pub const ICON_SIZE: f32 = 16.0;
+
+/// Colours of the badges that tell password stores apart
+const STORE_COLORS: [Color; 6] = [
+    color!(0x1c71d8),
+    color!(0x2ec27e),
+    color!(0xe66100),
+    color!(0x9141ac),
+    color!(0xc01c28),
+    color!(0x865e3c),
+];
+
+/// Returns the badge colour of the store at the given position in the configuration
+pub fn store_color(index: usize) -> Color {
+    STORE_COLORS[index % STORE_COLORS.len()]
+}
@@ -0,0 +1,59 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    git::{Change, StoreCommit},
+    store,
+};
+
+/// Narrows the timeline of a store down to the commits of interest
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub author: Option<String>,
+    /// Folder relative to the store directory, empty for the whole store
+    pub folder: String,
+    pub since: Option<SystemTime>,
+    /// Commits up to the end of this day are included
+    pub until: Option<SystemTime>,
+}
+
+impl Filter {
+    pub fn matches(&self, commit: &StoreCommit) -> bool {
+        self.author
+            .as_ref()
+            .is_none_or(|author| *author == commit.author)
+            && self.since.is_none_or(|since| commit.time >= since)
+            && self
+                .until
+                .is_none_or(|until| commit.time < until + Duration::from_secs(86_400))
+            && (self.folder.is_empty() || self.changes(commit).next().is_some())
+    }
+
+    /// Changes of the commit inside the folder
+    pub fn changes<'a>(&self, commit: &'a StoreCommit) -> impl Iterator<Item = &'a Change> + 'a {
+        let folder = PathBuf::from(self.folder.trim_matches('/'));
+        commit.changes.iter().filter(move |change| match change {
+            Change::Renamed(from, to) => from.starts_with(&folder) || to.starts_with(&folder),
+            change => change.path().starts_with(&folder),
+        })
+    }
+}
+
+/// Authors of the commits in the order they last committed
+pub fn authors(commits: &[StoreCommit]) -> Vec<String> {
+    let mut authors: Vec<String> = Vec::new();
+    for commit in commits {
+        if !authors.contains(&commit.author) {
+            authors.push(commit.author.clone());
+        }
+    }
+    authors
+}
+
+/// Pass ID of a changed password file, `None` for other files like `.gpg-id`
+pub fn pass_id(path: &Path, extension: &str) -> Option<String> {
+    store::has_extension(path, extension)
+        .then(|| path.with_extension("").to_string_lossy().into_owned())
+}
@@ -0,0 +1,267 @@
+use iced::futures::{channel::mpsc, SinkExt, StreamExt};
+use iced::{stream, Subscription};
+use notify::{RecursiveMode, Watcher};
+use passepartout::PasswordInfo;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc as std_mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::store;
+
+/// Time without further events after which a batch of changes is reported
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Changed(Vec<PathBuf>),
+    Failed(String),
+}
+
+/// Watches the password store directory recursively and reports debounced batches of changed paths
+pub fn watch(store_dir: PathBuf) -> Subscription<Event> {
+    Subscription::run_with_id(
+        store_dir.clone(),
+        stream::channel(10, move |mut output| async move {
+            let (raw_sender, raw_receiver) = std_mpsc::channel();
+            let mut watcher = match notify::recommended_watcher(raw_sender) {
+                Ok(watcher) => watcher,
+                Err(e) => {
+                    let _ = output.send(Event::Failed(e.to_string())).await;
+                    return;
+                }
+            };
+            if let Err(e) = watcher.watch(&store_dir, RecursiveMode::Recursive) {
+                let _ = output.send(Event::Failed(e.to_string())).await;
+                return;
+            }
+
+            let (batch_sender, mut batch_receiver) = mpsc::unbounded();
+            thread::spawn(move || debounce(raw_receiver, batch_sender));
+
+            // The watcher is kept alive as long as this subscription is running
+            while let Some(paths) = batch_receiver.next().await {
+                if output.send(Event::Changed(paths)).await.is_err() {
+                    break;
+                }
+            }
+            drop(watcher);
+        }),
+    )
+}
+
+fn debounce(
+    receiver: std_mpsc::Receiver<notify::Result<notify::Event>>,
+    sender: mpsc::UnboundedSender<Vec<PathBuf>>,
+) {
+    let mut pending = BTreeSet::new();
+    loop {
+        let event = if pending.is_empty() {
+            match receiver.recv() {
+                Ok(event) => event,
+                Err(_) => return,
+            }
+        } else {
+            match receiver.recv_timeout(DEBOUNCE_INTERVAL) {
+                Ok(event) => event,
+                Err(std_mpsc::RecvTimeoutError::Timeout) => {
+                    let paths = std::mem::take(&mut pending).into_iter().collect();
+                    if sender.unbounded_send(paths).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                Err(std_mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        };
+
+        if let Ok(event) = event {
+            if event.kind.is_access() {
+                continue;
+            }
+            pending.extend(
+                event
+                    .paths
+                    .into_iter()
+                    .filter(|path| !path.components().any(|c| c.as_os_str() == ".git")),
+            );
+        }
+    }
+}
+
+/// Applies a batch of changed paths to the list of password entries
+///
+/// Returns the IDs of all entries that were added, modified or removed.
+pub fn apply_changes(
+    passwords: &mut Vec<PasswordInfo>,
+    store_dir: &Path,
+    extension: &str,
+    paths: &[PathBuf],
+) -> Vec<String> {
+    let mut changed = Vec::new();
+
+    for path in paths {
+        let Ok(relative) = path.strip_prefix(store_dir) else {
+            continue;
+        };
+        // Hidden files and everything in hidden folders are not listed when the store is loaded
+        if relative.ancestors().any(store::is_hidden) {
+            continue;
+        }
+        if relative.as_os_str().is_empty() {
+            // The store directory itself changed
+            changed.extend(passwords.drain(..).map(|entry| entry.pass_id));
+            passwords.extend(store::password_infos(store_dir, extension));
+            changed.extend(passwords.iter().map(|entry| entry.pass_id.clone()));
+            continue;
+        }
+        let is_entry = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(extension));
+
+        if is_entry {
+            let pass_id: String = relative.with_extension("").to_string_lossy().into();
+            passwords.retain(|entry| entry.pass_id != pass_id);
+            if let Ok(metadata) = path.metadata() {
+                if metadata.is_file() {
+                    passwords.push(PasswordInfo::new(pass_id.clone(), metadata));
+                }
+            }
+            changed.push(pass_id);
+        } else if path.is_dir() || !path.exists() {
+            // A folder was created, moved or removed, its subtree is read again
+            let prefix = format!("{}/", relative.to_string_lossy());
+            passwords.retain(|entry| {
+                let inside = entry.pass_id.starts_with(&prefix);
+                if inside {
+                    changed.push(entry.pass_id.clone());
+                }
+                !inside
+            });
+            if path.is_dir() {
+                for entry in store::password_infos(path, extension) {
+                    let pass_id = format!("{prefix}{}", entry.pass_id);
+                    changed.push(pass_id.clone());
+                    passwords.push(PasswordInfo::new(pass_id, entry.metadata));
+                }
+            }
+        }
+    }
+
+    if !changed.is_empty() {
+        passwords.sort_by(|a, b| a.pass_id.cmp(&b.pass_id));
+        passwords.dedup_by(|a, b| a.pass_id == b.pass_id);
+        changed.sort();
+        changed.dedup();
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Store with the given password files and their entries as they are loaded
+    fn store(files: &[&str]) -> (tempfile::TempDir, Vec<PasswordInfo>) {
+        let store_dir = tempfile::tempdir().expect("temporary directory can be created");
+        for file in files {
+            write(store_dir.path(), file, "");
+        }
+        let passwords = store::password_infos(store_dir.path(), "gpg");
+        (store_dir, passwords)
+    }
+
+    fn write(store_dir: &Path, file: &str, contents: &str) -> PathBuf {
+        let path = store_dir.join(file);
+        fs::create_dir_all(path.parent().expect("file is in the store"))
+            .expect("folder can be created");
+        fs::write(&path, contents).expect("file can be written");
+        path
+    }
+
+    fn pass_ids(passwords: &[PasswordInfo]) -> Vec<&str> {
+        passwords
+            .iter()
+            .map(|entry| entry.pass_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn created_modified_and_deleted_files_are_applied() {
+        let (store_dir, mut passwords) = store(&["bank.gpg", "web/mail.gpg"]);
+        let store_dir = store_dir.path();
+
+        let created = write(store_dir, "web/forum.GPG", "");
+        let modified = write(store_dir, "bank.gpg", "new contents");
+        let deleted = store_dir.join("web/mail.gpg");
+        fs::remove_file(&deleted).expect("file can be removed");
+        // Events of the same file are reported once per batch
+        let changes = [created, modified.clone(), deleted, modified];
+
+        let changed = apply_changes(&mut passwords, store_dir, "gpg", &changes);
+        assert_eq!(changed, ["bank", "web/forum", "web/mail"]);
+        assert_eq!(pass_ids(&passwords), ["bank", "web/forum"]);
+        assert_eq!(passwords[0].metadata.len(), 12);
+    }
+
+    #[test]
+    fn moved_folders_are_read_again() {
+        let (store_dir, mut passwords) =
+            store(&["bank.gpg", "web/mail.gpg", "web/social/forum.gpg"]);
+        let store_dir = store_dir.path();
+
+        let (old, new) = (store_dir.join("web"), store_dir.join("internet"));
+        fs::rename(&old, &new).expect("folder can be moved");
+
+        let changed = apply_changes(&mut passwords, store_dir, "gpg", &[old, new]);
+        assert_eq!(
+            changed,
+            [
+                "internet/mail",
+                "internet/social/forum",
+                "web/mail",
+                "web/social/forum"
+            ]
+        );
+        assert_eq!(
+            pass_ids(&passwords),
+            ["bank", "internet/mail", "internet/social/forum"]
+        );
+    }
+
+    #[test]
+    fn changes_of_the_store_directory_read_the_whole_store_again() {
+        let (store_dir, mut passwords) = store(&["bank.gpg"]);
+        let store_dir = store_dir.path();
+        fs::remove_file(store_dir.join("bank.gpg")).expect("file can be removed");
+        write(store_dir, "web/mail.gpg", "");
+
+        let changed = apply_changes(&mut passwords, store_dir, "gpg", &[store_dir.to_owned()]);
+        assert_eq!(changed, ["bank", "web/mail"]);
+        assert_eq!(pass_ids(&passwords), ["web/mail"]);
+    }
+
+    #[test]
+    fn hidden_files_and_folders_are_ignored_like_when_the_store_is_loaded() {
+        let (store_dir, mut passwords) = store(&["bank.gpg"]);
+        let store_dir = store_dir.path();
+        let changes = [
+            write(store_dir, ".bank.gpg", ""),
+            write(store_dir, ".trash/mail.gpg", ""),
+            store_dir.join(".trash"),
+            write(store_dir, "web/.cache/forum.gpg", ""),
+            write(store_dir, "unrelated.txt", ""),
+        ];
+
+        assert!(apply_changes(&mut passwords, store_dir, "gpg", &changes).is_empty());
+        assert_eq!(pass_ids(&passwords), ["bank"]);
+        assert_eq!(
+            pass_ids(&store::password_infos(store_dir, "gpg")),
+            pass_ids(&passwords)
+        );
+    }
+}